@@ -1,13 +1,15 @@
+use std::path::Path;
 use std::sync::{Mutex, MutexGuard, OnceLock};
-use chrono::Duration;
+use chrono::{Duration, Utc};
 use rand::prelude::SliceRandom;
 use rand::Rng;
 use rusqlite::{Connection, Result};
 use crate::errors::{ArreError, ArreResult};
 use crate::item::{item_create};
-use crate::item_stats::{item_stats_get, item_stats_update};
 use crate::list::{list_create, list_items_add};
+use crate::session::{Session, session_persist};
 use crate::tag::{Tag, tag_persist};
+use crate::utils::ArreDateTime;
 
 pub struct DbConnectionWrapper(pub OnceLock<Mutex<Connection>>);
 impl DbConnectionWrapper {
@@ -31,18 +33,81 @@ impl DbConnectionWrapper {
 
 pub static DB: DbConnectionWrapper = DbConnectionWrapper::new();
 
-pub fn set_db_connection() {
-    let connection = Connection::open_in_memory().unwrap();
-    initialize_database(&connection).unwrap();
-    initialized_demo_content_dev(&connection).unwrap();
+// Bumped with every schema change, older databases are migrated in `open_database`
+pub const DB_VERSION: i64 = 11;
+
+/// On failure DB is left unset, so that views report the connection as not established
+pub fn set_db_connection(path: impl AsRef<Path>, is_demo_seeded: bool) -> ArreResult<()> {
+    let connection = open_database(path, is_demo_seeded)?;
     DB.init(connection);
+    Ok(())
+}
+
+/// Open the database file, creating the schema for a new one and seeding it with demo content if asked to.
+/// Creation and migrations run in one transaction along with the version bump, so a failing step leaves
+/// the database as it was instead of half migrated.
+pub fn open_database(path: impl AsRef<Path>, is_demo_seeded: bool) -> ArreResult<Connection> {
+    let mut connection = Connection::open(path)?;
+    let version: i64 = connection.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    if version > DB_VERSION {
+        return Err(ArreError::DatabaseVersionUnsupported(version, DB_VERSION).into());
+    }
+    let tx = connection.transaction()?;
+    if version == 0 {
+        initialize_database(&tx)?;
+        if is_demo_seeded {
+            initialized_demo_content_dev(&tx)?;
+        }
+    } else {
+        migrate_database(&tx, version)?;
+    }
+    tx.pragma_update(None, "user_version", DB_VERSION)?;
+    tx.commit()?;
+    Ok(connection)
+}
+
+/// Bring a database created at `version` up to DB_VERSION, one schema change at a time
+fn migrate_database(conn: &Connection, version: i64) -> ArreResult<()> {
+    if version < 2 {
+        initialize_session_pauses_table(conn)?;
+    }
+    if version < 3 {
+        initialize_sessions_outcome_columns(conn)?;
+    }
+    if version < 4 {
+        initialize_session_limit_columns(conn)?;
+    }
+    if version < 5 {
+        initialize_pomodoro_columns(conn)?;
+    }
+    if version < 6 {
+        initialize_abandoned_sessions_columns(conn)?;
+    }
+    if version < 7 {
+        initialize_settings_table(conn)?;
+    }
+    if version < 8 {
+        initialize_rolls_table(conn)?;
+    }
+    if version < 9 {
+        initialize_goal_columns(conn)?;
+    }
+    if version < 10 {
+        initialize_roll_odds_table(conn)?;
+    }
+    if version < 11 {
+        remove_sessions_synthetic_columns(conn)?;
+    }
+    Ok(())
 }
 
-pub fn initialize_database(conn: &Connection) -> Result<()> {
+pub fn initialize_database(conn: &Connection) -> ArreResult<()> {
     initialize_items_table(conn)?;
     initialize_items_stats_table(conn)?;
     initialize_items_details_table(conn)?;
+    initialize_items_availability_table(conn)?;
     initialize_lists_table(conn)?;
+    initialize_lists_details_table(conn)?;
     conn.execute(
         "CREATE TABLE item_list_map (
             list_id INTEGER,
@@ -54,6 +119,19 @@ pub fn initialize_database(conn: &Connection) -> Result<()> {
         (),
     )?;
     initialize_tags_table(conn)?;
+    initialize_rerolls_table(conn)?;
+    initialize_rolls_table(conn)?;
+    initialize_deck_draws_table(conn)?;
+    initialize_sessions_table(conn)?;
+    initialize_sessions_outcome_columns(conn)?;
+    initialize_session_limit_columns(conn)?;
+    initialize_active_session_table(conn)?;
+    initialize_session_pauses_table(conn)?;
+    initialize_pomodoro_columns(conn)?;
+    initialize_abandoned_sessions_columns(conn)?;
+    initialize_settings_table(conn)?;
+    initialize_goal_columns(conn)?;
+    initialize_roll_odds_table(conn)?;
     Ok(())
 }
 
@@ -102,6 +180,7 @@ pub fn initialize_items_stats_table(conn: &Connection) -> Result<()> {
             updated_date TEXT NOT NULL,
             times_worked INTEGER NOT NULL DEFAULT 0,
             time_spent INTEGER NOT NULL DEFAULT 0,
+            last_worked_date TEXT NULL,
             FOREIGN KEY(item_id) REFERENCES items(item_id) ON DELETE CASCADE
         );
         CREATE TRIGGER after_item_insert__insert_stats AFTER INSERT ON items BEGIN
@@ -118,6 +197,7 @@ pub fn initialize_items_details_table(conn: &Connection) -> Result<()> {
             item_id INTEGER PRIMARY KEY,
             updated_date TEXT NOT NULL,
             session_duration INTEGER NULL,
+            cooldown INTEGER NULL,
             FOREIGN KEY(item_id) REFERENCES items(item_id) ON DELETE CASCADE
         );
         CREATE TRIGGER after_item_insert__insert_details AFTER INSERT ON items BEGIN
@@ -128,6 +208,26 @@ pub fn initialize_items_details_table(conn: &Connection) -> Result<()> {
     )
 }
 
+pub fn initialize_items_availability_table(conn: &Connection) -> Result<()> {
+    conn.execute_batch("
+        CREATE TABLE item_availability (
+            item_id INTEGER PRIMARY KEY,
+            updated_date TEXT NOT NULL,
+            weekdays INTEGER NULL,
+            hour_start INTEGER NULL CHECK(hour_start BETWEEN 0 AND 23),
+            hour_end INTEGER NULL CHECK(hour_end BETWEEN 0 AND 24),
+            date_start TEXT NULL,
+            date_end TEXT NULL,
+            FOREIGN KEY(item_id) REFERENCES items(item_id) ON DELETE CASCADE
+        );
+        CREATE TRIGGER after_item_insert__insert_availability AFTER INSERT ON items BEGIN
+          INSERT INTO item_availability (item_id, updated_date)
+          VALUES(new.item_id, new.updated_date);
+        END;
+        "
+    )
+}
+
 pub fn initialize_lists_table(conn: &Connection) -> Result<()> {
     conn.execute_batch("
         CREATE TABLE lists (
@@ -164,6 +264,26 @@ pub fn initialize_lists_table(conn: &Connection) -> Result<()> {
     )
 }
 
+pub fn initialize_lists_details_table(conn: &Connection) -> Result<()> {
+    conn.execute_batch("
+        CREATE TABLE list_details (
+            list_id INTEGER PRIMARY KEY,
+            updated_date TEXT NOT NULL,
+            reroll_budget INTEGER NULL,
+            reroll_budget_period TEXT NOT NULL DEFAULT 'day' CHECK(reroll_budget_period IN ('day', 'week')),
+            cooldown INTEGER NULL,
+            roll_weight INTEGER NOT NULL DEFAULT 1 CHECK(roll_weight > 0),
+            is_deck_mode BOOLEAN NOT NULL DEFAULT 0 CHECK(is_deck_mode IN (0, 1)),
+            FOREIGN KEY(list_id) REFERENCES lists(list_id) ON DELETE CASCADE
+        );
+        CREATE TRIGGER after_list_insert__insert_details AFTER INSERT ON lists BEGIN
+          INSERT INTO list_details (list_id, updated_date)
+          VALUES(new.list_id, new.updated_date);
+        END;
+        "
+    )
+}
+
 pub fn initialize_tags_table(conn: &Connection) -> Result<()> {
     conn.execute_batch("
         CREATE TABLE tags (
@@ -191,6 +311,189 @@ pub fn initialize_tags_table(conn: &Connection) -> Result<()> {
     )
 }
 
+pub fn initialize_rerolls_table(conn: &Connection) -> Result<()> {
+    conn.execute_batch("
+        CREATE TABLE rerolls (
+            reroll_id INTEGER PRIMARY KEY,
+            list_id INTEGER NOT NULL,
+            created_date TEXT NOT NULL,
+            is_override BOOLEAN NOT NULL DEFAULT 0 CHECK(is_override IN (0, 1)),
+            FOREIGN KEY(list_id) REFERENCES lists(list_id) ON DELETE CASCADE
+        );
+        "
+    )
+}
+
+pub fn initialize_rolls_table(conn: &Connection) -> Result<()> {
+    conn.execute_batch("
+        CREATE TABLE rolls (
+            roll_id INTEGER PRIMARY KEY,
+            list_id INTEGER NULL,
+            item_id INTEGER NOT NULL,
+            created_date TEXT NOT NULL,
+            FOREIGN KEY(list_id) REFERENCES lists(list_id) ON DELETE CASCADE,
+            FOREIGN KEY(item_id) REFERENCES items(item_id) ON DELETE CASCADE
+        );
+        CREATE INDEX rolls_list_id_index ON rolls(list_id);
+        "
+    )
+}
+
+/// Probability every candidate had to be drawn by a roll, rolls recorded before it have none
+pub fn initialize_roll_odds_table(conn: &Connection) -> Result<()> {
+    conn.execute_batch("
+        CREATE TABLE roll_odds (
+            roll_id INTEGER NOT NULL,
+            item_id INTEGER NOT NULL,
+            probability REAL NOT NULL CHECK(probability > 0 AND probability <= 1),
+            PRIMARY KEY(roll_id, item_id),
+            FOREIGN KEY(roll_id) REFERENCES rolls(roll_id) ON DELETE CASCADE,
+            FOREIGN KEY(item_id) REFERENCES items(item_id) ON DELETE CASCADE
+        );
+        "
+    )
+}
+
+pub fn initialize_deck_draws_table(conn: &Connection) -> Result<()> {
+    conn.execute_batch("
+        CREATE TABLE deck_draws (
+            list_id INTEGER NOT NULL,
+            item_id INTEGER NOT NULL,
+            PRIMARY KEY(list_id, item_id),
+            FOREIGN KEY(list_id, item_id) REFERENCES item_list_map(list_id, item_id) ON DELETE CASCADE
+        );
+        "
+    )
+}
+
+pub fn initialize_sessions_table(conn: &Connection) -> Result<()> {
+    conn.execute_batch("
+        CREATE TABLE sessions (
+            session_id INTEGER PRIMARY KEY,
+            item_id INTEGER NOT NULL,
+            list_id INTEGER NULL,
+            start_date TEXT NOT NULL,
+            end_date TEXT NOT NULL,
+            duration INTEGER NOT NULL CHECK(duration >= 0),
+            FOREIGN KEY(item_id) REFERENCES items(item_id) ON DELETE CASCADE,
+            FOREIGN KEY(list_id) REFERENCES lists(list_id) ON DELETE SET NULL
+        );
+        CREATE INDEX sessions_item_id_index ON sessions(item_id);
+        "
+    )
+}
+
+/// Sessions used to also stand for totals recorded before they existed, none were ever created
+pub fn remove_sessions_synthetic_columns(conn: &Connection) -> Result<()> {
+    conn.execute_batch("
+        ALTER TABLE sessions DROP COLUMN sessions_count;
+        ALTER TABLE sessions DROP COLUMN is_synthetic;
+        "
+    )
+}
+
+pub fn initialize_sessions_outcome_columns(conn: &Connection) -> Result<()> {
+    conn.execute_batch("
+        ALTER TABLE sessions ADD COLUMN notes TEXT NULL;
+        ALTER TABLE sessions ADD COLUMN satisfaction INTEGER NULL CHECK(satisfaction BETWEEN 1 AND 5);
+        ALTER TABLE sessions ADD COLUMN difficulty INTEGER NULL CHECK(difficulty BETWEEN 1 AND 5);
+        ALTER TABLE sessions ADD COLUMN progress INTEGER NULL CHECK(progress BETWEEN 0 AND 100);
+        ALTER TABLE sessions ADD COLUMN is_item_finished BOOLEAN NOT NULL DEFAULT 0 CHECK(is_item_finished IN (0, 1));
+        "
+    )
+}
+
+pub fn initialize_session_limit_columns(conn: &Connection) -> Result<()> {
+    conn.execute_batch("
+        ALTER TABLE item_details ADD COLUMN is_auto_finish BOOLEAN NOT NULL DEFAULT 0 CHECK(is_auto_finish IN (0, 1));
+        ALTER TABLE sessions ADD COLUMN planned_duration INTEGER NULL CHECK(planned_duration >= 0);
+        ALTER TABLE sessions ADD COLUMN overtime INTEGER NOT NULL DEFAULT 0 CHECK(overtime >= 0);
+        "
+    )
+}
+
+pub fn initialize_pomodoro_columns(conn: &Connection) -> Result<()> {
+    conn.execute_batch("
+        ALTER TABLE item_details ADD COLUMN pomodoro_work INTEGER NULL CHECK(pomodoro_work > 0);
+        ALTER TABLE item_details ADD COLUMN pomodoro_short_break INTEGER NULL CHECK(pomodoro_short_break >= 0);
+        ALTER TABLE item_details ADD COLUMN pomodoro_long_break INTEGER NULL CHECK(pomodoro_long_break >= 0);
+        ALTER TABLE item_details ADD COLUMN pomodoro_long_break_every INTEGER NULL CHECK(pomodoro_long_break_every >= 0);
+        ALTER TABLE list_details ADD COLUMN pomodoro_work INTEGER NULL CHECK(pomodoro_work > 0);
+        ALTER TABLE list_details ADD COLUMN pomodoro_short_break INTEGER NULL CHECK(pomodoro_short_break >= 0);
+        ALTER TABLE list_details ADD COLUMN pomodoro_long_break INTEGER NULL CHECK(pomodoro_long_break >= 0);
+        ALTER TABLE list_details ADD COLUMN pomodoro_long_break_every INTEGER NULL CHECK(pomodoro_long_break_every >= 0);
+        ALTER TABLE active_session ADD COLUMN pomodoros INTEGER NOT NULL DEFAULT 0 CHECK(pomodoros >= 0);
+        ALTER TABLE sessions ADD COLUMN pomodoros INTEGER NOT NULL DEFAULT 0 CHECK(pomodoros >= 0);
+        "
+    )
+}
+
+pub fn initialize_abandoned_sessions_columns(conn: &Connection) -> Result<()> {
+    conn.execute_batch("
+        ALTER TABLE sessions ADD COLUMN is_abandoned BOOLEAN NOT NULL DEFAULT 0 CHECK(is_abandoned IN (0, 1));
+        ALTER TABLE sessions ADD COLUMN abandon_reason TEXT NULL;
+        ALTER TABLE item_stats ADD COLUMN times_skipped INTEGER NOT NULL DEFAULT 0;
+        "
+    )
+}
+
+pub fn initialize_goal_columns(conn: &Connection) -> Result<()> {
+    conn.execute_batch("
+        ALTER TABLE item_details ADD COLUMN goal_target INTEGER NULL CHECK(goal_target > 0);
+        ALTER TABLE item_details ADD COLUMN goal_kind TEXT NOT NULL DEFAULT 'time' CHECK(goal_kind IN ('time', 'sessions'));
+        ALTER TABLE item_details ADD COLUMN goal_period TEXT NOT NULL DEFAULT 'week' CHECK(goal_period IN ('week', 'month'));
+        ALTER TABLE item_details ADD COLUMN is_goal_boost BOOLEAN NOT NULL DEFAULT 0 CHECK(is_goal_boost IN (0, 1));
+        ALTER TABLE list_details ADD COLUMN goal_target INTEGER NULL CHECK(goal_target > 0);
+        ALTER TABLE list_details ADD COLUMN goal_kind TEXT NOT NULL DEFAULT 'time' CHECK(goal_kind IN ('time', 'sessions'));
+        ALTER TABLE list_details ADD COLUMN goal_period TEXT NOT NULL DEFAULT 'week' CHECK(goal_period IN ('week', 'month'));
+        ALTER TABLE list_details ADD COLUMN is_goal_boost BOOLEAN NOT NULL DEFAULT 0 CHECK(is_goal_boost IN (0, 1));
+        "
+    )
+}
+
+pub fn initialize_settings_table(conn: &Connection) -> Result<()> {
+    conn.execute_batch("
+        CREATE TABLE settings (
+            settings_id INTEGER PRIMARY KEY CHECK(settings_id = 1),
+            updated_date TEXT NOT NULL,
+            idle_timeout INTEGER NULL DEFAULT 3600 CHECK(idle_timeout > 0)
+        );
+        "
+    )?;
+    conn.execute("INSERT INTO settings (settings_id, updated_date) VALUES (1, ?1)", (ArreDateTime::now(),))?;
+    Ok(())
+}
+
+pub fn initialize_active_session_table(conn: &Connection) -> Result<()> {
+    conn.execute_batch("
+        CREATE TABLE active_session (
+            active_session_id INTEGER PRIMARY KEY CHECK(active_session_id = 1),
+            item_id INTEGER NOT NULL,
+            list_id INTEGER NULL,
+            start_date TEXT NOT NULL,
+            FOREIGN KEY(item_id) REFERENCES items(item_id) ON DELETE CASCADE,
+            FOREIGN KEY(list_id) REFERENCES lists(list_id) ON DELETE SET NULL
+        );
+        "
+    )
+}
+
+pub fn initialize_session_pauses_table(conn: &Connection) -> Result<()> {
+    conn.execute_batch("
+        CREATE TABLE session_pauses (
+            session_pause_id INTEGER PRIMARY KEY,
+            session_id INTEGER NULL,
+            start_date TEXT NOT NULL,
+            end_date TEXT NULL,
+            FOREIGN KEY(session_id) REFERENCES sessions(session_id) ON DELETE CASCADE
+        );
+        CREATE TRIGGER after_active_session_delete__delete_pauses AFTER DELETE ON active_session BEGIN
+          DELETE FROM session_pauses WHERE session_id IS NULL;
+        END;
+        "
+    )
+}
+
 pub fn initialized_demo_content_dev(c: &Connection) -> ArreResult<()> {
     let items = [
         item_create(&c, "Empower Elves", "Remember, an elf's power is directly proportional to the shininess of their shoes.")?,
@@ -226,11 +529,13 @@ pub fn initialized_demo_content_dev(c: &Connection) -> ArreResult<()> {
         item_create(&c, "Nurture Nymphs", "Nature's caretakers, and the best gardeners around.")?,
     ];
     let mut rng = rand::thread_rng();
+    let now = Utc::now();
     for (idx, item) in items.iter().enumerate() {
-        let mut stats = item_stats_get(&c, item.get_id()?)?;
-        stats.time_spent = Duration::hours(idx as i64) + Duration::seconds(rng.gen_range(0..3600));
-        stats.times_worked = idx;
-        item_stats_update(&c, &stats)?
+        for _ in 0..idx {
+            let end = now - Duration::days(rng.gen_range(1..60)) - Duration::seconds(rng.gen_range(0..86400));
+            let start = end - Duration::minutes(rng.gen_range(10..120));
+            session_persist(&c, &mut Session::new(item.get_id()?, None, start, end))?;
+        }
     }
 
     let lists = [
@@ -255,4 +560,139 @@ pub fn initialized_demo_content_dev(c: &Connection) -> ArreResult<()> {
         tag_persist(&c, &mut Tag::new("Important".to_string(), "#f1e507".to_string()))?,
     ];
     Ok(())
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use rstest::*;
+    use crate::item::item_get_all;
+    use super::*;
+
+    fn temp_database_path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("arre_test_{}_{}.db", name, std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    #[rstest]
+    fn database_file_is_reopened() -> ArreResult<()> {
+        let path = temp_database_path("reopened");
+        let items_nb = {
+            let connection = open_database(&path, false)?;
+            item_create(&connection, "Kept", "Across restarts")?;
+            item_get_all::<Vec<_>>(&connection)?.len()
+        };
+        let connection = open_database(&path, false)?;
+        assert_eq!(item_get_all::<Vec<_>>(&connection)?.len(), items_nb, "Reopening should neither lose nor reseed items");
+
+        connection.pragma_update(None, "user_version", DB_VERSION + 1)?;
+        drop(connection);
+        assert!(open_database(&path, false).is_err(), "Newer database versions should be refused");
+        std::fs::remove_file(&path)?;
+        Ok(())
+    }
+
+    #[rstest]
+    fn demo_content_is_only_seeded_when_asked() -> ArreResult<()> {
+        let path = temp_database_path("unseeded");
+        let connection = open_database(&path, false)?;
+        assert!(item_get_all::<Vec<_>>(&connection)?.is_empty());
+        drop(connection);
+        std::fs::remove_file(&path)?;
+        Ok(())
+    }
+
+    #[rstest]
+    fn failed_migration_is_rolled_back() -> ArreResult<()> {
+        let path = temp_database_path("rolled_back");
+        let connection = open_database(&path, false)?;
+        // Version 7 without roll history, but with goal columns already there so that adding them fails
+        connection.execute_batch("
+            DROP TABLE roll_odds;
+            DROP TABLE rolls;
+            PRAGMA user_version = 7;
+        ")?;
+        drop(connection);
+
+        assert!(open_database(&path, false).is_err());
+        let connection = Connection::open(&path)?;
+        let version: i64 = connection.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        assert_eq!(version, 7, "The version should not be bumped");
+        let rolls_tables = connection.query_row("
+            SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = 'rolls'
+        ", [], |row| row.get::<_, i64>(0))?;
+        assert_eq!(rolls_tables, 0, "Steps before the failing one should be rolled back");
+        drop(connection);
+        std::fs::remove_file(&path)?;
+        Ok(())
+    }
+
+    #[rstest]
+    fn database_is_migrated_from_version_1() -> ArreResult<()> {
+        let path = temp_database_path("migrated");
+        let connection = open_database(&path, true)?;
+        // Schema of the first version, before settings, roll history, goals and before sessions could be paused, abandoned, have an outcome, a limit or pomodoros,
+        // when sessions could still be synthetic
+        connection.execute_batch("
+            DROP TRIGGER after_active_session_delete__delete_pauses;
+            DROP TABLE session_pauses;
+            ALTER TABLE sessions DROP COLUMN notes;
+            ALTER TABLE sessions DROP COLUMN satisfaction;
+            ALTER TABLE sessions DROP COLUMN difficulty;
+            ALTER TABLE sessions DROP COLUMN progress;
+            ALTER TABLE sessions DROP COLUMN is_item_finished;
+            ALTER TABLE sessions DROP COLUMN planned_duration;
+            ALTER TABLE sessions DROP COLUMN overtime;
+            ALTER TABLE item_details DROP COLUMN is_auto_finish;
+            ALTER TABLE item_details DROP COLUMN pomodoro_work;
+            ALTER TABLE item_details DROP COLUMN pomodoro_short_break;
+            ALTER TABLE item_details DROP COLUMN pomodoro_long_break;
+            ALTER TABLE item_details DROP COLUMN pomodoro_long_break_every;
+            ALTER TABLE list_details DROP COLUMN pomodoro_work;
+            ALTER TABLE list_details DROP COLUMN pomodoro_short_break;
+            ALTER TABLE list_details DROP COLUMN pomodoro_long_break;
+            ALTER TABLE list_details DROP COLUMN pomodoro_long_break_every;
+            ALTER TABLE active_session DROP COLUMN pomodoros;
+            ALTER TABLE sessions DROP COLUMN pomodoros;
+            ALTER TABLE sessions DROP COLUMN is_abandoned;
+            ALTER TABLE sessions DROP COLUMN abandon_reason;
+            ALTER TABLE item_stats DROP COLUMN times_skipped;
+            DROP TABLE settings;
+            DROP TABLE roll_odds;
+            DROP TABLE rolls;
+            ALTER TABLE item_details DROP COLUMN goal_target;
+            ALTER TABLE item_details DROP COLUMN goal_kind;
+            ALTER TABLE item_details DROP COLUMN goal_period;
+            ALTER TABLE item_details DROP COLUMN is_goal_boost;
+            ALTER TABLE list_details DROP COLUMN goal_target;
+            ALTER TABLE list_details DROP COLUMN goal_kind;
+            ALTER TABLE list_details DROP COLUMN goal_period;
+            ALTER TABLE list_details DROP COLUMN is_goal_boost;
+            ALTER TABLE sessions ADD COLUMN sessions_count INTEGER NOT NULL DEFAULT 1 CHECK(sessions_count > 0);
+            ALTER TABLE sessions ADD COLUMN is_synthetic BOOLEAN NOT NULL DEFAULT 0 CHECK(is_synthetic IN (0, 1));
+            PRAGMA user_version = 1;
+        ")?;
+        drop(connection);
+
+        let connection = open_database(&path, false)?;
+        let version: i64 = connection.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        assert_eq!(version, DB_VERSION);
+        assert_eq!(connection.query_row("SELECT COUNT(*) FROM session_pauses", [], |row| row.get::<_, i64>(0))?, 0);
+        let sessions_without_outcome = connection.query_row("
+            SELECT COUNT(*) FROM sessions WHERE notes IS NULL AND is_item_finished = 0
+        ", [], |row| row.get::<_, i64>(0))?;
+        assert!(sessions_without_outcome > 0, "Sessions recorded before the migration should be kept without an outcome");
+        let sessions_with_overtime = connection.query_row("
+            SELECT COUNT(*) FROM sessions WHERE overtime > 0 OR planned_duration IS NOT NULL
+        ", [], |row| row.get::<_, i64>(0))?;
+        assert_eq!(sessions_with_overtime, 0, "Sessions recorded before the migration should have no overtime");
+        let synthetic_columns = connection.query_row("
+            SELECT COUNT(*) FROM pragma_table_info('sessions') WHERE name IN ('sessions_count', 'is_synthetic')
+        ", [], |row| row.get::<_, i64>(0))?;
+        assert_eq!(synthetic_columns, 0);
+        drop(connection);
+        std::fs::remove_file(&path)?;
+        Ok(())
+    }
+}
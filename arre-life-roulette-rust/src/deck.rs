@@ -0,0 +1,181 @@
+use std::borrow::Borrow;
+use std::collections::HashSet;
+use rusqlite::{Connection, Result};
+use crate::errors::ArreResult;
+use crate::item::ItemId;
+use crate::list::ListId;
+use crate::roll::roll_insert;
+
+/// Items of the list already drawn since the deck was last reshuffled
+pub fn deck_drawn_get<C>(conn: &Connection, list_id: ListId) -> Result<C>
+where C: FromIterator<ItemId>
+{
+    let mut stmt = conn.prepare("
+        SELECT item_id
+        FROM deck_draws
+        WHERE list_id = ?1
+    ")?;
+    let results = stmt.query_map([list_id], |row| {
+        Ok(ItemId::new(row.get(0)?))
+    })?;
+    results.collect::<Result<C>>()
+}
+
+/// Take the items out of the deck until it is reshuffled
+pub fn deck_draws_record(
+    conn: &Connection,
+    list_id: ListId,
+    items: impl IntoIterator<Item=impl Borrow<ItemId>>
+) -> ArreResult<()> {
+    let mut stmt = conn.prepare("INSERT OR IGNORE INTO deck_draws (list_id, item_id) VALUES (?1, ?2)")?;
+    for item_id in items {
+        stmt.execute([*list_id, **item_id.borrow()])?;
+    }
+    Ok(())
+}
+
+/// Put all items of the list back into the deck
+pub fn deck_reshuffle(conn: &Connection, list_id: ListId) -> ArreResult<()> {
+    conn.execute("DELETE FROM deck_draws WHERE list_id = ?1", [list_id])?;
+    Ok(())
+}
+
+/// Items left out of a roll among the `rollable` items of the list, as they were already drawn from the deck.
+/// None are left out when the deck is due to be reshuffled, once every card was drawn, but also when none
+/// of the cards left can be rolled, e.g. because they are cooling down or unavailable, so that the list cannot get stuck.
+/// The deck itself is only reshuffled by `deck_roll_record`.
+pub fn deck_drawn_before_roll(conn: &Connection, list_id: ListId, rollable: &HashSet<ItemId>) -> ArreResult<HashSet<ItemId>> {
+    let drawn = deck_drawn_get::<HashSet<_>>(conn, list_id)?;
+    let is_stuck = !rollable.is_empty() && rollable.is_subset(&drawn);
+    if is_stuck || deck_remaining_count(conn, list_id)? == 0 {
+        return Ok(HashSet::new());
+    }
+    Ok(drawn)
+}
+
+/// Record a roll of the list in deck mode, reshuffling the deck first if it is due, then taking the drawn cards out of it.
+/// The reshuffle, the roll and the draws are recorded in one transaction.
+pub fn deck_roll_record(
+    conn: &Connection,
+    list_id: ListId,
+    candidates: &[(ItemId, u32)],
+    drawn: &[ItemId],
+) -> ArreResult<()> {
+    let tx = conn.unchecked_transaction()?;
+    let rollable = candidates.iter().map(|(item_id, _)| *item_id).collect();
+    if deck_drawn_before_roll(&tx, list_id, &rollable)?.is_empty() {
+        deck_reshuffle(&tx, list_id)?;
+    }
+    roll_insert(&tx, Some(list_id), candidates, drawn)?;
+    deck_draws_record(&tx, list_id, drawn)?;
+    tx.commit()?;
+    Ok(())
+}
+
+/// Number of list items not drawn yet
+pub fn deck_remaining_count(conn: &Connection, list_id: ListId) -> ArreResult<usize> {
+    let mut stmt = conn.prepare("
+        SELECT COUNT(*)
+        FROM item_list_map ilm
+        LEFT JOIN deck_draws dd ON ilm.list_id = dd.list_id AND ilm.item_id = dd.item_id
+        WHERE ilm.list_id = ?1 AND dd.item_id IS NULL
+    ")?;
+    Ok(stmt.query_row([list_id], |row| row.get(0))?)
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+    use rusqlite::Connection;
+    use crate::item::items_to_ids;
+    use crate::list::{list_items_add, list_items_delete};
+    use crate::test_fixtures::{conn, TestFactory};
+    use super::*;
+
+    #[rstest]
+    fn draws_empty_the_deck_until_reshuffle(conn: Connection) -> ArreResult<()> {
+        let mut tf = TestFactory::new(&conn);
+        let lists = tf.create_lists(2)?;
+        let (list_id, other_list_id) = (lists[0].get_id()?, lists[1].get_id()?);
+        let item_ids = items_to_ids::<_, Vec<_>>(tf.create_items(3)?.iter())?;
+        list_items_add(&conn, list_id, item_ids.iter())?;
+        list_items_add(&conn, other_list_id, item_ids.iter())?;
+        assert_eq!(deck_remaining_count(&conn, list_id)?, 3);
+
+        deck_draws_record(&conn, list_id, item_ids[0..2].iter())?;
+        // Drawing the same item twice does not fail
+        deck_draws_record(&conn, list_id, item_ids[0..1].iter())?;
+        assert_eq!(deck_remaining_count(&conn, list_id)?, 1);
+        assert_eq!(deck_drawn_get::<HashSet<_>>(&conn, list_id)?, item_ids[0..2].iter().copied().collect());
+        // Decks are independent
+        assert_eq!(deck_remaining_count(&conn, other_list_id)?, 3);
+
+        deck_reshuffle(&conn, list_id)?;
+        assert_eq!(deck_remaining_count(&conn, list_id)?, 3);
+        assert!(deck_drawn_get::<Vec<_>>(&conn, list_id)?.is_empty());
+        Ok(())
+    }
+
+    #[rstest]
+    fn deck_is_reshuffled_when_no_card_left_can_be_rolled(conn: Connection) -> ArreResult<()> {
+        let mut tf = TestFactory::new(&conn);
+        let list_id = tf.create_lists(1)?[0].get_id()?;
+        let item_ids = items_to_ids::<_, Vec<_>>(tf.create_items(3)?.iter())?;
+        list_items_add(&conn, list_id, item_ids.iter())?;
+        deck_draws_record(&conn, list_id, item_ids[0..2].iter())?;
+
+        // The card left can be rolled
+        let drawn = deck_drawn_before_roll(&conn, list_id, &item_ids.iter().copied().collect())?;
+        assert_eq!(drawn, item_ids[0..2].iter().copied().collect());
+        // Nothing can be rolled at all, reshuffling would not help
+        let drawn = deck_drawn_before_roll(&conn, list_id, &HashSet::new())?;
+        assert_eq!(drawn.len(), 2);
+        // The card left is e.g. cooling down
+        let drawn = deck_drawn_before_roll(&conn, list_id, &item_ids[0..2].iter().copied().collect())?;
+        assert!(drawn.is_empty());
+        assert_eq!(deck_remaining_count(&conn, list_id)?, 1, "Only rolling should reshuffle the deck");
+
+        // Once every card was drawn
+        deck_draws_record(&conn, list_id, item_ids[2..3].iter())?;
+        assert!(deck_drawn_before_roll(&conn, list_id, &HashSet::new())?.is_empty());
+        assert_eq!(deck_remaining_count(&conn, list_id)?, 0);
+        Ok(())
+    }
+
+    #[rstest]
+    fn rolling_the_deck_reshuffles_it_when_due(conn: Connection) -> ArreResult<()> {
+        let mut tf = TestFactory::new(&conn);
+        let list_id = tf.create_lists(1)?[0].get_id()?;
+        let item_ids = items_to_ids::<_, Vec<_>>(tf.create_items(3)?.iter())?;
+        list_items_add(&conn, list_id, item_ids.iter())?;
+
+        let candidates = item_ids.iter().map(|item_id| (*item_id, 1)).collect::<Vec<_>>();
+        deck_roll_record(&conn, list_id, &candidates, &item_ids[0..2])?;
+        tf.assert_table_count("rolls", 2)?;
+        assert_eq!(deck_remaining_count(&conn, list_id)?, 1);
+
+        // The card left is e.g. cooling down, so the drawn ones are rolled again
+        deck_roll_record(&conn, list_id, &candidates[0..2], &item_ids[0..1])?;
+        tf.assert_table_count("rolls", 3)?;
+        assert_eq!(deck_drawn_get::<Vec<_>>(&conn, list_id)?, vec![item_ids[0]]);
+
+        // A failing roll leaves the deck as it was
+        assert!(deck_roll_record(&conn, list_id, &[(ItemId::new(9999), 1)], &[ItemId::new(9999)]).is_err());
+        assert_eq!(deck_drawn_get::<Vec<_>>(&conn, list_id)?, vec![item_ids[0]]);
+        tf.assert_table_count("rolls", 3)?;
+        Ok(())
+    }
+
+    #[rstest]
+    fn removing_item_from_list_removes_its_draw(conn: Connection) -> ArreResult<()> {
+        let mut tf = TestFactory::new(&conn);
+        let list_id = tf.create_lists(1)?[0].get_id()?;
+        let item_ids = items_to_ids::<_, Vec<_>>(tf.create_items(2)?.iter())?;
+        list_items_add(&conn, list_id, item_ids.iter())?;
+        deck_draws_record(&conn, list_id, item_ids.iter())?;
+
+        list_items_delete(&conn, list_id, item_ids[0..1].iter().copied())?;
+        tf.assert_table_count("deck_draws", 1)?;
+        Ok(())
+    }
+}
@@ -20,6 +20,18 @@ pub enum ArreError {
     ItemsSelectionIsEmpty(),
     #[error("[color=red]Owned bus cannot be cloned[/color]")]
     OwnedBusCannotBeCloned(),
+    #[error("[color=red]No rerolls left for this period[/color]")]
+    RerollBudgetExhausted(),
+    #[error("[color=red]Invalid date [b]`{0}`[/b], expected YYYY-MM-DD[/color]")]
+    InvalidDate(String),
+    #[error("[color=red]Invalid date and time [b]`{0}`[/b], expected YYYY-MM-DD HH:MM[/color]")]
+    InvalidDateTime(String),
+    #[error("[color=red]No work session in progress[/color]")]
+    NoActiveSession(),
+    #[error("[color=red]Session overlaps another one, from [b]`{0}`[/b] to [b]`{1}`[/b][/color]")]
+    SessionOverlap(String, String),
+    #[error("[color=red]Session must end after it starts and last at most as long[/color]")]
+    SessionTimesInvalid(),
     // Core errors
     // TODO: Lists and Tags are also using this error, so maybe rename it
     #[error("[color=red] Attempt to operate on non persisted item [/color]")]
@@ -28,4 +40,6 @@ pub enum ArreError {
     DatabaseConnectionNotEstablished(),
     #[error("[color=red]Database connection mutex lock failed[/color]")]
     DatabaseConnectionMutexFailed(),
+    #[error("[color=red]Database version [b]`{0}`[/b] is newer than supported [b]`{1}`[/b][/color]")]
+    DatabaseVersionUnsupported(i64, i64),
 }
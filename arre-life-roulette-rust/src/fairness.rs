@@ -0,0 +1,257 @@
+use rusqlite::{Connection, Result, Row};
+use crate::errors::ArreResult;
+use crate::item::ItemId;
+use crate::list::ListId;
+
+/// Below this many expected draws for an item, the chi-square approximation is not reliable
+pub const MIN_EXPECTED_DRAWS: f64 = 5.0;
+
+const GAMMA_EPSILON: f64 = 1e-12;
+const GAMMA_MAX_ITERATIONS: usize = 500;
+
+/// Compare how often the items were drawn by the rolls made on the list alone with the odds they had when rolled.
+/// Rolls recorded before their odds were are left out of the comparison.
+pub fn roll_fairness_get(conn: &Connection, list_id: ListId) -> ArreResult<FairnessReport> {
+    let mut stmt = conn.prepare("
+        SELECT
+         i.item_id, i.name,
+         (SELECT COUNT(*) FROM rolls dr WHERE dr.list_id = ?1 AND dr.item_id = i.item_id
+           AND EXISTS (SELECT 1 FROM roll_odds do WHERE do.roll_id = dr.roll_id)),
+         SUM(o.probability)
+        FROM roll_odds o
+        JOIN rolls r ON o.roll_id = r.roll_id
+        JOIN items i ON o.item_id = i.item_id
+        WHERE r.list_id = ?1
+        GROUP BY i.item_id
+    ")?;
+    let items = stmt.query_map([list_id], |row| {
+        ItemFairness::from_row(row)
+    })?.collect::<Result<Vec<_>>>()?;
+    let mut stmt = conn.prepare("
+        SELECT COUNT(*)
+        FROM rolls r
+        WHERE r.list_id = ?1 AND NOT EXISTS (SELECT 1 FROM roll_odds o WHERE o.roll_id = r.roll_id)
+    ")?;
+    let unaudited_draws_count = stmt.query_row([list_id], |row| row.get(0))?;
+    Ok(FairnessReport::new(Some(list_id), items, unaudited_draws_count))
+}
+
+/// Pearson's chi-square goodness of fit of the observed counts to the expected ones.
+/// Categories nothing was expected in are left out, as they cannot be drawn.
+pub fn chi_square(counts: impl IntoIterator<Item=(usize, f64)>) -> ChiSquare {
+    let (statistic, categories) = counts
+        .into_iter()
+        .filter(|(_, expected)| *expected > 0.0)
+        .fold((0.0, 0), |(statistic, categories), (observed, expected)| {
+            (statistic + (observed as f64 - expected).powi(2) / expected, categories + 1)
+        });
+    let degrees_of_freedom = categories.max(1) - 1;
+    ChiSquare { statistic, degrees_of_freedom, p_value: chi_square_p_value(statistic, degrees_of_freedom) }
+}
+
+/// Probability of a chi-square statistic at least as high as `statistic` if the draws were fair
+pub fn chi_square_p_value(statistic: f64, degrees_of_freedom: usize) -> f64 {
+    if degrees_of_freedom == 0 || statistic <= 0.0 {
+        return 1.0;
+    }
+    regularized_upper_gamma(degrees_of_freedom as f64 / 2.0, statistic / 2.0)
+}
+
+/// Q(a, x), by its series below a + 1 and its continued fraction above, where each converges quickly
+fn regularized_upper_gamma(a: f64, x: f64) -> f64 {
+    let prefactor = (-x + a * x.ln() - ln_gamma(a)).exp();
+    if x < a + 1.0 {
+        let mut term = 1.0 / a;
+        let mut sum = term;
+        for n in 1..GAMMA_MAX_ITERATIONS {
+            term *= x / (a + n as f64);
+            sum += term;
+            if term.abs() < sum.abs() * GAMMA_EPSILON {
+                break;
+            }
+        }
+        (1.0 - sum * prefactor).clamp(0.0, 1.0)
+    } else {
+        // Modified Lentz's method
+        let tiny = f64::MIN_POSITIVE / GAMMA_EPSILON;
+        let mut b = x + 1.0 - a;
+        let mut c = 1.0 / tiny;
+        let mut d = 1.0 / b;
+        let mut fraction = d;
+        for n in 1..GAMMA_MAX_ITERATIONS {
+            let an = -(n as f64) * (n as f64 - a);
+            b += 2.0;
+            d = an * d + b;
+            if d.abs() < tiny { d = tiny; }
+            c = b + an / c;
+            if c.abs() < tiny { c = tiny; }
+            d = 1.0 / d;
+            let delta = d * c;
+            fraction *= delta;
+            if (delta - 1.0).abs() < GAMMA_EPSILON {
+                break;
+            }
+        }
+        (prefactor * fraction).clamp(0.0, 1.0)
+    }
+}
+
+/// Lanczos approximation of ln(Γ(x)), for x of at least 1/2
+fn ln_gamma(x: f64) -> f64 {
+    const COEFFICIENTS: [f64; 9] = [
+        0.999_999_999_999_809_9,
+        676.520_368_121_885_1,
+        -1_259.139_216_722_402_8,
+        771.323_428_777_653_1,
+        -176.615_029_162_140_6,
+        12.507_343_278_686_905,
+        -0.138_571_095_265_720_12,
+        9.984_369_578_019_572e-6,
+        1.505_632_735_149_311_6e-7,
+    ];
+    let x = x - 1.0;
+    let t = x + 7.5;
+    let series = COEFFICIENTS[1..]
+        .iter()
+        .enumerate()
+        .fold(COEFFICIENTS[0], |series, (i, coefficient)| series + coefficient / (x + i as f64 + 1.0));
+    0.5 * (2.0 * std::f64::consts::PI).ln() + (x + 0.5) * t.ln() - t + series.ln()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChiSquare {
+    pub statistic: f64,
+    pub degrees_of_freedom: usize,
+    pub p_value: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct FairnessReport {
+    pub list_id: Option<ListId>, // None if the report is not about the rolls of a list
+    pub items: Vec<ItemFairness>, // most drawn first
+    pub draws_count: usize,
+    pub unaudited_draws_count: usize, // draws recorded without their odds
+    pub chi_square: ChiSquare,
+}
+
+impl FairnessReport {
+    pub fn new(list_id: Option<ListId>, mut items: Vec<ItemFairness>, unaudited_draws_count: usize) -> FairnessReport {
+        items.sort_by(|a, b| b.observed.cmp(&a.observed).then(b.expected.total_cmp(&a.expected)));
+        let chi_square = chi_square(items.iter().map(|item| (item.observed, item.expected)));
+        FairnessReport {
+            list_id,
+            draws_count: items.iter().map(|item| item.observed).sum(),
+            items,
+            unaudited_draws_count,
+            chi_square,
+        }
+    }
+
+    /// Enough draws were expected for every item to trust the chi-square statistic
+    pub fn is_conclusive(&self) -> bool {
+        !self.items.is_empty() && self.items.iter().all(|item| item.expected >= MIN_EXPECTED_DRAWS)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ItemFairness {
+    pub item_id: ItemId,
+    pub item_name: String,
+    pub observed: usize, // times drawn
+    pub expected: f64, // sum of its odds over the draws it was a candidate of
+}
+
+impl ItemFairness {
+    /// Expects the item id and name, the times it was drawn and the sum of its odds
+    pub fn from_row(row: &Row) -> Result<ItemFairness> {
+        Ok(ItemFairness {
+            item_id: row.get(0)?,
+            item_name: row.get(1)?,
+            observed: row.get(2)?,
+            expected: row.get(3)?,
+        })
+    }
+
+    /// Share of all the draws that went to the item
+    pub fn observed_frequency(&self, draws_count: usize) -> f64 {
+        if draws_count == 0 { 0.0 } else { self.observed as f64 / draws_count as f64 }
+    }
+
+    /// Share of all the draws the item was expected to get, its average odds
+    pub fn expected_probability(&self, draws_count: usize) -> f64 {
+        if draws_count == 0 { 0.0 } else { self.expected / draws_count as f64 }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+    use rusqlite::Connection;
+    use crate::item::items_to_ids;
+    use crate::roll::roll_record;
+    use crate::test_fixtures::{conn, TestFactory};
+    use super::*;
+
+    #[rstest]
+    #[case::one_degree(3.841_459, 1, 0.05)]
+    #[case::five_degrees(11.070_498, 5, 0.05)]
+    #[case::two_degrees(2.0, 2, (-1.0f64).exp())]
+    #[case::small_statistic(0.5, 10, 0.999_999_7)]
+    #[case::large_statistic(100.0, 3, 1.554_159e-21)]
+    #[case::no_statistic(0.0, 3, 1.0)]
+    #[case::no_freedom(12.0, 0, 1.0)]
+    fn p_values_match_the_chi_square_distribution(
+        #[case] statistic: f64,
+        #[case] degrees_of_freedom: usize,
+        #[case] p_value: f64,
+    ) {
+        let computed = chi_square_p_value(statistic, degrees_of_freedom);
+        assert!((computed - p_value).abs() <= p_value * 1e-3, "Got {} instead of {}", computed, p_value);
+    }
+
+    #[rstest]
+    fn chi_square_compares_observed_and_expected_counts() {
+        let fit = chi_square([(25, 25.0), (75, 75.0)]);
+        assert_eq!(fit.statistic, 0.0);
+        assert_eq!(fit.degrees_of_freedom, 1);
+        assert_eq!(fit.p_value, 1.0);
+        // Nothing expected, so not a possible outcome
+        let skewed = chi_square([(50, 25.0), (50, 75.0), (0, 0.0)]);
+        assert!((skewed.statistic - (25.0 + 25.0 / 3.0)).abs() < 1e-9);
+        assert_eq!(skewed.degrees_of_freedom, 1);
+        assert!(skewed.p_value < 1e-6);
+    }
+
+    #[rstest]
+    fn fairness_compares_draws_with_their_odds(conn: Connection) -> ArreResult<()> {
+        let mut tf = TestFactory::new(&conn);
+        let list_id = tf.create_lists(1)?[0].get_id()?;
+        let item_ids = items_to_ids::<_, Vec<_>>(tf.create_items(3)?.iter())?;
+        let candidates = vec![(item_ids[0], 1), (item_ids[1], 3)];
+        for _ in 0..3 {
+            roll_record(&conn, Some(list_id), &candidates, &[item_ids[1]])?;
+        }
+        roll_record(&conn, Some(list_id), &candidates, &[item_ids[0], item_ids[1]])?;
+        // The odds change with the strategy, here the third item joined with a high weight
+        roll_record(&conn, Some(list_id), &[(item_ids[0], 1), (item_ids[1], 1), (item_ids[2], 2)], &[item_ids[2]])?;
+        // Rolls of other lists or recorded before odds were are left out
+        roll_record(&conn, None, &candidates, &[item_ids[0]])?;
+        conn.execute("INSERT INTO rolls (list_id, item_id, created_date) VALUES (?1, ?2, '2023-01-01T00:00:00Z')", (list_id, item_ids[0]))?;
+
+        let report = roll_fairness_get(&conn, list_id)?;
+        assert_eq!(report.draws_count, 6);
+        assert_eq!(report.unaudited_draws_count, 1);
+        let ids = report.items.iter().map(|item| item.item_id).collect::<Vec<_>>();
+        assert_eq!(ids, vec![item_ids[1], item_ids[0], item_ids[2]]);
+        let expected = report.items.iter().map(|item| item.expected).collect::<Vec<_>>();
+        // 4 x 0.75 + 1 (alone after the first draw) + 0.25, 4 x 0.25 + 0.25, 0.5
+        assert!((expected[0] - 4.25).abs() < 1e-9);
+        assert!((expected[1] - 1.25).abs() < 1e-9);
+        assert!((expected[2] - 0.5).abs() < 1e-9);
+        assert!((expected.iter().sum::<f64>() - report.draws_count as f64).abs() < 1e-9, "Each draw spreads one expected draw");
+        assert_eq!(report.chi_square.degrees_of_freedom, 2);
+        assert!(!report.is_conclusive(), "Too few draws to conclude");
+        assert!((report.items[0].observed_frequency(report.draws_count) - 4.0 / 6.0).abs() < 1e-9);
+        Ok(())
+    }
+}
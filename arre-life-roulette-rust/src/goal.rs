@@ -0,0 +1,313 @@
+use std::collections::HashMap;
+use chrono::{DateTime, Duration, Local, Months, Utc};
+use rusqlite::{Connection, Result, Row};
+use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSql, ToSqlOutput, ValueRef};
+use crate::errors::ArreResult;
+use crate::item::ItemId;
+use crate::item_details::item_details_get;
+use crate::list::ListId;
+use crate::list_details::list_details_get;
+use crate::utils::{ArreDateTime, local_month_start, local_week_start};
+
+/// Progress of the item towards its goal in the period containing `now`, None if it has no goal
+pub fn item_goal_progress_get(conn: &Connection, item_id: ItemId, now: DateTime<Utc>) -> ArreResult<Option<GoalProgress>> {
+    let Some(goal) = item_details_get(conn, item_id)?.goal else { return Ok(None) };
+    let (start, end) = goal.period.bounds(now);
+    let mut stmt = conn.prepare("
+        SELECT COUNT(*), COALESCE(SUM(duration), 0)
+        FROM sessions
+        WHERE item_id = ?1 AND is_abandoned = 0 AND start_date >= ?2
+    ")?;
+    Ok(Some(stmt.query_row((item_id, ArreDateTime::new(start)), |row| {
+        GoalProgress::from_row(row, goal, start, end)
+    })?))
+}
+
+/// Progress of all items having a goal set to boost them, in the period containing `now`, in a single query
+pub fn item_boost_goals_progress_get(conn: &Connection, now: DateTime<Utc>) -> ArreResult<HashMap<ItemId, GoalProgress>> {
+    let (week_start, week_end) = GoalPeriod::Week.bounds(now);
+    let (month_start, month_end) = GoalPeriod::Month.bounds(now);
+    let mut stmt = conn.prepare("
+        SELECT
+         COUNT(s.session_id), COALESCE(SUM(s.duration), 0),
+         d.item_id, d.goal_target, d.goal_kind, d.goal_period, d.is_goal_boost
+        FROM item_details d
+        LEFT JOIN sessions s ON s.item_id = d.item_id AND s.is_abandoned = 0
+         AND s.start_date >= CASE WHEN d.goal_period = ?1 THEN ?2 ELSE ?3 END
+        WHERE d.goal_target IS NOT NULL AND d.is_goal_boost = 1
+        GROUP BY d.item_id
+    ")?;
+    let params = (GoalPeriod::Month, ArreDateTime::new(month_start), ArreDateTime::new(week_start));
+    let results = stmt.query_map(params, |row| {
+        let Some(goal) = Goal::from_row_at(row, 3)? else { return Ok(None) };
+        let (start, end) = match goal.period {
+            GoalPeriod::Week => (week_start, week_end),
+            GoalPeriod::Month => (month_start, month_end),
+        };
+        Ok(Some((row.get(2)?, GoalProgress::from_row(row, goal, start, end)?)))
+    })?;
+    Ok(results.filter_map(Result::transpose).collect::<Result<_>>()?)
+}
+
+/// Progress of the list towards its goal in the period containing `now`, any work on its items counts
+pub fn list_goal_progress_get(conn: &Connection, list_id: ListId, now: DateTime<Utc>) -> ArreResult<Option<GoalProgress>> {
+    let Some(goal) = list_details_get(conn, list_id)?.goal else { return Ok(None) };
+    let (start, end) = goal.period.bounds(now);
+    let mut stmt = conn.prepare("
+        SELECT COUNT(*), COALESCE(SUM(s.duration), 0)
+        FROM sessions s
+        JOIN item_list_map ilm ON s.item_id = ilm.item_id
+        WHERE ilm.list_id = ?1 AND s.is_abandoned = 0 AND s.start_date >= ?2
+    ")?;
+    Ok(Some(stmt.query_row((list_id, ArreDateTime::new(start)), |row| {
+        GoalProgress::from_row(row, goal, start, end)
+    })?))
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum GoalTarget {
+    Time(Duration),
+    Sessions(usize),
+}
+
+/// Period the goal has to be reached in, starting over at local midnight
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Default)]
+pub enum GoalPeriod {
+    #[default]
+    Week, // starting on Monday
+    Month,
+}
+
+impl GoalPeriod {
+    /// Start and end of the period containing `now`
+    pub fn bounds(&self, now: DateTime<Utc>) -> (DateTime<Utc>, DateTime<Utc>) {
+        let today = now.with_timezone(&Local).date_naive();
+        match self {
+            GoalPeriod::Week => (local_week_start(today), local_week_start(today + Duration::weeks(1))),
+            GoalPeriod::Month => (local_month_start(today), local_month_start(today + Months::new(1))),
+        }
+    }
+}
+
+impl ToSql for GoalPeriod {
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>> {
+        Ok(match self {
+            GoalPeriod::Week => "week",
+            GoalPeriod::Month => "month",
+        }.into())
+    }
+}
+
+impl FromSql for GoalPeriod {
+    fn column_result(value: ValueRef) -> FromSqlResult<Self> {
+        match value.as_str()? {
+            "week" => Ok(GoalPeriod::Week),
+            "month" => Ok(GoalPeriod::Month),
+            _ => Err(FromSqlError::InvalidType),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Goal {
+    pub target: GoalTarget,
+    pub period: GoalPeriod,
+    pub is_boost: bool, // make the roll more likely while behind on the goal
+}
+
+impl Goal {
+    /// Expects the target (seconds or sessions), its kind, the period and the boost flag
+    pub fn from_row_at(row: &Row, first: usize) -> Result<Option<Goal>> {
+        let Some(target) = row.get::<_, Option<i64>>(first)? else { return Ok(None) };
+        let target = match row.get::<_, String>(first + 1)?.as_str() {
+            "sessions" => GoalTarget::Sessions(target as usize),
+            _ => GoalTarget::Time(Duration::seconds(target)),
+        };
+        Ok(Some(Goal {
+            target,
+            period: row.get(first + 2)?,
+            is_boost: row.get(first + 3)?,
+        }))
+    }
+
+    /// Column values in the order expected by `from_row_at`, the target is NULL when there is no goal
+    pub fn to_columns(goal: &Option<Goal>) -> (Option<i64>, &'static str, GoalPeriod, bool) {
+        match goal {
+            Some(goal) => {
+                let (target, kind) = match goal.target {
+                    GoalTarget::Time(time) => (time.num_seconds(), "time"),
+                    GoalTarget::Sessions(sessions) => (sessions as i64, "sessions"),
+                };
+                (Some(target), kind, goal.period, goal.is_boost)
+            }
+            None => (None, "time", GoalPeriod::default(), false),
+        }
+    }
+}
+
+impl Default for Goal {
+    fn default() -> Self {
+        Goal {
+            target: GoalTarget::Time(Duration::hours(3)),
+            period: GoalPeriod::Week,
+            is_boost: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct GoalProgress {
+    pub goal: Goal,
+    pub sessions_count: usize,
+    pub time_spent: Duration,
+    pub period_start: DateTime<Utc>,
+    pub period_end: DateTime<Utc>,
+}
+
+impl GoalProgress {
+    /// Expects the sessions count and the time spent in seconds
+    fn from_row(row: &Row, goal: Goal, period_start: DateTime<Utc>, period_end: DateTime<Utc>) -> Result<GoalProgress> {
+        Ok(GoalProgress {
+            goal,
+            sessions_count: row.get(0)?,
+            time_spent: Duration::seconds(row.get(1)?),
+            period_start,
+            period_end,
+        })
+    }
+
+    /// Part of the target done, from 0 to 1
+    pub fn fraction(&self) -> f64 {
+        let fraction = match self.goal.target {
+            GoalTarget::Time(time) if time > Duration::zero() => {
+                self.time_spent.num_seconds() as f64 / time.num_seconds() as f64
+            }
+            GoalTarget::Sessions(sessions) if sessions > 0 => self.sessions_count as f64 / sessions as f64,
+            _ => 1.0,
+        };
+        fraction.min(1.0)
+    }
+
+    /// Less was done than the part of the period already elapsed
+    pub fn is_behind(&self, now: DateTime<Utc>) -> bool {
+        let elapsed = (now - self.period_start).num_seconds() as f64
+            / (self.period_end - self.period_start).num_seconds().max(1) as f64;
+        self.fraction() < elapsed.min(1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Datelike, NaiveDate, TimeZone, Weekday};
+    use rstest::*;
+    use rusqlite::Connection;
+    use crate::item::item_create;
+    use crate::item_details::item_details_update;
+    use crate::list::list_items_add;
+    use crate::list_details::list_details_update;
+    use crate::session::{Session, session_persist};
+    use crate::test_fixtures::{conn, TestFactory};
+    use super::*;
+
+    fn local(year: i32, month: u32, day: u32, hour: u32) -> DateTime<Utc> {
+        Local.with_ymd_and_hms(year, month, day, hour, 0, 0).unwrap().with_timezone(&Utc)
+    }
+
+    #[rstest]
+    fn periods_start_on_monday_and_first_of_month() {
+        // Wednesday
+        let now = local(2023, 6, 14, 12);
+        let (start, end) = GoalPeriod::Week.bounds(now);
+        assert_eq!(start.with_timezone(&Local).weekday(), Weekday::Mon);
+        assert_eq!(start, local(2023, 6, 12, 0));
+        assert_eq!(end, local(2023, 6, 19, 0));
+        let (start, end) = GoalPeriod::Month.bounds(now);
+        assert_eq!(start.with_timezone(&Local).date_naive(), NaiveDate::from_ymd_opt(2023, 6, 1).unwrap());
+        assert_eq!(end, local(2023, 7, 1, 0));
+    }
+
+    #[rstest]
+    fn progress_is_behind_when_work_lags_the_period() {
+        let (period_start, period_end) = GoalPeriod::Week.bounds(local(2023, 6, 14, 12));
+        let progress = GoalProgress {
+            goal: Goal { target: GoalTarget::Time(Duration::hours(7)), period: GoalPeriod::Week, is_boost: true },
+            sessions_count: 2,
+            time_spent: Duration::hours(2),
+            period_start,
+            period_end,
+        };
+        assert!((progress.fraction() - 2.0 / 7.0).abs() < 1e-9);
+        // Monday evening, less than a day out of seven elapsed
+        assert!(!progress.is_behind(local(2023, 6, 12, 20)));
+        // Thursday, more than three days elapsed
+        assert!(progress.is_behind(local(2023, 6, 15, 12)));
+    }
+
+    #[rstest]
+    fn item_and_list_goals_count_work_of_the_period(conn: Connection) -> ArreResult<()> {
+        let mut tf = TestFactory::new(&conn);
+        let list_id = tf.create_lists(1)?[0].get_id()?;
+        let item_id = item_create(&conn, "Name", "Description")?.get_id()?;
+        let other_id = item_create(&conn, "Other", "Description")?.get_id()?;
+        list_items_add(&conn, list_id, [item_id, other_id].iter())?;
+        let now = Utc::now();
+        assert!(item_goal_progress_get(&conn, item_id, now)?.is_none());
+
+        let mut details = item_details_get(&conn, item_id)?;
+        details.goal = Some(Goal { target: GoalTarget::Time(Duration::hours(3)), period: GoalPeriod::Week, is_boost: false });
+        item_details_update(&conn, &details)?;
+        let mut list_details = list_details_get(&conn, list_id)?;
+        list_details.goal = Some(Goal { target: GoalTarget::Sessions(5), period: GoalPeriod::Month, is_boost: false });
+        list_details_update(&conn, &list_details)?;
+
+        // Within both the week and the month
+        let start = GoalPeriod::Week.bounds(now).0.max(GoalPeriod::Month.bounds(now).0);
+        session_persist(&conn, &mut Session::new(item_id, None, start, start + Duration::minutes(90)))?;
+        session_persist(&conn, &mut Session::new(other_id, None, start, start + Duration::minutes(10)))?;
+        // Before the period
+        session_persist(&conn, &mut Session::new(item_id, None, start - Duration::days(40), start - Duration::days(39)))?;
+
+        let progress = item_goal_progress_get(&conn, item_id, now)?.unwrap();
+        assert_eq!(progress.time_spent, Duration::minutes(90));
+        assert_eq!(progress.fraction(), 0.5);
+        let progress = list_goal_progress_get(&conn, list_id, now)?.unwrap();
+        assert_eq!(progress.sessions_count, 2);
+        assert_eq!(progress.fraction(), 0.4);
+        Ok(())
+    }
+
+    #[rstest]
+    fn boost_goals_progress_matches_item_progress(conn: Connection) -> ArreResult<()> {
+        let mut tf = TestFactory::new(&conn);
+        let item_ids = tf.create_items(4)?.iter().map(|item| item.get_id()).collect::<ArreResult<Vec<_>>>()?;
+        let now = Utc::now();
+        let goals = [
+            Some(Goal { target: GoalTarget::Time(Duration::hours(3)), period: GoalPeriod::Week, is_boost: true }),
+            Some(Goal { target: GoalTarget::Sessions(5), period: GoalPeriod::Month, is_boost: true }),
+            Some(Goal { target: GoalTarget::Sessions(5), period: GoalPeriod::Month, is_boost: false }),
+            None,
+        ];
+        for (item_id, goal) in item_ids.iter().zip(goals) {
+            let mut details = item_details_get(&conn, *item_id)?;
+            details.goal = goal;
+            item_details_update(&conn, &details)?;
+        }
+        // Within the month, but before the week when the month started earlier
+        let month_start = GoalPeriod::Month.bounds(now).0;
+        for item_id in item_ids.iter() {
+            session_persist(&conn, &mut Session::new(*item_id, None, month_start, month_start + Duration::minutes(30)))?;
+            session_persist(&conn, &mut Session::new(*item_id, None, now - Duration::minutes(20), now - Duration::minutes(10)))?;
+        }
+
+        let progress = item_boost_goals_progress_get(&conn, now)?;
+        assert_eq!(progress.len(), 2, "Only goals boosting the item are included");
+        for item_id in item_ids[0..2].iter() {
+            let expected = item_goal_progress_get(&conn, *item_id, now)?.unwrap();
+            assert_eq!(progress[item_id].sessions_count, expected.sessions_count);
+            assert_eq!(progress[item_id].time_spent, expected.time_spent);
+            assert_eq!(progress[item_id].period_start, expected.period_start);
+        }
+        assert_eq!(progress[&item_ids[1]].sessions_count, 2);
+        Ok(())
+    }
+}
@@ -0,0 +1,80 @@
+use godot::engine::{Control, ControlVirtual};
+use godot::prelude::*;
+
+const BAR_COLOR: Color = Color::from_rgba(0.2, 0.6, 1.0, 1.0);
+const AXIS_COLOR: Color = Color::from_rgba(1.0, 1.0, 1.0, 0.5);
+const LABELS_HEIGHT: f32 = 20.0;
+const BAR_GAP: f32 = 2.0;
+const LABEL_MIN_WIDTH: f32 = 50.0; // labels closer than this are skipped, so they do not overlap
+
+pub struct ChartBar {
+    pub label: String,
+    pub value: f64,
+}
+
+/// Bars scaled to the highest value, labelled along the bottom axis
+#[derive(GodotClass)]
+#[class(base=Control)]
+pub struct BarChart {
+    #[base]
+    base: Base<Control>,
+
+    // state
+    bars: Vec<ChartBar>,
+    max_label: String, // value of the highest bar, displayed on top of the chart
+}
+
+#[godot_api]
+impl BarChart {
+    pub fn set_bars(&mut self, bars: Vec<ChartBar>, max_label: String) {
+        self.bars = bars;
+        self.max_label = max_label;
+        self.base.queue_redraw();
+    }
+}
+
+#[godot_api]
+impl ControlVirtual for BarChart {
+    fn init(base: Base<Self::Base>) -> Self {
+        Self {
+            base,
+
+            // state
+            bars: vec![],
+            max_label: String::new(),
+        }
+    }
+    fn draw(&mut self) {
+        let size = self.base.get_size();
+        let chart_height = size.y - LABELS_HEIGHT;
+        self.base.draw_line(Vector2::new(0.0, chart_height), Vector2::new(size.x, chart_height), AXIS_COLOR);
+        if self.bars.is_empty() {
+            return;
+        }
+        let max_value = self.bars.iter().fold(0.0, |max: f64, bar| max.max(bar.value));
+        let bar_width = size.x / self.bars.len() as f32;
+        let label_every = (LABEL_MIN_WIDTH / bar_width).ceil().max(1.0) as usize;
+        let font = self.base.get_theme_default_font();
+        for (idx, bar) in self.bars.iter().enumerate() {
+            let x = idx as f32 * bar_width;
+            if max_value > 0.0 && bar.value > 0.0 {
+                let height = (bar.value / max_value) as f32 * chart_height;
+                self.base.draw_rect(
+                    Rect2::new(Vector2::new(x + BAR_GAP / 2.0, chart_height - height), Vector2::new(bar_width - BAR_GAP, height)),
+                    BAR_COLOR,
+                );
+            }
+            // The most recent bar is always labelled
+            if let Some(font) = &font {
+                if (self.bars.len() - 1 - idx) % label_every == 0 {
+                    self.base.draw_string(font.share(), Vector2::new(x, size.y - 4.0), bar.label.clone().into());
+                }
+            }
+        }
+        if let Some(font) = font {
+            if max_value > 0.0 {
+                self.base.draw_string(font, Vector2::new(0.0, 14.0), self.max_label.clone().into());
+            }
+        }
+    }
+}
@@ -0,0 +1,98 @@
+use godot::engine::{Control, ControlVirtual, InputEvent, InputEventMouseButton};
+use godot::engine::global::MouseButton;
+use godot::prelude::*;
+
+const EMPTY_COLOR: Color = Color::from_rgba(1.0, 1.0, 1.0, 0.1);
+const ACTIVE_COLOR: Color = Color::from_rgba(0.2, 0.8, 0.3, 1.0);
+const SELECTED_COLOR: Color = Color::from_rgba(1.0, 0.72, 0.24, 1.0);
+const MIN_INTENSITY: f32 = 0.25; // so that little work still stands out from no work
+const CELL_GAP: f32 = 2.0;
+
+/// Days laid out in columns of weeks, Monday on top, shaded by the time spent on them
+#[derive(GodotClass)]
+#[class(base=Control)]
+pub struct CalendarHeatmap {
+    #[base]
+    base: Base<Control>,
+
+    // state
+    values: Vec<f64>, // one per day, the first one being a Monday
+    selected: Option<usize>,
+}
+
+#[godot_api]
+impl CalendarHeatmap {
+    /// Emitted with the index of the day clicked
+    #[signal]
+    fn day_selected(day: i64);
+
+    pub fn set_values(&mut self, values: Vec<f64>) {
+        self.values = values;
+        self.selected = None;
+        self.base.queue_redraw();
+    }
+
+    pub fn set_selected(&mut self, selected: Option<usize>) {
+        self.selected = selected;
+        self.base.queue_redraw();
+    }
+
+    /// Side of the square cells, so that all weeks fit in the width and all weekdays in the height
+    fn cell_size(&self) -> f32 {
+        let size = self.base.get_size();
+        let weeks = ((self.values.len() + 6) / 7).max(1) as f32;
+        (size.x / weeks).min(size.y / 7.0)
+    }
+
+    fn day_at(&self, position: Vector2) -> Option<usize> {
+        let cell_size = self.cell_size();
+        if position.x < 0.0 || position.y < 0.0 || cell_size <= 0.0 {
+            return None;
+        }
+        let (week, weekday) = ((position.x / cell_size) as usize, (position.y / cell_size) as usize);
+        let day = week * 7 + weekday;
+        (weekday < 7 && day < self.values.len()).then_some(day)
+    }
+}
+
+#[godot_api]
+impl ControlVirtual for CalendarHeatmap {
+    fn init(base: Base<Self::Base>) -> Self {
+        Self {
+            base,
+
+            // state
+            values: vec![],
+            selected: None,
+        }
+    }
+    fn draw(&mut self) {
+        let cell_size = self.cell_size();
+        let max_value = self.values.iter().fold(0.0, |max: f64, value| max.max(*value));
+        for (day, value) in self.values.iter().enumerate() {
+            let position = Vector2::new((day / 7) as f32 * cell_size, (day % 7) as f32 * cell_size);
+            let color = if self.selected == Some(day) {
+                SELECTED_COLOR
+            } else if max_value > 0.0 && *value > 0.0 {
+                let intensity = MIN_INTENSITY + (1.0 - MIN_INTENSITY) * (*value / max_value) as f32;
+                Color::from_rgba(ACTIVE_COLOR.r, ACTIVE_COLOR.g, ACTIVE_COLOR.b, intensity)
+            } else {
+                EMPTY_COLOR
+            };
+            self.base.draw_rect(
+                Rect2::new(position, Vector2::new(cell_size - CELL_GAP, cell_size - CELL_GAP)),
+                color,
+            );
+        }
+    }
+    fn gui_input(&mut self, event: Gd<InputEvent>) {
+        if let Some(event) = event.try_cast::<InputEventMouseButton>() {
+            if event.is_pressed() && event.get_button_index() == MouseButton::MOUSE_BUTTON_LEFT {
+                if let Some(day) = self.day_at(event.get_position()) {
+                    self.set_selected(Some(day));
+                    self.base.emit_signal("day_selected".into(), &[(day as i64).to_variant()]);
+                }
+            }
+        }
+    }
+}
@@ -1,13 +1,17 @@
-use godot::engine::{MarginContainer, InputEvent, InputEventMouseButton, MarginContainerVirtual, Label, Button};
+use chrono::Utc;
+use godot::engine::{MarginContainer, InputEvent, InputEventMouseButton, MarginContainerVirtual, Label, Button, ProgressBar};
 use godot::engine::global::MouseButton;
 use godot::prelude::*;
 use crate::errors::{BoxedError};
+use crate::goal::GoalProgress;
 use crate::godot_classes::singletons::buses::{BusType};
 use crate::godot_classes::singletons::logger::log_error;
 use crate::godot_classes::utils::{GdHolder};
 use crate::item::Item;
 use crate::list::List;
 
+const GOAL_BEHIND_COLOR: Color = Color::from_rgba(1.0, 0.6, 0.2, 1.0);
+
 #[derive(Clone)]
 pub enum Content {
     Empty,
@@ -36,6 +40,8 @@ pub struct ElementCard {
     pub button: GdHolder<Button>,
     pub name_label: GdHolder<Label>,
     pub description_label: GdHolder<Label>,
+    pub badge_label: GdHolder<Label>,
+    pub goal_progress_bar: GdHolder<ProgressBar>,
 
     // buses
     pub bus_left_click: BusType<InstanceId>,
@@ -43,6 +49,8 @@ pub struct ElementCard {
 
     // state
     pub content: Content,
+    pub badge: Option<String>,
+    pub goal_progress: Option<GoalProgress>,
 }
 
 #[godot_api]
@@ -57,6 +65,25 @@ impl ElementCard {
             };
             self.name_label.ok_mut()?.set_text(name.into());
             self.description_label.ok_mut()?.set_text(description.into());
+            let badge_label = self.badge_label.ok_mut()?;
+            match &self.badge {
+                Some(badge) => {
+                    badge_label.set_text(badge.clone().into());
+                    badge_label.set_visible(true);
+                }
+                None => badge_label.set_visible(false),
+            }
+            let goal_progress_bar = self.goal_progress_bar.ok_mut()?;
+            match &self.goal_progress {
+                Some(progress) => {
+                    goal_progress_bar.set_value(progress.fraction());
+                    goal_progress_bar.set_self_modulate(
+                        if progress.is_behind(Utc::now()) { GOAL_BEHIND_COLOR } else { Color::from_rgba(1.0, 1.0, 1.0, 1.0) }
+                    );
+                    goal_progress_bar.set_visible(true);
+                }
+                None => goal_progress_bar.set_visible(false),
+            }
         } {
             Ok(_) => {}
             Err::<_, BoxedError>(e) => log_error(e),
@@ -81,6 +108,18 @@ impl ElementCard {
         self.content = content.into();
         self.refresh_display();
     }
+
+    /// Short note displayed on top of the card, e.g. a countdown
+    pub fn set_badge(&mut self, badge: Option<String>) {
+        self.badge = badge;
+        self.refresh_display();
+    }
+
+    /// Progress towards the goal of the item or list, displayed as a bar under the name
+    pub fn set_goal_progress(&mut self, goal_progress: Option<GoalProgress>) {
+        self.goal_progress = goal_progress;
+        self.refresh_display();
+    }
 }
 
 #[godot_api]
@@ -93,6 +132,8 @@ impl MarginContainerVirtual for ElementCard {
             button: GdHolder::default(),
             name_label: GdHolder::default(),
             description_label: GdHolder::default(),
+            badge_label: GdHolder::default(),
+            goal_progress_bar: GdHolder::default(),
 
             // buses
             bus_left_click: BusType::None,
@@ -100,6 +141,8 @@ impl MarginContainerVirtual for ElementCard {
 
             // state
             content: Content::Empty,
+            badge: None,
+            goal_progress: None,
         }
     }
 
@@ -118,6 +161,8 @@ impl MarginContainerVirtual for ElementCard {
             );
             self.name_label = GdHolder::from_path(base, "MarginContainer/VBoxContainer/NameLabel");
             self.description_label = GdHolder::from_path(base, "MarginContainer/VBoxContainer/DescriptionLabel");
+            self.badge_label = GdHolder::from_path(base, "MarginContainer/VBoxContainer/BadgeLabel");
+            self.goal_progress_bar = GdHolder::from_path(base, "MarginContainer/VBoxContainer/GoalProgressBar");
         } {
             Ok(_) => {}
             Err::<_, BoxedError>(e) => log_error(e),
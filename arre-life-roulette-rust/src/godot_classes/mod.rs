@@ -6,4 +6,5 @@ pub mod singletons;
 pub mod tab_view_selector;
 pub mod views;
 pub mod tag_card;
-pub mod sliding_button;
\ No newline at end of file
+pub mod sliding_button;
+pub mod bar_chart;pub mod calendar_heatmap;
@@ -15,5 +15,8 @@ pub const TAG_BG_COLOR_ICON: &str = "res://Icons/Tags/tag_bg_color.png";
 pub const TAG_DELETE_ICON: &str = "res://Icons/Tags/tag_delete.png";
 pub const TAG_REJECT_CHANGES_ICON: &str = "res://Icons/Tags/tag_reject.png";
 
+// Data
+pub const DATABASE_FILE: &str = "user://arre_life_roulette.db";
+
 // InputMap
 pub const LOGS_VIEW_TOGGLE: &str = "LogsViewToggle";
\ No newline at end of file
@@ -1,8 +1,14 @@
-use godot::engine::{Engine, NodeExt};
+use godot::engine::{Engine, NodeExt, OptionButton};
 use godot::obj::dom;
 use godot::prelude::*;
-use crate::errors::ArreError;
+use crate::db::DB;
+use crate::errors::{ArreError, ArreResult};
+use crate::item::ItemFilter;
+use crate::list::{List, list_get_all};
+use crate::tag::{Tag, tag_get_all};
 
+const UI_TEXT_ALL_LISTS: &str = "All Lists";
+const UI_TEXT_ALL_TAGS: &str = "All Tags";
 
 pub fn get_singleton<T>(name: impl Into<StringName>) -> Gd<T>
 where
@@ -12,6 +18,53 @@ where
     Engine::singleton().get_singleton(name).unwrap().cast::<T>()
 }
 
+/// Fill the option button with an entry standing for no choice, followed by the names, and select the former
+pub fn option_button_fill(option_button: &mut Gd<OptionButton>, none_text: &str, names: impl Iterator<Item = String>) {
+    option_button.clear();
+    option_button.add_item(none_text.into());
+    for name in names {
+        option_button.add_item(name.into());
+    }
+    option_button.select(0);
+}
+
+/// Element picked in an option button filled with `option_button_fill`, None if no choice is selected
+pub fn option_button_selected<'a, T>(option_button: &Gd<OptionButton>, elements: &'a [T]) -> Option<&'a T> {
+    match option_button.get_selected() {
+        index if index > 0 => elements.get(index as usize - 1),
+        _ => None,
+    }
+}
+
+/// Lists and tags offered by a pair of option buttons to filter items on
+#[derive(Default)]
+pub struct ItemFilterChoices {
+    lists: Vec<List>, // in the order of the list option button, after the "All Lists" entry
+    tags: Vec<Tag>, // in the order of the tag option button, after the "All Tags" entry
+}
+
+impl ItemFilterChoices {
+    /// Reload the lists and tags into the option buttons, resetting the filter
+    pub fn fill(&mut self, list_option_button: &mut Gd<OptionButton>, tag_option_button: &mut Gd<OptionButton>) -> ArreResult<()> {
+        {
+            let connection = &*DB.ok()?;
+            self.lists = list_get_all(connection)?;
+            self.tags = tag_get_all(connection)?;
+        }
+        option_button_fill(list_option_button, UI_TEXT_ALL_LISTS, self.lists.iter().map(|list| list.name.clone()));
+        option_button_fill(tag_option_button, UI_TEXT_ALL_TAGS, self.tags.iter().map(|tag| tag.name.clone()));
+        Ok(())
+    }
+
+    /// Filter on the list and tag selected in the option buttons filled with `fill`
+    pub fn selected(&self, list_option_button: &Gd<OptionButton>, tag_option_button: &Gd<OptionButton>) -> ArreResult<ItemFilter> {
+        Ok(ItemFilter {
+            list_id: option_button_selected(list_option_button, &self.lists).map(List::get_id).transpose()?,
+            tag_id: option_button_selected(tag_option_button, &self.tags).map(Tag::get_id).transpose()?,
+        })
+    }
+}
+
 pub struct GdHolder<T>
     where T: GodotClass
 {
@@ -1,8 +1,13 @@
 pub mod roll;
+pub mod view_activity;
 pub mod view_item_modify;
+pub mod view_item_sessions;
 pub mod view_item_stats;
 pub mod view_items;
 pub mod view_list_modify;
+pub mod view_list_stats;
 pub mod view_lists;
 pub mod view_logs;
+pub mod view_neglect;
+pub mod view_session_resume;
 pub mod view_tags;
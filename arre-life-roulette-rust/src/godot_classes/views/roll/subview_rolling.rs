@@ -1,7 +1,8 @@
-use godot::engine::{ScrollContainer, VBoxContainer, VBoxContainerVirtual};
+use std::f64::consts::TAU;
+use godot::engine::{Control, MarginContainer, ScrollContainer, VBoxContainer, VBoxContainerVirtual};
 use godot::prelude::*;
-use rand::prelude::SliceRandom;
 use rand::Rng;
+use rand::seq::SliceRandom;
 use crate::errors::{ArreResult, ArreError, BoxedError};
 use crate::godot_classes::element_card::ElementCard;
 use crate::godot_classes::resources::ELEMENT_CARD_PREFAB;
@@ -10,8 +11,51 @@ use crate::godot_classes::utils::{GdHolder};
 use crate::godot_classes::views::roll::view_roll::{RollState, RollView};
 use crate::item::{Item};
 
-const ROLL_ANIMATION_DURATION: f64 = 10.; // seconds
 const ROLL_CARDS_ROWS: usize = 100;
+const WHEEL_SEGMENTS_MAX: usize = 12;
+const WHEEL_RADIUS: f32 = 240.;
+const WHEEL_TURNS: f64 = 4.; // full turns made before landing
+const CARD_WIDTH: f32 = 160.; // as in the ElementCard prefab
+const CARD_HEIGHT: f32 = 200.;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Default)]
+pub enum PresentationMode {
+    #[default]
+    Slots,
+    Wheel,
+    Instant,
+}
+
+/// How the roll, already decided by the roll engine, is revealed
+#[derive(Debug, Clone, Copy)]
+pub struct Presentation {
+    pub mode: PresentationMode,
+    pub duration: f64, // seconds, ignored by the instant mode
+    pub reduced_motion: bool, // no moving content, the result is revealed right away
+}
+
+impl Presentation {
+    fn effective_mode(&self) -> PresentationMode {
+        if self.reduced_motion { PresentationMode::Instant } else { self.mode }
+    }
+
+    fn effective_duration(&self) -> f64 {
+        match self.effective_mode() {
+            PresentationMode::Instant => 0.,
+            _ => self.duration,
+        }
+    }
+}
+
+impl Default for Presentation {
+    fn default() -> Self {
+        Presentation {
+            mode: PresentationMode::default(),
+            duration: 10.,
+            reduced_motion: false,
+        }
+    }
+}
 
 #[derive(GodotClass)]
 #[class(base=VBoxContainer)]
@@ -23,7 +67,11 @@ pub struct RollRollingSubview {
     element_card_prefab: Gd<PackedScene>,
 
     // cached internal UI elements
+    pub slots_container: GdHolder<MarginContainer>,
     pub scrolls: [GdHolder<ScrollContainer>; 3],
+    pub wheel_container: GdHolder<Control>,
+    pub wheel_pivot: GdHolder<Control>,
+    pub wheel_cards: Vec<Gd<ElementCard>>,
 
     // cached external UI elements
     pub roll_view: GdHolder<RollView>,
@@ -32,19 +80,42 @@ pub struct RollRollingSubview {
     rng: rand::rngs::ThreadRng,
     is_animating: bool,
     animation_time: f64, // total time of the ongoing animation
-    chosen_item: Item,
+    queue: Vec<Item>,
+    presentation: Presentation,
+    wheel_final_rotation: f64, // radians, the chosen item is under the pointer at this rotation
 
 }
 
 #[godot_api]
 impl RollRollingSubview {
 
-    pub fn animate(&mut self, mut eligible_items: Vec<Item>) -> ArreResult<()> {
+    /// Animate the roll landing on the head of the already drawn `queue`
+    pub fn animate(&mut self, eligible_items: Vec<Item>, queue: Vec<Item>, presentation: Presentation) -> ArreResult<()> {
+        let chosen_item = queue.first().cloned().ok_or(ArreError::ItemsSelectionIsEmpty())?;
         self.is_animating = true;
         self.animation_time = 0.;
+        self.queue = queue;
+        self.presentation = presentation;
+
+        let mode = presentation.effective_mode();
+        self.slots_container.ok_mut()?.set_visible(mode == PresentationMode::Slots);
+        self.wheel_container.ok_mut()?.set_visible(mode == PresentationMode::Wheel);
+        match mode {
+            PresentationMode::Slots => self.prepare_slots(eligible_items, chosen_item),
+            PresentationMode::Wheel => self.prepare_wheel(eligible_items, chosen_item),
+            PresentationMode::Instant => Ok(()),
+        }
+    }
+
+    fn prepare_slots(&mut self, eligible_items: Vec<Item>, chosen_item: Item) -> ArreResult<()> {
 
         // Animation takes ROLL_ANIMATION_DURATION seconds, during which we display ROLL_CARDS_ROWS cards.
         // Eligible cards are slowly reduced and so later rows must respect this reduction.
+        // The chosen item is never reduced, so it is the only one left in the last row.
+        let mut other_items = eligible_items
+            .into_iter()
+            .filter(|item| item.id != chosen_item.id)
+            .collect::<Vec<_>>();
         let mut cards = [Array::new(), Array::new(), Array::new()];
         for scroll_idx in 0..3 {
             let scroll = self.scrolls[scroll_idx].ok_mut()?;
@@ -52,40 +123,101 @@ impl RollRollingSubview {
             cards[scroll_idx] = vbox.ok()?.get_children();
         }
         for row in 0..ROLL_CARDS_ROWS {
-            if eligible_items.len() > ROLL_CARDS_ROWS - row {
-                eligible_items.remove(self.rng.gen_range(0..eligible_items.len()));
-            } else if row == ROLL_CARDS_ROWS - 1 && eligible_items.len() > 1 {
-                while eligible_items.len() > 1 {
-                    eligible_items.remove(self.rng.gen_range(0..eligible_items.len()));
-                }
+            if other_items.len() + 1 > ROLL_CARDS_ROWS - row {
+                other_items.remove(self.rng.gen_range(0..other_items.len()));
+            } else if row == ROLL_CARDS_ROWS - 1 {
+                other_items.clear();
             }
             for scroll_idx in 0..3 {
                 let mut card = GdHolder::<ElementCard>::from_gd(cards[scroll_idx].get(row));
                 let mut card = card.ok_mut()?.bind_mut();
-                let card_item = eligible_items.choose(&mut self.rng).ok_or(ArreError::UnexpectedNone("RollRollingSubview::animate".to_string()))?;
+                let card_item = match self.rng.gen_range(0..=other_items.len()) {
+                    idx if idx == other_items.len() => &chosen_item,
+                    idx => &other_items[idx],
+                };
                 card.set_content(card_item.clone());
             }
         }
-        self.chosen_item = eligible_items.pop().ok_or(ArreError::UnexpectedNone("RollRollingSubview::animate".to_string()))?;
+        Ok(())
+    }
+
+    /// Lay out up to WHEEL_SEGMENTS_MAX cards around the wheel, the chosen one at a random segment
+    fn prepare_wheel(&mut self, eligible_items: Vec<Item>, chosen_item: Item) -> ArreResult<()> {
+        self.wheel_cards.drain(..).for_each(|mut card| card.bind_mut().queue_free());
+        let other_items = eligible_items
+            .into_iter()
+            .filter(|item| item.id != chosen_item.id)
+            .collect::<Vec<_>>();
+        let mut segments = other_items
+            .choose_multiple(&mut self.rng, WHEEL_SEGMENTS_MAX - 1)
+            .cloned()
+            .collect::<Vec<_>>();
+        let chosen_segment = self.rng.gen_range(0..=segments.len());
+        segments.insert(chosen_segment, chosen_item);
+
+        let segment_angle = TAU / segments.len() as f64;
+        let card_half_size = Vector2::new(CARD_WIDTH, CARD_HEIGHT) / 2.;
+        let wheel_pivot = self.wheel_pivot.ok_mut()?;
+        wheel_pivot.set_rotation(0.);
+        for (idx, item) in segments.into_iter().enumerate() {
+            let mut card = self.element_card_prefab
+                .try_instantiate_as::<ElementCard>()
+                .ok_or(ArreError::InstantiateFailed("ElementCard".into(), "RollRollingSubview::prepare_wheel".into()))?;
+            wheel_pivot.add_child(card.share().upcast());
+            {
+                let mut card = card.bind_mut();
+                card.set_content(item);
+                let angle = (segment_angle * idx as f64) as f32;
+                let direction = Vector2::new(angle.sin(), -angle.cos());
+                card.set_pivot_offset(card_half_size);
+                card.set_position(direction * WHEEL_RADIUS - card_half_size);
+                card.set_rotation(angle);
+            }
+            self.wheel_cards.push(card);
+        }
+        // Rotate backwards, so the chosen segment ends up at the top, under the pointer
+        self.wheel_final_rotation = -(segment_angle * chosen_segment as f64) - TAU * WHEEL_TURNS;
         Ok(())
     }
 
     fn progress_animation(&mut self, delta: f64) -> ArreResult<()> {
         self.animation_time += delta;
-        if self.animation_time >= ROLL_ANIMATION_DURATION {
+        let duration = self.presentation.effective_duration();
+        match self.presentation.effective_mode() {
+            PresentationMode::Slots => self.progress_slots(duration)?,
+            PresentationMode::Wheel => self.progress_wheel(duration)?,
+            PresentationMode::Instant => {}
+        }
+        if self.animation_time >= duration {
             self.is_animating = false;
             let mut roll_view = self.roll_view.ok_mut()?.bind_mut();
-            roll_view.roll_state_change_request(RollState::WorkAssigned{item: self.chosen_item.clone()});
+            roll_view.roll_state_change_request(RollState::WorkAssigned{queue: self.queue.clone()});
         }
+        Ok(())
+    }
+
+    fn progress_wheel(&mut self, duration: f64) -> ArreResult<()> {
+        let center = self.wheel_container.ok()?.get_size() / 2.;
+        let wheel_pivot = self.wheel_pivot.ok_mut()?;
+        wheel_pivot.set_position(center);
+        // Cubic ease out, the wheel slows down until it stops on the chosen segment
+        let progress = (self.animation_time / duration).min(1.);
+        let eased = 1. - (1. - progress).powi(3);
+        wheel_pivot.set_rotation((self.wheel_final_rotation * eased) as f32);
+        Ok(())
+    }
+
+    fn progress_slots(&mut self, duration: f64) -> ArreResult<()> {
         for scroll_idx in 0..3 {
             let scroll = self.scrolls[scroll_idx].ok_mut()?;
             let mut vbox = GdHolder::<VBoxContainer>::from_path(&self.base, format!("{}/VBoxContainer", scroll.get_path()));
             let vbox = vbox.ok_mut()?;
 
             // Apply a power to time before logarithm to slow down the growth rate
-            let adjusted_time = (self.animation_time / ROLL_ANIMATION_DURATION).powf(0.20) * ROLL_ANIMATION_DURATION;
+            let animation_time = self.animation_time.min(duration);
+            let adjusted_time = (animation_time / duration).powf(0.20) * duration;
             // We use a scaling factor to make sure we reach max_position when time is max_time.
-            let scale_factor = (vbox.get_size().y as f64 / ROLL_ANIMATION_DURATION.log10()).max(0.);
+            let scale_factor = (vbox.get_size().y as f64 / duration.log10()).max(0.);
             let new_scroll_position = scale_factor * adjusted_time.log10();
             scroll.set_v_scroll(new_scroll_position.round() as i32);
         }
@@ -102,7 +234,11 @@ impl VBoxContainerVirtual for RollRollingSubview {
             element_card_prefab: load(ELEMENT_CARD_PREFAB),
 
             // cached internal UI elements
+            slots_container: GdHolder::default(),
             scrolls: [GdHolder::default(), GdHolder::default(), GdHolder::default()],
+            wheel_container: GdHolder::default(),
+            wheel_pivot: GdHolder::default(),
+            wheel_cards: vec![],
 
             // cached external UI elements
             roll_view: GdHolder::default(),
@@ -111,13 +247,18 @@ impl VBoxContainerVirtual for RollRollingSubview {
             rng: rand::thread_rng(),
             is_animating: false,
             animation_time: 0.,
-            chosen_item: Item::default(),
+            queue: vec![],
+            presentation: Presentation::default(),
+            wheel_final_rotation: 0.,
         }
     }
     fn ready(&mut self) {
         match try {
             let base = &self.base;
             // cached internal UI elements
+            self.slots_container = GdHolder::from_path(base, "MarginContainer");
+            self.wheel_container = GdHolder::from_path(base, "WheelContainer");
+            self.wheel_pivot = GdHolder::from_path(base, "WheelContainer/WheelPivot");
             self.scrolls = [
                 GdHolder::from_path(base, "MarginContainer/HBoxContainer/ScrollContainer1"),
                 GdHolder::from_path(base, "MarginContainer/HBoxContainer/ScrollContainer2"),
@@ -1,16 +1,27 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use bus::BusReader;
-use godot::engine::{Button, VBoxContainer, VBoxContainerVirtual};
+use chrono::{DateTime, Duration, Utc};
+use godot::engine::{Button, CheckButton, Label, OptionButton, SpinBox, VBoxContainer, VBoxContainerVirtual};
 use godot::prelude::*;
 use crate::db::DB;
+use crate::deck::{deck_drawn_before_roll, deck_remaining_count, deck_roll_record};
 use crate::errors::{ArreResult, BoxedError};
 use crate::godot_classes::containers::cards_flow_container::CardsFlowContainer;
 use crate::godot_classes::element_card::{Content, ElementCard};
 use crate::godot_classes::singletons::logger::log_error;
 use crate::godot_classes::utils::{GdHolder};
+use crate::godot_classes::views::roll::subview_rolling::{Presentation, PresentationMode};
 use crate::godot_classes::views::roll::view_roll::{RollState, RollView};
-use crate::item::{Item, ItemId};
-use crate::list::{list_items_get, ListId};
+use crate::item::{Item, ItemId, items_to_ids};
+use crate::list::ListId;
+use crate::list_details::list_details_get;
+use crate::roll::{NoDurationPolicy, roll_candidates_get, roll_draw, roll_eligible_items_get, roll_record, RollSource, TimeBudget};
+use crate::simulation::{roll_inclusion_probabilities, simulate_rolls, SIMULATION_ROLLS};
+use crate::utils::format_duration;
+
+const UI_TEXT_DECK_DRAWN: &str = "Already drawn from the deck";
+const UI_TEXT_OVER_TIME_BUDGET: &str = "Longer than the time available";
+const UI_TEXT_DECK_LEFT: &str = "Cards left in the deck: {remaining} of {cards}";
 
 #[derive(GodotClass)]
 #[class(base=VBoxContainer)]
@@ -21,6 +32,16 @@ pub struct RollSelectionSubview {
     // cached internal UI elements
     pub cards_container: GdHolder<CardsFlowContainer>,
     pub roll_start_button: GdHolder<Button>,
+    pub roll_count_spin_box: GdHolder<SpinBox>,
+    pub time_budget_check_button: GdHolder<CheckButton>,
+    pub time_budget_spin_box: GdHolder<SpinBox>,
+    pub no_duration_option_button: GdHolder<OptionButton>,
+    pub deck_remaining_label: GdHolder<Label>,
+    pub simulate_button: GdHolder<Button>,
+    pub simulation_report_label: GdHolder<Label>,
+    pub presentation_option_button: GdHolder<OptionButton>,
+    pub presentation_duration_spin_box: GdHolder<SpinBox>,
+    pub reduced_motion_check_button: GdHolder<CheckButton>,
 
     // cached external UI elements
     pub roll_view: GdHolder<RollView>,
@@ -29,28 +50,57 @@ pub struct RollSelectionSubview {
     observer_card_left_click: Option<BusReader<InstanceId>>,
 
     // state
-    list_id: ListId,
+    rng: rand::rngs::ThreadRng,
+    source: RollSource,
     items: HashMap<ItemId, Item>,
-    items_enabled: HashMap<ItemId, bool>,
+    weights: HashMap<ItemId, u32>,
+    items_enabled: HashMap<ItemId, bool>, // false for items the user left out of the roll
+    eligible: HashSet<ItemId>, // items the roll can draw from at this moment and within the time budget
+    cooldowns: HashMap<ItemId, DateTime<Utc>>, // end of the cooldown of items not eligible yet
+    exclusions: HashMap<ItemId, String>, // why items are excluded, e.g. outside of their availability
+    deck_list_id: Option<ListId>, // set when rolling a single list in deck mode
+    deck_drawn: HashSet<ItemId>, // cards already drawn from the deck, left out of the roll
+    time_budget: Option<TimeBudget>,
 }
 
 #[godot_api]
 impl RollSelectionSubview {
 
-    pub fn set_state(&mut self, list_id: ListId) {
-        self.list_id = list_id;
+    pub fn set_state(&mut self, source: RollSource) {
+        self.source = source;
         self.refresh_state();
     }
 
     pub fn refresh_state(&mut self) {
         match try {
+            let now = Utc::now();
+            self.deck_list_id = match self.source.single_list() {
+                Some(list_id) => {
+                    let is_deck_mode = list_details_get(&*DB.ok()?, list_id)?.is_deck_mode;
+                    is_deck_mode.then_some(list_id)
+                }
+                None => None,
+            };
+            // The roll draws from the eligible items, candidates only explain why the other cards cannot be rolled
+            self.refresh_eligible(now)?;
             let connection = &*DB.ok()?;
 
-            self.items = list_items_get::<Vec<_>>(connection, self.list_id)?
-                .into_iter()
-                .map(|item| {
-                    Ok((item.get_id()?, item))
-                }).collect::<ArreResult<_>>()?;
+            self.items = HashMap::new();
+            self.weights = HashMap::new();
+            self.cooldowns = HashMap::new();
+            self.exclusions = HashMap::new();
+
+            for candidate in roll_candidates_get(connection, &self.source, now)? {
+                let item_id = candidate.item.get_id()?;
+                if let Some(cooldown_end) = candidate.cooldown_end.filter(|_| !candidate.is_eligible(now)) {
+                    self.cooldowns.insert(item_id, cooldown_end);
+                }
+                if let Some(unavailability) = candidate.unavailability(now) {
+                    self.exclusions.insert(item_id, unavailability.to_string());
+                }
+                self.weights.insert(item_id, candidate.weight);
+                self.items.insert(item_id, candidate.item);
+            }
             self.items_enabled = self.items.keys().map(|item_id| (*item_id, true)).collect();
         } {
             Ok(_) => {}
@@ -60,8 +110,244 @@ impl RollSelectionSubview {
 
     pub fn refresh_display(&mut self) {
         match try {
+            self.cards_container.ok_mut()?.bind_mut().set_cards(self.items.values().cloned().collect());
+            self.simulation_report_label.ok_mut()?.set_visible(false);
+            self.refresh_cards()?;
+            self.refresh_cooldowns_display()?;
+            self.refresh_deck_display()?;
+        } {
+            Ok(_) => {}
+            Err::<_, BoxedError>(e) => log_error(e)
+        }
+    }
+
+    fn refresh_deck_display(&mut self) -> ArreResult<()> {
+        let label = self.deck_remaining_label.ok_mut()?;
+        match self.deck_list_id {
+            Some(list_id) => {
+                let connection = &*DB.ok()?;
+                let remaining = deck_remaining_count(connection, list_id)?;
+                let text = UI_TEXT_DECK_LEFT
+                    .replace("{remaining}", &remaining.to_string())
+                    .replace("{cards}", &self.items.len().to_string());
+                label.set_text(text.into());
+                label.set_visible(true);
+            }
+            None => label.set_visible(false),
+        }
+        Ok(())
+    }
+
+    /// Query the items the roll can draw from, the deck is left as it is until rolling
+    fn refresh_eligible(&mut self, now: DateTime<Utc>) -> ArreResult<()> {
+        let connection = &*DB.ok()?;
+        let eligible_items = roll_eligible_items_get(connection, &self.source, now, self.time_budget.as_ref())?;
+        self.eligible = items_to_ids(eligible_items.iter())?;
+        self.deck_drawn = match self.deck_list_id {
+            Some(list_id) => deck_drawn_before_roll(connection, list_id, &self.eligible)?,
+            None => HashSet::new(),
+        };
+        Ok(())
+    }
+
+    /// Whether the item can be enabled, deck mode leaving out the cards already drawn
+    fn is_item_selectable(&self, item_id: &ItemId) -> bool {
+        self.eligible.contains(item_id) && !self.deck_drawn.contains(item_id)
+    }
+
+    fn is_item_rollable(&self, item_id: &ItemId) -> bool {
+        self.is_item_selectable(item_id) && self.items_enabled[item_id]
+    }
+
+    /// Items taking part in the roll, along with their weights
+    fn rollable_items(&self) -> Vec<(Item, u32)> {
+        self.items
+            .iter()
+            .filter(|(item_id, _)| self.is_item_rollable(item_id))
+            .map(|(item_id, item)| (item.clone(), self.weights[item_id]))
+            .collect()
+    }
+
+    fn roll_count(&self) -> ArreResult<usize> {
+        Ok(self.roll_count_spin_box.ok()?.get_value() as usize)
+    }
+
+    fn presentation(&self) -> ArreResult<Presentation> {
+        Ok(Presentation {
+            mode: match self.presentation_option_button.ok()?.get_selected() {
+                1 => PresentationMode::Wheel,
+                2 => PresentationMode::Instant,
+                _ => PresentationMode::Slots,
+            },
+            duration: self.presentation_duration_spin_box.ok()?.get_value(),
+            reduced_motion: self.reduced_motion_check_button.ok()?.is_pressed(),
+        })
+    }
+
+    /// Dim the cards of items that will not take part in the roll and show the chance of the others to be rolled.
+    /// Cooling down items show the time left, then `refresh_cooldowns_display` keeps counting it down.
+    fn refresh_cards(&mut self) -> ArreResult<()> {
+        let now = Utc::now();
+        let rollable_items = self.rollable_items();
+        let probabilities = match roll_inclusion_probabilities(&rollable_items, self.roll_count()?) {
+            Ok(probabilities) => rollable_items
+                .iter()
+                .map(|(item, _)| item.get_id())
+                .zip(probabilities)
+                .map(|(item_id, probability)| Ok((item_id?, probability)))
+                .collect::<ArreResult<HashMap<_, _>>>()?,
+            // Nothing to roll
+            Err(_) => HashMap::new(),
+        };
+        let cards_state = self.items
+            .keys()
+            .map(|item_id| {
+                let badge = if let Some(probability) = probabilities.get(item_id) {
+                    Some(format!("{:.1}%", probability * 100.))
+                } else if let Some(cooldown_end) = self.cooldowns.get(item_id) {
+                    Some(format_duration((*cooldown_end - now).max(Duration::zero())))
+                } else if let Some(exclusion) = self.exclusions.get(item_id) {
+                    Some(exclusion.clone())
+                } else if self.deck_drawn.contains(item_id) {
+                    Some(UI_TEXT_DECK_DRAWN.to_string())
+                } else if !self.eligible.contains(item_id) {
+                    // Neither cooling down nor unavailable, so left out by the time budget
+                    Some(UI_TEXT_OVER_TIME_BUDGET.to_string())
+                } else {
+                    None
+                };
+                let modulate = if self.is_item_rollable(item_id) {
+                    Color::from_rgba(1.0, 1.0, 1.0, 1.0)
+                } else {
+                    Color::from_rgba(1.0, 1.0, 1.0, 0.3)
+                };
+                (*item_id, (modulate, badge))
+            })
+            .collect::<HashMap<_, _>>();
+        let mut cards_container = self.cards_container.ok_mut()?.bind_mut();
+        for card in cards_container.item_cards.iter_mut() {
+            let mut card = card.bind_mut();
+            let item_id = match &card.content {
+                Content::Item(item) => item.get_id()?,
+                _ => continue,
+            };
+            let (modulate, badge) = cards_state[&item_id].clone();
+            card.set_modulate(modulate);
+            card.set_badge(badge);
+        }
+        Ok(())
+    }
+
+    /// Update countdown badges of cooling down items and re-enable those whose cooldown ended
+    fn refresh_cooldowns_display(&mut self) -> ArreResult<()> {
+        if self.cooldowns.is_empty() { return Ok(()); }
+        let now = Utc::now();
+        let mut cooldowns_ended = false;
+        {
             let mut cards_container = self.cards_container.ok_mut()?.bind_mut();
-            cards_container.set_cards(self.items.values().cloned().collect());
+            for card in cards_container.item_cards.iter_mut() {
+                let mut card = card.bind_mut();
+                let item_id = match &card.content {
+                    Content::Item(item) => item.get_id()?,
+                    _ => continue,
+                };
+                let Some(cooldown_end) = self.cooldowns.get(&item_id).copied() else { continue };
+                if cooldown_end > now {
+                    card.set_badge(Some(format_duration(cooldown_end - now)));
+                } else {
+                    self.cooldowns.remove(&item_id);
+                    cooldowns_ended = true;
+                }
+            }
+        }
+        if cooldowns_ended {
+            self.refresh_eligible(now)?;
+            self.refresh_cards()?;
+            self.refresh_deck_display()?;
+        }
+        Ok(())
+    }
+
+    #[func]
+    fn on_time_budget_check_button_toggled(&mut self, _checked: bool) {
+        self.on_time_budget_changed();
+    }
+
+    #[func]
+    fn on_time_budget_spin_box_value_changed(&mut self, _value: f64) {
+        self.on_time_budget_changed();
+    }
+
+    #[func]
+    fn on_no_duration_option_button_item_selected(&mut self, _index: i64) {
+        self.on_time_budget_changed();
+    }
+
+    #[func]
+    fn on_presentation_option_button_item_selected(&mut self, _index: i64) {
+        self.on_presentation_changed();
+    }
+
+    #[func]
+    fn on_reduced_motion_check_button_toggled(&mut self, _pressed: bool) {
+        self.on_presentation_changed();
+    }
+
+    /// The duration only matters for animated presentations
+    fn on_presentation_changed(&mut self) {
+        match try {
+            let is_animated = self.presentation()?.mode != PresentationMode::Instant
+                && !self.reduced_motion_check_button.ok()?.is_pressed();
+            self.presentation_duration_spin_box.ok_mut()?.set_editable(is_animated);
+        } {
+            Ok(_) => {}
+            Err::<_, BoxedError>(e) => log_error(e)
+        }
+    }
+
+    fn on_time_budget_changed(&mut self) {
+        match try {
+            let is_budget_set = self.time_budget_check_button.ok()?.is_pressed();
+            self.time_budget_spin_box.ok_mut()?.set_editable(is_budget_set);
+            self.no_duration_option_button.ok_mut()?.set_disabled(!is_budget_set);
+            self.time_budget = if is_budget_set {
+                Some(TimeBudget {
+                    available: Duration::minutes(self.time_budget_spin_box.ok()?.get_value() as i64),
+                    no_duration_policy: match self.no_duration_option_button.ok()?.get_selected() {
+                        1 => NoDurationPolicy::Exclude,
+                        _ => NoDurationPolicy::Include,
+                    },
+                })
+            } else {
+                None
+            };
+            self.refresh_eligible(Utc::now())?;
+            self.refresh_cards()?;
+            self.refresh_deck_display()?;
+        } {
+            Ok(_) => {}
+            Err::<_, BoxedError>(e) => log_error(e)
+        }
+    }
+
+    #[func]
+    fn on_roll_count_spin_box_value_changed(&mut self, _value: f64) {
+        match try {
+            self.refresh_cards()?;
+        } {
+            Ok(_) => {}
+            Err::<_, BoxedError>(e) => log_error(e)
+        }
+    }
+
+    #[func]
+    fn on_simulate_button_up(&mut self) {
+        match try {
+            let rollable_items = self.rollable_items();
+            let report = simulate_rolls(&mut self.rng, &rollable_items, self.roll_count()?, SIMULATION_ROLLS)?;
+            let label = self.simulation_report_label.ok_mut()?;
+            label.set_text(report.format(|item| item.name.clone()).into());
+            label.set_visible(true);
         } {
             Ok(_) => {}
             Err::<_, BoxedError>(e) => log_error(e)
@@ -71,13 +357,23 @@ impl RollSelectionSubview {
     #[func]
     fn on_roll_start_button_up(&mut self) {
         match try {
-            // make list of enabled items to choose from
-            let work_items = self.items
-                .iter()
-                .filter(|(item_id, _)| self.items_enabled[*item_id])
-                .map(|(_, item)| item.clone())
-                .collect::<Vec<_>>();
-            self.roll_view.ok_mut()?.bind_mut().roll_state_change_request(RollState::Rolling(work_items));
+            let work_items = self.rollable_items();
+            let queue = roll_draw(&mut self.rng, &work_items, self.roll_count()?)?;
+            {
+                let connection = &*DB.ok()?;
+                let queue_ids = items_to_ids::<_, Vec<_>>(queue.iter())?;
+                let candidates = work_items
+                    .iter()
+                    .map(|(item, weight)| Ok((item.get_id()?, *weight)))
+                    .collect::<ArreResult<Vec<_>>>()?;
+                match self.deck_list_id {
+                    Some(list_id) => deck_roll_record(connection, list_id, &candidates, &queue_ids)?,
+                    None => roll_record(connection, self.source.single_list(), &candidates, &queue_ids)?,
+                }
+            }
+            let work_items = work_items.into_iter().map(|(item, _)| item).collect();
+            let presentation = self.presentation()?;
+            self.roll_view.ok_mut()?.bind_mut().roll_state_change_request(RollState::Rolling{eligible: work_items, queue, presentation});
         } {
             Ok(_) => {}
             Err::<_, BoxedError>(e) => log_error(e)
@@ -85,17 +381,19 @@ impl RollSelectionSubview {
     }
 
     fn on_item_card_left_click(&mut self, card_id: InstanceId) -> ArreResult<()> {
-        let mut card = GdHolder::<ElementCard>::from_instance_id(card_id);
-        let mut card = card.ok_mut()?.bind_mut();
-        if let Content::Item(item) = &card.content {
-            let item_id = item.get_id()?;
-            let was_item_enabled = self.items_enabled[&item_id];
-            let is_item_enabled = !was_item_enabled;
-            self.items_enabled.insert(item_id, is_item_enabled);
-            card.set_modulate(
-                if is_item_enabled { Color::from_rgba(1.0, 1.0, 1.0, 1.0) } else { Color::from_rgba(1.0, 1.0, 1.0, 0.3) }
-            );
+        let card = GdHolder::<ElementCard>::from_instance_id(card_id);
+        let item_id = match &card.ok()?.bind().content {
+            Content::Item(item) => item.get_id()?,
+            _ => return Ok(()),
+        };
+        if !self.is_item_selectable(&item_id) {
+            // Items cooling down, unavailable, not fitting the time budget or already drawn cannot be rolled
+            return Ok(());
         }
+        let was_item_enabled = self.items_enabled[&item_id];
+        self.items_enabled.insert(item_id, !was_item_enabled);
+        // Probabilities of all the other items change as well
+        self.refresh_cards()?;
         Ok(())
     }
 }
@@ -109,6 +407,16 @@ impl VBoxContainerVirtual for RollSelectionSubview {
             // cached internal UI elements
             cards_container: GdHolder::default(),
             roll_start_button: GdHolder::default(),
+            roll_count_spin_box: GdHolder::default(),
+            time_budget_check_button: GdHolder::default(),
+            time_budget_spin_box: GdHolder::default(),
+            no_duration_option_button: GdHolder::default(),
+            deck_remaining_label: GdHolder::default(),
+            simulate_button: GdHolder::default(),
+            simulation_report_label: GdHolder::default(),
+            presentation_option_button: GdHolder::default(),
+            presentation_duration_spin_box: GdHolder::default(),
+            reduced_motion_check_button: GdHolder::default(),
 
             // cached external UI elements
             roll_view: GdHolder::default(),
@@ -117,9 +425,17 @@ impl VBoxContainerVirtual for RollSelectionSubview {
             observer_card_left_click: None,
 
             // state
-            list_id: 0.into(),
+            rng: rand::thread_rng(),
+            source: RollSource::AllItems,
             items: HashMap::new(),
+            weights: HashMap::new(),
             items_enabled: HashMap::new(),
+            eligible: HashSet::new(),
+            cooldowns: HashMap::new(),
+            exclusions: HashMap::new(),
+            deck_list_id: None,
+            deck_drawn: HashSet::new(),
+            time_budget: None,
         }
     }
     fn ready(&mut self) {
@@ -135,6 +451,44 @@ impl VBoxContainerVirtual for RollSelectionSubview {
                 "button_up".into(),
                 base.callable("on_roll_start_button_up"),
             );
+            self.roll_count_spin_box = GdHolder::from_path(base, "RollCountHBoxContainer/SpinBox");
+            self.roll_count_spin_box.ok_mut()?.connect(
+                "value_changed".into(),
+                base.callable("on_roll_count_spin_box_value_changed"),
+            );
+            self.simulate_button = GdHolder::from_path(base, "RollCountHBoxContainer/SimulateButton");
+            self.simulate_button.ok_mut()?.connect(
+                "button_up".into(),
+                base.callable("on_simulate_button_up"),
+            );
+            self.simulation_report_label = GdHolder::from_path(base, "SimulationReportLabel");
+            self.deck_remaining_label = GdHolder::from_path(base, "DeckRemainingLabel");
+            self.time_budget_check_button = GdHolder::from_path(base, "TimeBudgetHBoxContainer/CheckButton");
+            self.time_budget_check_button.ok_mut()?.connect(
+                "toggled".into(),
+                base.callable("on_time_budget_check_button_toggled"),
+            );
+            self.time_budget_spin_box = GdHolder::from_path(base, "TimeBudgetHBoxContainer/SpinBox");
+            self.time_budget_spin_box.ok_mut()?.connect(
+                "value_changed".into(),
+                base.callable("on_time_budget_spin_box_value_changed"),
+            );
+            self.no_duration_option_button = GdHolder::from_path(base, "TimeBudgetHBoxContainer/NoDurationOptionButton");
+            self.no_duration_option_button.ok_mut()?.connect(
+                "item_selected".into(),
+                base.callable("on_no_duration_option_button_item_selected"),
+            );
+            self.presentation_option_button = GdHolder::from_path(base, "PresentationHBoxContainer/OptionButton");
+            self.presentation_option_button.ok_mut()?.connect(
+                "item_selected".into(),
+                base.callable("on_presentation_option_button_item_selected"),
+            );
+            self.presentation_duration_spin_box = GdHolder::from_path(base, "PresentationHBoxContainer/SpinBox");
+            self.reduced_motion_check_button = GdHolder::from_path(base, "PresentationHBoxContainer/ReducedMotionCheckButton");
+            self.reduced_motion_check_button.ok_mut()?.connect(
+                "toggled".into(),
+                base.callable("on_reduced_motion_check_button_toggled"),
+            );
 
             // cached external UI elements
             // self.roll_view is set from RollView::ready()
@@ -150,6 +504,9 @@ impl VBoxContainerVirtual for RollSelectionSubview {
                     self.on_item_card_left_click(card)?;
                 }
             }
+            if self.base.is_visible() {
+                self.refresh_cooldowns_display()?;
+            }
         } {
             Ok(_) => {}
             Err::<_, BoxedError>(e) => log_error(e),
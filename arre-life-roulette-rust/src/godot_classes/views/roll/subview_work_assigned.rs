@@ -1,16 +1,30 @@
 use chrono::{DateTime, Duration, Utc};
-use godot::engine::{Button, Label, VBoxContainer, VBoxContainerVirtual};
+use godot::engine::{AudioStreamPlayer, Button, CheckButton, DisplayServer, InputEvent, Label, LineEdit, SpinBox, VBoxContainer, VBoxContainerVirtual};
 use godot::prelude::*;
 use crate::db::DB;
-use crate::errors::{ArreResult, BoxedError};
+use crate::errors::{ArreError, ArreResult, BoxedError};
 use crate::godot_classes::singletons::logger::log_error;
 use crate::godot_classes::utils::{GdHolder};
 use crate::godot_classes::views::roll::view_roll::{RollState, RollView};
+use crate::idle::IdleDetector;
 use crate::item::{Item};
 use crate::item_details::{item_details_get, ItemDetails};
-use crate::item_stats::{item_stats_get, item_stats_update};
+use crate::list::ListId;
+use crate::pomodoro::{Pomodoro, pomodoro_config_get, PomodoroBreak, PomodoroEvent, PomodoroPhase};
+use crate::session::{Session, active_session_abandon, active_session_finish, active_session_get, active_session_pause, active_session_pause_revert, active_session_pomodoro_break, active_session_resume, active_session_start, ActiveSession};
+use crate::settings::{settings_get, settings_update};
 use crate::utils::format_duration;
 
+const UI_TEXT_QUEUE_POSITION: &str = "Item {position} of {total}";
+const UI_TEXT_QUEUE_NEXT: &str = ", next up: ";
+const UI_TEXT_PAUSE: &str = "Pause";
+const UI_TEXT_RESUME: &str = "Resume";
+const UI_TEXT_SKIP_BREAK: &str = "Skip break";
+const UI_TEXT_OVERTIME: &str = "Time's up! Overtime: {overtime}";
+const UI_TEXT_IDLE_RETURN: &str = "Welcome back! The session was paused while you were away for {idle}. Did you keep working?";
+const UI_TEXT_POMODORO_WORK: &str = "Pomodoro {number}: {left} of work left";
+const UI_TEXT_POMODORO_SHORT_BREAK: &str = "Short break: {left} left, {completed} pomodoros done";
+const UI_TEXT_POMODORO_LONG_BREAK: &str = "Long break: {left} left, {completed} pomodoros done";
 
 #[derive(GodotClass)]
 #[class(base=VBoxContainer)]
@@ -22,27 +36,79 @@ pub struct RollWorkAssignedSubview {
     pub item_name_label: GdHolder<Label>,
     pub item_description_label: GdHolder<Label>,
     pub session_time_label: GdHolder<Label>,
+    pub overtime_label: GdHolder<Label>,
+    pub pomodoro_label: GdHolder<Label>,
     pub elapsed_time_label: GdHolder<Label>,
+    pub queue_label: GdHolder<Label>,
     pub work_finish_button: GdHolder<Button>,
+    pub work_skip_button: GdHolder<Button>,
+    pub work_pause_button: GdHolder<Button>,
+    pub abandon_reason_line_edit: GdHolder<LineEdit>,
+    pub idle_check_button: GdHolder<CheckButton>,
+    pub idle_spin_box: GdHolder<SpinBox>,
+    pub idle_return_container: GdHolder<VBoxContainer>,
+    pub idle_return_label: GdHolder<Label>,
+    pub idle_count_button: GdHolder<Button>,
+    pub idle_discard_button: GdHolder<Button>,
+    pub session_end_audio_stream_player: GdHolder<AudioStreamPlayer>,
+    pub pomodoro_audio_stream_player: GdHolder<AudioStreamPlayer>,
 
     // cached external UI elements
     pub roll_view: GdHolder<RollView>,
 
     // state
+    pub queue: Vec<Item>,
+    pub list_id: Option<ListId>, // list the queue was rolled from, if it was a single one
+    pub queue_position: usize,
+    pub queue_time_worked: Duration,
+    pub last_session: Option<Session>, // last session recorded from the queue, its outcome is captured once the queue is done
     pub work_item: Item,
     pub work_item_details: ItemDetails,
-    pub work_started_timestamp: DateTime<Utc>
+    pub work_session: Option<ActiveSession>, // copy of the persisted active session
+    pub is_session_end_alerted: bool, // the alert goes off once, when session_duration runs out
+    pub pomodoro: Option<Pomodoro>, // None when no pomodoro cycle is set for the item or its list
+    pub idle_detector: IdleDetector,
+    pub idle_pause_start: Option<DateTime<Utc>>, // start of the pause made because the user went idle
+    pub was_window_focused: bool,
 }
 
 #[godot_api]
 impl RollWorkAssignedSubview {
 
-    pub fn set_state(&mut self, work_item: Item) -> ArreResult<()> {
-        self.work_item = work_item;
-        self.work_started_timestamp = Utc::now();
+    pub fn set_state(&mut self, queue: Vec<Item>, list_id: Option<ListId>) -> ArreResult<()> {
+        self.queue = queue;
+        self.list_id = list_id;
+        self.queue_position = 0;
+        self.queue_time_worked = Duration::zero();
+        self.last_session = None;
+        self.start_work_item()
+    }
 
-        let connection = &*DB.ok()?;
-        self.work_item_details = item_details_get(connection, self.work_item.get_id()?)?;
+    fn start_work_item(&mut self) -> ArreResult<()> {
+        self.work_item = self.queue
+            .get(self.queue_position)
+            .cloned()
+            .ok_or(ArreError::UnexpectedNone("RollWorkAssignedSubview::start_work_item".into()))?;
+        let item_id = self.work_item.get_id()?;
+        {
+            let connection = &*DB.ok()?;
+            // Continue the persisted session of the same item, e.g. one resumed after a restart
+            match active_session_get(connection)? {
+                Some(active) if active.item_id == item_id => {}
+                _ => active_session_start(connection, &ActiveSession::new(item_id, self.list_id, Utc::now()))?,
+            }
+            self.work_session = active_session_get(connection)?;
+            self.work_item_details = item_details_get(connection, item_id)?;
+            // A resumed session carries on with the pomodoros it already completed
+            self.pomodoro = pomodoro_config_get(connection, item_id, self.list_id)?
+                .zip(self.work_session.as_ref())
+                .map(|(config, session)| Pomodoro::new(config, session.pomodoros, session.active_duration(Utc::now())));
+            self.idle_detector = IdleDetector::new(settings_get(connection)?.idle_timeout, Utc::now());
+        }
+        self.refresh_idle_settings_display()?;
+        self.hide_idle_return()?;
+        self.is_session_end_alerted = false;
+        self.abandon_reason_line_edit.ok_mut()?.set_text("".into());
         self.refresh_display()?;
         Ok(())
     }
@@ -50,41 +116,303 @@ impl RollWorkAssignedSubview {
     pub fn refresh_display(&mut self) -> ArreResult<()> {
         self.item_name_label.ok_mut()?.set_text(self.work_item.name.clone().into());
         self.item_description_label.ok_mut()?.set_text(self.work_item.description.clone().into());
+        let is_queue = self.queue.len() > 1;
+        if is_queue {
+            let mut queue_text = UI_TEXT_QUEUE_POSITION
+                .replace("{position}", &(self.queue_position + 1).to_string())
+                .replace("{total}", &self.queue.len().to_string());
+            if let Some(next_item) = self.queue.get(self.queue_position + 1) {
+                queue_text = format!("{}{}{}", queue_text, UI_TEXT_QUEUE_NEXT, next_item.name);
+            }
+            self.queue_label.ok_mut()?.set_text(queue_text.into());
+        }
+        self.queue_label.ok_mut()?.set_visible(is_queue);
+        self.work_skip_button.ok_mut()?.set_visible(is_queue);
+        let is_paused = self.work_session.as_ref().is_some_and(|session| session.is_paused());
+        let is_break = self.pomodoro.as_ref().is_some_and(|pomodoro| pomodoro.is_break());
+        let pause_text = match (is_paused, is_break) {
+            (true, true) => UI_TEXT_SKIP_BREAK,
+            (true, false) => UI_TEXT_RESUME,
+            (false, _) => UI_TEXT_PAUSE,
+        };
+        self.work_pause_button.ok_mut()?.set_text(pause_text.into());
+        self.work_finish_button.ok_mut()?.set_disabled(is_paused);
         self.refresh_time_display()?;
         Ok(())
     }
 
     pub fn refresh_time_display(&mut self) -> ArreResult<()> {
-        let now = Utc::now();
-        let elapsed_time = now - self.work_started_timestamp;
+        let Some(session) = &self.work_session else { return Ok(()) };
+        // Pauses are excluded, so the time stands still while paused
+        let elapsed_time = session.active_duration(Utc::now());
         self.elapsed_time_label.ok_mut()?.set_text(format_duration(elapsed_time).into());
         if let Some(session_time) = self.work_item_details.session_duration {
-            let session_time = session_time - elapsed_time;
-            let session_time = if session_time < Duration::zero() { Duration::zero() } else { session_time };
-            self.session_time_label.ok_mut()?.set_text(format_duration(session_time).into());
+            let remaining_time = (session_time - elapsed_time).max(Duration::zero());
+            self.session_time_label.ok_mut()?.set_text(format_duration(remaining_time).into());
             self.session_time_label.ok_mut()?.set_visible(true);
+            let is_overtime = elapsed_time >= session_time;
+            if is_overtime {
+                let overtime = Session::overtime_of(elapsed_time, Some(session_time));
+                self.overtime_label.ok_mut()?.set_text(UI_TEXT_OVERTIME.replace("{overtime}", &format_duration(overtime)).into());
+                if !self.is_session_end_alerted {
+                    self.is_session_end_alerted = true;
+                    self.session_end_audio_stream_player.ok_mut()?.play();
+                }
+            }
+            self.overtime_label.ok_mut()?.set_visible(is_overtime);
         } else {
             self.session_time_label.ok_mut()?.set_visible(false);
+            self.overtime_label.ok_mut()?.set_visible(false);
+        }
+        if let Some(pomodoro) = &self.pomodoro {
+            let left = format_duration(pomodoro.time_left(elapsed_time, Utc::now()));
+            let template = match pomodoro.phase {
+                PomodoroPhase::Work{..} => UI_TEXT_POMODORO_WORK,
+                PomodoroPhase::Break{kind: PomodoroBreak::Short, ..} => UI_TEXT_POMODORO_SHORT_BREAK,
+                PomodoroPhase::Break{kind: PomodoroBreak::Long, ..} => UI_TEXT_POMODORO_LONG_BREAK,
+            };
+            let text = template
+                .replace("{number}", &(pomodoro.completed + 1).to_string())
+                .replace("{completed}", &pomodoro.completed.to_string())
+                .replace("{left}", &left);
+            self.pomodoro_label.ok_mut()?.set_text(text.into());
+        }
+        self.pomodoro_label.ok_mut()?.set_visible(self.pomodoro.is_some());
+        Ok(())
+    }
+
+    fn refresh_idle_settings_display(&mut self) -> ArreResult<()> {
+        let idle_timeout = self.idle_detector.timeout;
+        self.idle_spin_box.ok_mut()?.set_value_no_signal(idle_timeout.unwrap_or(Duration::hours(1)).num_minutes() as f64);
+        self.idle_spin_box.ok_mut()?.set_editable(idle_timeout.is_some());
+        self.idle_check_button.ok_mut()?.call_deferred(
+            // Deferred call, as it triggers `toggle` signal, which this class is handling
+            "set_pressed".into() , &[idle_timeout.is_some().to_variant()]
+        );
+        Ok(())
+    }
+
+    fn save_idle_settings(&mut self) -> ArreResult<()> {
+        let connection = &*DB.ok()?;
+        let mut settings = settings_get(connection)?;
+        settings.idle_timeout =
+            if self.idle_check_button.ok()?.is_pressed() {
+                Some(Duration::minutes(self.idle_spin_box.ok()?.get_value() as i64))
+            } else {
+                None
+            };
+        settings_update(connection, &settings)?;
+        self.idle_detector.timeout = settings.idle_timeout;
+        Ok(())
+    }
+
+    /// Pause the session once the user is idle, from the moment they were last active.
+    /// Sessions already paused are left alone, the user is considered idle once they resume.
+    fn detect_idle(&mut self) -> ArreResult<()> {
+        let is_window_focused = DisplayServer::singleton().window_is_focused();
+        if is_window_focused && !self.was_window_focused {
+            self.register_activity()?;
         }
+        self.was_window_focused = is_window_focused;
+
+        let Some(session) = &self.work_session else { return Ok(()) };
+        if session.is_paused() { return Ok(()) }
+        let Some(idle_since) = self.idle_detector.check(Utc::now()) else { return Ok(()) };
+        // The pause cannot overlap the previous ones, e.g. a pomodoro break which ended meanwhile
+        let pause_start = session.pauses
+            .iter()
+            .filter_map(|pause| pause.end.as_deref().copied())
+            .fold(idle_since.max(*session.start), |start, end| start.max(end));
+        {
+            let connection = &*DB.ok()?;
+            active_session_pause(connection, pause_start)?;
+            self.work_session = active_session_get(connection)?;
+        }
+        self.idle_pause_start = Some(pause_start);
+        self.refresh_display()
+    }
+
+    /// The user is back, ask them whether the time they were away counts, if the session was paused meanwhile
+    fn register_activity(&mut self) -> ArreResult<()> {
+        let now = Utc::now();
+        self.idle_detector.activity(now);
+        let Some(idle_pause_start) = self.idle_pause_start else { return Ok(()) };
+        if self.idle_return_container.ok()?.is_visible() { return Ok(()) }
+        let text = UI_TEXT_IDLE_RETURN.replace("{idle}", &format_duration(now - idle_pause_start));
+        self.idle_return_label.ok_mut()?.set_text(text.into());
+        self.idle_return_container.ok_mut()?.set_visible(true);
+        Ok(())
+    }
+
+    fn hide_idle_return(&mut self) -> ArreResult<()> {
+        self.idle_pause_start = None;
+        self.idle_return_container.ok_mut()?.set_visible(false);
         Ok(())
     }
 
     #[func]
-    fn on_work_finish_button_up(&mut self) {
+    fn on_idle_count_button_up(&mut self) {
+        match try {
+            {
+                let connection = &*DB.ok()?;
+                active_session_pause_revert(connection)?;
+                self.work_session = active_session_get(connection)?;
+            }
+            self.hide_idle_return()?;
+            self.refresh_display()?;
+        } {
+            Ok(_) => {}
+            Err::<_, BoxedError>(e) => log_error(e)
+        }
+    }
+
+    #[func]
+    fn on_idle_discard_button_up(&mut self) {
+        match try {
+            {
+                let connection = &*DB.ok()?;
+                active_session_resume(connection, Utc::now())?;
+                self.work_session = active_session_get(connection)?;
+            }
+            self.hide_idle_return()?;
+            self.refresh_display()?;
+        } {
+            Ok(_) => {}
+            Err::<_, BoxedError>(e) => log_error(e)
+        }
+    }
+
+    #[func]
+    fn on_idle_check_button_toggled(&mut self, checked: bool) {
         match try {
-            let time_worked = Utc::now() - self.work_started_timestamp;
-            // Update stats in db
+            self.idle_spin_box.ok_mut()?.set_editable(checked);
+            self.save_idle_settings()?;
+        } {
+            Ok(_) => {}
+            Err::<_, BoxedError>(e) => log_error(e)
+        }
+    }
+
+    #[func]
+    fn on_idle_spin_box_value_changed(&mut self, _value: f64) {
+        match try {
+            self.save_idle_settings()?;
+        } {
+            Ok(_) => {}
+            Err::<_, BoxedError>(e) => log_error(e)
+        }
+    }
+
+    /// Take a break once the work interval is over and get back to work after it, breaks are session pauses
+    fn advance_pomodoro(&mut self) -> ArreResult<()> {
+        let (Some(pomodoro), Some(session)) = (&mut self.pomodoro, &self.work_session) else { return Ok(()) };
+        let now = Utc::now();
+        let Some(event) = pomodoro.advance(session.active_duration(now), now) else { return Ok(()) };
+        {
             let connection = &*DB.ok()?;
-            let mut item_stats = item_stats_get(connection, self.work_item.get_id()?)?;
-            item_stats.times_worked += 1;
-            item_stats.time_spent = item_stats.time_spent + time_worked;
-            item_stats_update(connection, &item_stats)?;
-            self.roll_view.ok_mut()?.bind_mut().roll_state_change_request(RollState::WorkFinished(time_worked));
+            match event {
+                PomodoroEvent::BreakStarted(_) => active_session_pomodoro_break(connection, now)?,
+                PomodoroEvent::BreakEnded => active_session_resume(connection, now)?,
+            }
+            self.work_session = active_session_get(connection)?;
+        }
+        self.pomodoro_audio_stream_player.ok_mut()?.play();
+        self.refresh_display()
+    }
+
+    /// Finish the session at the moment session_duration was worked, if the item is set to do so
+    fn auto_finish_at_limit(&mut self) -> ArreResult<()> {
+        let (Some(session), Some(session_time)) = (&self.work_session, self.work_item_details.session_duration) else {
+            return Ok(());
+        };
+        if !self.work_item_details.is_auto_finish || session.is_paused() {
+            return Ok(());
+        }
+        match session.limit_reached_at(session_time) {
+            Some(limit_reached) if limit_reached <= Utc::now() => self.finish_work_item(limit_reached),
+            _ => Ok(()),
+        }
+    }
+
+    fn finish_work_item(&mut self, end: DateTime<Utc>) -> ArreResult<()> {
+        let session = active_session_finish(&*DB.ok()?, end)?;
+        self.work_session = None;
+        self.queue_time_worked += session.duration;
+        self.last_session = Some(session);
+        self.advance_queue()
+    }
+
+    #[func]
+    fn on_work_finish_button_up(&mut self) {
+        match try {
+            self.finish_work_item(Utc::now())?;
+        } {
+            Ok(_) => {}
+            Err::<_, BoxedError>(e) => log_error(e)
+        }
+    }
+
+    #[func]
+    fn on_work_pause_button_up(&mut self) {
+        match try {
+            let is_paused = self.work_session.as_ref().is_some_and(|session| session.is_paused());
+            {
+                let connection = &*DB.ok()?;
+                if is_paused {
+                    active_session_resume(connection, Utc::now())?;
+                    // Resuming during a pomodoro break cuts it short
+                    if let (Some(pomodoro), Some(session)) = (&mut self.pomodoro, &self.work_session) {
+                        if pomodoro.is_break() {
+                            pomodoro.skip_break(session.active_duration(Utc::now()));
+                        }
+                    }
+                } else {
+                    active_session_pause(connection, Utc::now())?;
+                }
+                self.work_session = active_session_get(connection)?;
+            }
+            // Resuming by hand leaves the time away uncounted
+            self.hide_idle_return()?;
+            self.refresh_display()?;
+        } {
+            Ok(_) => {}
+            Err::<_, BoxedError>(e) => log_error(e)
+        }
+    }
+
+    /// Record the item being worked on as cancelled or skipped, with the reason given if any
+    pub fn abandon_work_item(&mut self) -> ArreResult<()> {
+        if self.work_session.take().is_none() { return Ok(()) }
+        let reason = self.abandon_reason_line_edit.ok()?.get_text().to_string();
+        let reason = if reason.trim().is_empty() { None } else { Some(reason.trim().to_string()) };
+        active_session_abandon(&*DB.ok()?, Utc::now(), reason)?;
+        Ok(())
+    }
+
+    #[func]
+    fn on_work_skip_button_up(&mut self) {
+        match try {
+            self.abandon_work_item()?;
+            self.advance_queue()?;
         } {
             Ok(_) => {}
             Err::<_, BoxedError>(e) => log_error(e)
         }
     }
+
+    /// Move on to the next item in the queue, or finish the roll once the queue is exhausted
+    fn advance_queue(&mut self) -> ArreResult<()> {
+        self.queue_position += 1;
+        if self.queue_position < self.queue.len() {
+            self.start_work_item()
+        } else {
+            self.roll_view.ok_mut()?.bind_mut().roll_state_change_request(RollState::WorkFinished{
+                time_worked: self.queue_time_worked,
+                session: self.last_session.take(),
+            });
+            Ok(())
+        }
+    }
 }
 
 #[godot_api]
@@ -97,16 +425,40 @@ impl VBoxContainerVirtual for RollWorkAssignedSubview {
             item_name_label: GdHolder::default(),
             item_description_label: GdHolder::default(),
             session_time_label: GdHolder::default(),
+            overtime_label: GdHolder::default(),
+            pomodoro_label: GdHolder::default(),
             elapsed_time_label: GdHolder::default(),
+            queue_label: GdHolder::default(),
             work_finish_button: GdHolder::default(),
+            work_skip_button: GdHolder::default(),
+            work_pause_button: GdHolder::default(),
+            abandon_reason_line_edit: GdHolder::default(),
+            idle_check_button: GdHolder::default(),
+            idle_spin_box: GdHolder::default(),
+            idle_return_container: GdHolder::default(),
+            idle_return_label: GdHolder::default(),
+            idle_count_button: GdHolder::default(),
+            idle_discard_button: GdHolder::default(),
+            session_end_audio_stream_player: GdHolder::default(),
+            pomodoro_audio_stream_player: GdHolder::default(),
 
             // cached external UI elements
             roll_view: GdHolder::default(),
 
             // state
+            queue: vec![],
+            list_id: None,
+            queue_position: 0,
+            queue_time_worked: Duration::zero(),
+            last_session: None,
             work_item: Item::default(),
             work_item_details: ItemDetails::default(),
-            work_started_timestamp: Utc::now(),
+            work_session: None,
+            is_session_end_alerted: false,
+            pomodoro: None,
+            idle_detector: IdleDetector::new(None, Utc::now()),
+            idle_pause_start: None,
+            was_window_focused: true,
         }
     }
     fn ready(&mut self) {
@@ -117,12 +469,50 @@ impl VBoxContainerVirtual for RollWorkAssignedSubview {
             self.item_name_label = GdHolder::from_path(base, "VBoxContainer/ItemNameLabel");
             self.item_description_label = GdHolder::from_path(base, "VBoxContainer/ItemDescriptionLabel");
             self.session_time_label = GdHolder::from_path(base, "VBoxContainer/SessionTimeLabel");
+            self.overtime_label = GdHolder::from_path(base, "VBoxContainer/OvertimeLabel");
+            self.pomodoro_label = GdHolder::from_path(base, "VBoxContainer/PomodoroLabel");
             self.elapsed_time_label = GdHolder::from_path(base, "VBoxContainer/ElapsedTimeLabel");
+            self.queue_label = GdHolder::from_path(base, "VBoxContainer/QueueLabel");
             self.work_finish_button = GdHolder::from_path(base, "VBoxContainer/BottomMarginContainer/WorkFinishButton");
             self.work_finish_button.ok_mut()?.connect(
                 "button_up".into(),
                 base.callable("on_work_finish_button_up"),
             );
+            self.work_pause_button = GdHolder::from_path(base, "VBoxContainer/WorkPauseButton");
+            self.work_pause_button.ok_mut()?.connect(
+                "button_up".into(),
+                base.callable("on_work_pause_button_up"),
+            );
+            self.abandon_reason_line_edit = GdHolder::from_path(base, "VBoxContainer/AbandonReasonLineEdit");
+            self.work_skip_button = GdHolder::from_path(base, "VBoxContainer/WorkSkipButton");
+            self.work_skip_button.ok_mut()?.connect(
+                "button_up".into(),
+                base.callable("on_work_skip_button_up"),
+            );
+            self.idle_check_button = GdHolder::from_path(base, "VBoxContainer/IdleHBoxContainer/CheckButton");
+            self.idle_check_button.ok_mut()?.connect(
+                "toggled".into(),
+                base.callable("on_idle_check_button_toggled"),
+            );
+            self.idle_spin_box = GdHolder::from_path(base, "VBoxContainer/IdleHBoxContainer/SpinBox");
+            self.idle_spin_box.ok_mut()?.connect(
+                "value_changed".into(),
+                base.callable("on_idle_spin_box_value_changed"),
+            );
+            self.idle_return_container = GdHolder::from_path(base, "VBoxContainer/IdleReturnVBoxContainer");
+            self.idle_return_label = GdHolder::from_path(base, "VBoxContainer/IdleReturnVBoxContainer/IdleReturnLabel");
+            self.idle_count_button = GdHolder::from_path(base, "VBoxContainer/IdleReturnVBoxContainer/HBoxContainer/CountButton");
+            self.idle_count_button.ok_mut()?.connect(
+                "button_up".into(),
+                base.callable("on_idle_count_button_up"),
+            );
+            self.idle_discard_button = GdHolder::from_path(base, "VBoxContainer/IdleReturnVBoxContainer/HBoxContainer/DiscardButton");
+            self.idle_discard_button.ok_mut()?.connect(
+                "button_up".into(),
+                base.callable("on_idle_discard_button_up"),
+            );
+            self.session_end_audio_stream_player = GdHolder::from_path(base, "SessionEndAudioStreamPlayer");
+            self.pomodoro_audio_stream_player = GdHolder::from_path(base, "PomodoroAudioStreamPlayer");
 
             // cached external UI elements
             // self.roll_view is set from RollView::ready()
@@ -137,6 +527,20 @@ impl VBoxContainerVirtual for RollWorkAssignedSubview {
             if godot::engine::Engine::singleton().is_editor_hint() { return; }
             if self.base.is_visible() {
                 self.refresh_time_display()?;
+                self.detect_idle()?;
+                self.advance_pomodoro()?;
+                self.auto_finish_at_limit()?;
+            }
+        } {
+            Ok(_) => {}
+            Err::<_, BoxedError>(e) => log_error(e),
+        }
+    }
+
+    fn input(&mut self, _event: Gd<InputEvent>) {
+        match try {
+            if self.base.is_visible() {
+                self.register_activity()?;
             }
         } {
             Ok(_) => {}
@@ -1,10 +1,14 @@
-use godot::engine::{Button, VBoxContainer, VBoxContainerVirtual};
+use godot::engine::{Button, CheckButton, Label, OptionButton, SpinBox, TextEdit, VBoxContainer, VBoxContainerVirtual};
 use godot::prelude::*;
-use crate::errors::{BoxedError};
+use crate::db::DB;
+use crate::errors::{ArreResult, BoxedError};
 use crate::godot_classes::singletons::logger::log_error;
 use crate::godot_classes::utils::{GdHolder};
 use crate::godot_classes::views::roll::view_roll::{RollState, RollView};
+use crate::item::item_get;
+use crate::session::{Session, session_outcome_update, SessionOutcome};
 
+const UI_TEXT_OUTCOME_TITLE: &str = "How did \"{item}\" go?";
 
 #[derive(GodotClass)]
 #[class(base=VBoxContainer)]
@@ -15,17 +19,91 @@ pub struct RollWorkFinishedSubview {
     // cached internal UI elements
     roll_again_button: GdHolder<Button>,
     close_button: GdHolder<Button>,
+    outcome_container: GdHolder<VBoxContainer>,
+    outcome_title_label: GdHolder<Label>,
+    notes_text_edit: GdHolder<TextEdit>,
+    satisfaction_option_button: GdHolder<OptionButton>,
+    difficulty_option_button: GdHolder<OptionButton>,
+    progress_check_button: GdHolder<CheckButton>,
+    progress_spin_box: GdHolder<SpinBox>,
+    item_finished_check_button: GdHolder<CheckButton>,
 
     // cached external UI elements
     pub roll_view: GdHolder<RollView>,
+
+    // state
+    session: Option<Session>, // the outcome is captured for this session
 }
 
 #[godot_api]
 impl RollWorkFinishedSubview {
 
+    pub fn set_state(&mut self, session: Option<Session>) -> ArreResult<()> {
+        self.session = session;
+        let Some(session) = &self.session else {
+            self.outcome_container.ok_mut()?.set_visible(false);
+            return Ok(());
+        };
+        let item = item_get(&*DB.ok()?, session.item_id)?;
+        self.outcome_title_label.ok_mut()?.set_text(UI_TEXT_OUTCOME_TITLE.replace("{item}", &item.name).into());
+        self.notes_text_edit.ok_mut()?.set_text("".into());
+        self.satisfaction_option_button.ok_mut()?.select(0);
+        self.difficulty_option_button.ok_mut()?.select(0);
+        self.progress_check_button.ok_mut()?.call_deferred("set_pressed".into(), &[false.to_variant()]);
+        self.progress_spin_box.ok_mut()?.set_editable(false);
+        self.item_finished_check_button.ok_mut()?.set_pressed(false);
+        self.outcome_container.ok_mut()?.set_visible(true);
+        Ok(())
+    }
+
+    pub fn set_roll_again_enabled(&mut self, is_enabled: bool) -> ArreResult<()> {
+        self.roll_again_button.ok_mut()?.set_disabled(!is_enabled);
+        Ok(())
+    }
+
+    /// Store the outcome, if anything was filled in
+    fn save_outcome(&mut self) -> ArreResult<()> {
+        let Some(session) = self.session.take() else { return Ok(()) };
+        let notes = self.notes_text_edit.ok()?.get_text().to_string();
+        let outcome = SessionOutcome {
+            notes: if notes.trim().is_empty() { None } else { Some(notes) },
+            satisfaction: Self::selected_rating(self.satisfaction_option_button.ok()?),
+            difficulty: Self::selected_rating(self.difficulty_option_button.ok()?),
+            progress: if self.progress_check_button.ok()?.is_pressed() {
+                Some(self.progress_spin_box.ok()?.get_value() as u8)
+            } else {
+                None
+            },
+            is_item_finished: self.item_finished_check_button.ok()?.is_pressed(),
+        };
+        if outcome != SessionOutcome::default() {
+            session_outcome_update(&*DB.ok()?, session.get_id()?, &outcome)?;
+        }
+        Ok(())
+    }
+
+    // The first option stands for no rating, the others for ratings from 1 to 5
+    fn selected_rating(option_button: &OptionButton) -> Option<u8> {
+        match option_button.get_selected() {
+            rating @ 1..=5 => Some(rating as u8),
+            _ => None,
+        }
+    }
+
+    #[func]
+    fn on_progress_check_button_toggled(&mut self, pressed: bool) {
+        match try {
+            self.progress_spin_box.ok_mut()?.set_editable(pressed);
+        } {
+            Ok(_) => {}
+            Err::<_, BoxedError>(e) => log_error(e)
+        }
+    }
+
     #[func]
     fn on_close_button_up(&mut self) {
         match try {
+            self.save_outcome()?;
             self.roll_view.ok_mut()?.bind_mut().close_dialog();
         } {
             Ok(_) => {}
@@ -36,7 +114,11 @@ impl RollWorkFinishedSubview {
     #[func]
     fn on_roll_again_button_up(&mut self) {
         match try {
-            self.roll_view.ok_mut()?.bind_mut().roll_state_change_request(RollState::ItemsSelection)
+            self.save_outcome()?;
+            // Counts as a reroll, see RollView::reroll_consume
+            let mut roll_view = self.roll_view.ok_mut()?.bind_mut();
+            roll_view.reroll_consume()?;
+            roll_view.roll_state_change_request(RollState::ItemsSelection);
         } {
             Ok(_) => {}
             Err::<_, BoxedError>(e) => log_error(e)
@@ -53,9 +135,20 @@ impl VBoxContainerVirtual for RollWorkFinishedSubview {
             // cached internal UI elements
             roll_again_button: GdHolder::default(),
             close_button: GdHolder::default(),
+            outcome_container: GdHolder::default(),
+            outcome_title_label: GdHolder::default(),
+            notes_text_edit: GdHolder::default(),
+            satisfaction_option_button: GdHolder::default(),
+            difficulty_option_button: GdHolder::default(),
+            progress_check_button: GdHolder::default(),
+            progress_spin_box: GdHolder::default(),
+            item_finished_check_button: GdHolder::default(),
 
             // cached external UI elements
             roll_view: GdHolder::default(),
+
+            // state
+            session: None,
         }
     }
     fn ready(&mut self) {
@@ -73,6 +166,18 @@ impl VBoxContainerVirtual for RollWorkFinishedSubview {
                 "button_up".into(),
                 base.callable("on_close_button_up"),
             );
+            self.outcome_container = GdHolder::from_path(base, "OutcomeVBoxContainer");
+            self.outcome_title_label = GdHolder::from_path(base, "OutcomeVBoxContainer/TitleLabel");
+            self.notes_text_edit = GdHolder::from_path(base, "OutcomeVBoxContainer/NotesTextEdit");
+            self.satisfaction_option_button = GdHolder::from_path(base, "OutcomeVBoxContainer/RatingsHBoxContainer/SatisfactionOptionButton");
+            self.difficulty_option_button = GdHolder::from_path(base, "OutcomeVBoxContainer/RatingsHBoxContainer/DifficultyOptionButton");
+            self.progress_check_button = GdHolder::from_path(base, "OutcomeVBoxContainer/ProgressHBoxContainer/CheckButton");
+            self.progress_check_button.ok_mut()?.connect(
+                "toggled".into(),
+                base.callable("on_progress_check_button_toggled"),
+            );
+            self.progress_spin_box = GdHolder::from_path(base, "OutcomeVBoxContainer/ProgressHBoxContainer/SpinBox");
+            self.item_finished_check_button = GdHolder::from_path(base, "OutcomeVBoxContainer/ItemFinishedCheckButton");
 
             // cached external UI elements
             // self.roll_view is set from RollView::ready()
@@ -1,21 +1,32 @@
-use chrono::{Duration};
-use godot::engine::{Panel, PanelVirtual, Button, Label};
+use chrono::{Duration, Local};
+use godot::engine::{Panel, PanelVirtual, Button, Label, CheckButton};
 use godot::prelude::*;
-use crate::errors::{ArreResult, BoxedError};
+use crate::db::DB;
+use crate::errors::{ArreError, ArreResult, BoxedError};
 use crate::godot_classes::singletons::logger::log_error;
 use crate::godot_classes::utils::{GdHolder};
-use crate::godot_classes::views::roll::subview_rolling::RollRollingSubview;
+use crate::godot_classes::views::roll::subview_rolling::{Presentation, RollRollingSubview};
 use crate::godot_classes::views::roll::subview_selection::RollSelectionSubview;
 use crate::godot_classes::views::roll::subview_work_assigned::RollWorkAssignedSubview;
 use crate::godot_classes::views::roll::subview_work_finished::RollWorkFinishedSubview;
-use crate::item::{Item};
+use crate::item::{Item, ItemId};
 use crate::list::{List};
+use crate::list_details::{BudgetPeriod, list_details_get};
+use crate::reroll::{reroll_record, reroll_tokens_left};
+use crate::roll::RollSource;
+use crate::session::{Session};
+
+const UI_TEXT_REROLLS_LEFT_DAY: &str = "Rerolls left today: ";
+const UI_TEXT_REROLLS_LEFT_WEEK: &str = "Rerolls left this week: ";
+const UI_TEXT_NO_REROLLS_LEFT: &str = "No rerolls left, use the override to reroll anyway";
+const UI_TEXT_ALL_ITEMS: &str = "All Items";
 
 pub enum RollState {
     ItemsSelection,
-    Rolling(Vec<Item>),
-    WorkAssigned{item: Item},
-    WorkFinished(Duration),
+    /// `queue` is already decided, the animation only presents its head
+    Rolling{eligible: Vec<Item>, queue: Vec<Item>, presentation: Presentation},
+    WorkAssigned{queue: Vec<Item>},
+    WorkFinished{time_worked: Duration, session: Option<Session>},
 }
 
 #[derive(GodotClass)]
@@ -26,7 +37,9 @@ pub struct RollView {
 
     // cached UI elements
     work_cancel_button: GdHolder<Button>,
+    reroll_override_check_button: GdHolder<CheckButton>,
     list_name_label: GdHolder<Label>,
+    reroll_tokens_label: GdHolder<Label>,
     // subviews
     selection_subview: GdHolder<RollSelectionSubview>,
     rolling_subview: GdHolder<RollRollingSubview>,
@@ -34,7 +47,8 @@ pub struct RollView {
     work_finished_subview: GdHolder<RollWorkFinishedSubview>,
 
     // state
-    list: List,
+    source: RollSource,
+    title: String,
     pub roll_state: RollState,
     roll_state_requested: Option<RollState>,
 }
@@ -44,21 +58,61 @@ impl RollView {
     #[signal]
     fn dialog_closed();
 
-    pub fn set_list(&mut self, list: List) {
+    pub fn set_list(&mut self, list: List) -> ArreResult<()> {
+        self.set_lists(vec![list])
+    }
+
+    /// Roll on the union of the lists, each weighted by its roll weight
+    pub fn set_lists(&mut self, lists: Vec<List>) -> ArreResult<()> {
+        let list_ids = lists.iter().map(|list| list.get_id()).collect::<ArreResult<Vec<_>>>()?;
+        self.title = lists.iter().map(|list| list.name.as_str()).collect::<Vec<_>>().join(" + ");
+        self.set_source(RollSource::Lists(list_ids));
+        Ok(())
+    }
+
+    pub fn set_all_items(&mut self) {
+        self.title = UI_TEXT_ALL_ITEMS.to_string();
+        self.set_source(RollSource::AllItems);
+    }
+
+    /// Roll among a set of items picked elsewhere, e.g. by a report
+    pub fn set_items(&mut self, title: String, item_ids: Vec<ItemId>) {
+        self.title = title;
+        self.set_source(RollSource::Items(item_ids));
+    }
+
+    /// Continue working on the item of a session interrupted by the app being closed
+    pub fn resume_session(&mut self, item: Item, list: Option<List>) -> ArreResult<()> {
+        match list {
+            Some(list) => {
+                self.title = list.name.clone();
+                self.source = RollSource::Lists(vec![list.get_id()?]);
+            }
+            None => {
+                self.title = UI_TEXT_ALL_ITEMS.to_string();
+                self.source = RollSource::AllItems;
+            }
+        }
+        self.roll_state_requested = Some(RollState::WorkAssigned{queue: vec![item]});
+        Ok(())
+    }
+
+    fn set_source(&mut self, source: RollSource) {
         self.roll_state_requested = Some(RollState::ItemsSelection);
-        self.list = list;
+        self.source = source;
     }
 
     #[func]
     pub fn refresh_view(&mut self){
         match try {
-            self.list_name_label.ok_mut()?.set_text(self.list.name.clone().into());
+            self.list_name_label.ok_mut()?.set_text(self.title.clone().into());
+            self.refresh_reroll_tokens_display()?;
             self.hide_all_subviews()?;
             match &self.roll_state {
                 RollState::ItemsSelection => self.selection_subview.ok_mut()?.bind_mut().set_visible(true),
-                RollState::Rolling(_items_list) => self.rolling_subview.ok_mut()?.bind_mut().set_visible(true),
+                RollState::Rolling{..} => self.rolling_subview.ok_mut()?.bind_mut().set_visible(true),
                 RollState::WorkAssigned{..} => self.work_assigned_subview.ok_mut()?.bind_mut().set_visible(true),
-                RollState::WorkFinished(_duration) => self.work_finished_subview.ok_mut()?.bind_mut().set_visible(true),
+                RollState::WorkFinished{..} => self.work_finished_subview.ok_mut()?.bind_mut().set_visible(true),
             }
         } {
             Ok(_) => {}
@@ -66,6 +120,41 @@ impl RollView {
         }
     }
 
+    /// Reroll budgets are set per list, so they are only displayed when rolling on a single list.
+    /// Once the budget is exhausted, the buttons rolling again are disabled unless the override is on.
+    fn refresh_reroll_tokens_display(&mut self) -> ArreResult<()> {
+        let (period, tokens_left) = match self.source.single_list() {
+            Some(list_id) => {
+                let connection = &*DB.ok()?;
+                let period = list_details_get(connection, list_id)?
+                    .reroll_budget
+                    .map(|budget| budget.period);
+                (period, reroll_tokens_left(connection, list_id, Local::now())?)
+            }
+            None => (None, None),
+        };
+        let is_exhausted = tokens_left == Some(0) && !self.reroll_override_check_button.ok()?.is_pressed();
+        let is_cancel_reroll = matches!(self.roll_state, RollState::WorkAssigned{..});
+        let cancel_button = self.work_cancel_button.ok_mut()?;
+        cancel_button.set_disabled(is_exhausted && is_cancel_reroll);
+        cancel_button.set_tooltip_text(if is_exhausted && is_cancel_reroll { UI_TEXT_NO_REROLLS_LEFT } else { "" }.into());
+        self.work_finished_subview.ok_mut()?.bind_mut().set_roll_again_enabled(!is_exhausted)?;
+
+        let label = self.reroll_tokens_label.ok_mut()?;
+        match (period, tokens_left) {
+            (Some(period), Some(tokens_left)) => {
+                let text = match period {
+                    BudgetPeriod::Day => UI_TEXT_REROLLS_LEFT_DAY,
+                    BudgetPeriod::Week => UI_TEXT_REROLLS_LEFT_WEEK,
+                };
+                label.set_text(format!("{}{}", text, tokens_left).into());
+                label.set_visible(true);
+            }
+            _ => label.set_visible(false),
+        }
+        Ok(())
+    }
+
     fn hide_all_subviews(&mut self) -> ArreResult<()> {
         self.selection_subview.ok_mut()?.bind_mut().set_visible(false);
         self.rolling_subview.ok_mut()?.bind_mut().set_visible(false);
@@ -76,7 +165,53 @@ impl RollView {
 
     #[func]
     fn on_work_finish_button_up(&mut self) {
-        self.roll_state_requested = Some(RollState::WorkFinished(Duration::zero()));
+        self.roll_state_requested = Some(RollState::WorkFinished{time_worked: Duration::zero(), session: None});
+    }
+
+    #[func]
+    fn on_work_cancel_button_up(&mut self) {
+        match try {
+            if let RollState::WorkAssigned{..} = self.roll_state {
+                // The token is only consumed once the work was abandoned, so a failure does not waste it
+                self.reroll_check()?;
+                self.work_assigned_subview.ok_mut()?.bind_mut().abandon_work_item()?;
+                self.reroll_consume()?;
+            }
+            self.close_dialog();
+        } {
+            Ok(_) => {}
+            Err::<_, BoxedError>(e) => log_error(e)
+        }
+    }
+
+    #[func]
+    fn on_reroll_override_check_button_toggled(&mut self, _pressed: bool) {
+        match try {
+            self.refresh_reroll_tokens_display()?;
+        } {
+            Ok(_) => {}
+            Err::<_, BoxedError>(e) => log_error(e)
+        }
+    }
+
+    /// Rolling again counts as a reroll and consumes a token from the list budget, whether the assigned item
+    /// is dismissed or its session was finished first, as both let the user roll until something fun comes up.
+    /// Rolls made across several lists are not limited.
+    pub fn reroll_consume(&mut self) -> ArreResult<()> {
+        self.reroll_check()?;
+        let Some(list_id) = self.source.single_list() else { return Ok(()) };
+        let is_override = self.reroll_override_check_button.ok()?.is_pressed();
+        reroll_record(&*DB.ok()?, list_id, is_override)
+    }
+
+    /// Fail if rolling again would need a token while none are left, without consuming one
+    pub fn reroll_check(&self) -> ArreResult<()> {
+        let Some(list_id) = self.source.single_list() else { return Ok(()) };
+        let is_override = self.reroll_override_check_button.ok()?.is_pressed();
+        if !is_override && reroll_tokens_left(&*DB.ok()?, list_id, Local::now())? == Some(0) {
+            return Err(ArreError::RerollBudgetExhausted().into());
+        }
+        Ok(())
     }
 
     #[func]
@@ -87,8 +222,14 @@ impl RollView {
 
     #[func]
     fn on_roll_again_button_up(&mut self) {
-        self.roll_state = RollState::ItemsSelection;
-        self.refresh_view();
+        match try {
+            self.reroll_consume()?;
+            // Through a request, so the selection is refreshed with what changed since the last roll
+            self.roll_state_requested = Some(RollState::ItemsSelection);
+        } {
+            Ok(_) => {}
+            Err::<_, BoxedError>(e) => log_error(e)
+        }
     }
 
     pub fn roll_state_change_request(&mut self, new_state: RollState) {
@@ -104,14 +245,17 @@ impl PanelVirtual for RollView {
 
             // cached UI elements
             work_cancel_button: GdHolder::default(),
+            reroll_override_check_button: GdHolder::default(),
             list_name_label: GdHolder::default(),
+            reroll_tokens_label: GdHolder::default(),
             // subviews
             selection_subview: GdHolder::default(),
             rolling_subview: GdHolder::default(),
             work_assigned_subview: GdHolder::default(),
             work_finished_subview: GdHolder::default(),
 
-            list: List::default(),
+            source: RollSource::AllItems,
+            title: String::new(),
             roll_state: RollState::ItemsSelection,
             roll_state_requested: None,
         }
@@ -123,9 +267,15 @@ impl PanelVirtual for RollView {
             self.work_cancel_button = GdHolder::from_path(base, "WorkCancelButton");
             self.work_cancel_button.ok_mut()?.connect(
                 "button_up".into(),
-                base.callable("close_dialog"),
+                base.callable("on_work_cancel_button_up"),
+            );
+            self.reroll_override_check_button = GdHolder::from_path(base, "RerollOverrideCheckButton");
+            self.reroll_override_check_button.ok_mut()?.connect(
+                "toggled".into(),
+                base.callable("on_reroll_override_check_button_toggled"),
             );
             self.list_name_label = GdHolder::from_path(base, "VBoxContainer/TopMarginContainer/ListNameLabel");
+            self.reroll_tokens_label = GdHolder::from_path(base, "VBoxContainer/RerollTokensLabel");
             // subviews
             self.selection_subview = GdHolder::from_path(base, "VBoxContainer/SelectionSubview");
             self.selection_subview.ok_mut()?.bind_mut().roll_view = GdHolder::from_gd(base.share());
@@ -147,17 +297,19 @@ impl PanelVirtual for RollView {
                 match &self.roll_state {
                     RollState::ItemsSelection => {
                         let mut selection_subview = self.selection_subview.ok_mut()?.bind_mut();
-                        selection_subview.set_state(self.list.get_id()?);
+                        selection_subview.set_state(self.source.clone());
                         selection_subview.refresh_display();
                     },
-                    RollState::Rolling(eligible_items) => {
-                        self.rolling_subview.ok_mut()?.bind_mut().animate(eligible_items.clone())?;
+                    RollState::Rolling{eligible, queue, presentation} => {
+                        self.rolling_subview.ok_mut()?.bind_mut().animate(eligible.clone(), queue.clone(), *presentation)?;
                     },
-                    RollState::WorkAssigned{item} => {
+                    RollState::WorkAssigned{queue} => {
                         let mut work_subview = self.work_assigned_subview.ok_mut()?.bind_mut();
-                        work_subview.set_state(item.clone())?;
+                        work_subview.set_state(queue.clone(), self.source.single_list())?;
+                    }
+                    RollState::WorkFinished{session, ..} => {
+                        self.work_finished_subview.ok_mut()?.bind_mut().set_state(session.clone())?;
                     }
-                    RollState::WorkFinished(..) => {}
                 };
                 self.refresh_view();
             };
@@ -0,0 +1,169 @@
+use chrono::Local;
+use godot::engine::{Panel, PanelVirtual, Button, Label, OptionButton, VBoxContainer};
+use godot::prelude::*;
+use crate::db::DB;
+use crate::errors::{ArreResult, BoxedError};
+use crate::godot_classes::calendar_heatmap::CalendarHeatmap;
+use crate::godot_classes::singletons::logger::log_error;
+use crate::godot_classes::utils::{GdHolder, ItemFilterChoices};
+use crate::item::ItemFilter;
+use crate::time_series::{activity_calendar_get, day_sessions_get, DaySession, TimeBucket};
+use crate::utils::format_duration;
+
+const UI_TEXT_DATE_FORMAT: &str = "%A %Y-%m-%d";
+const UI_TEXT_TIME_FORMAT: &str = "%H:%M";
+const UI_TEXT_DAY: &str = "{date}: {time_spent} in {sessions} sessions";
+const UI_TEXT_NO_DAY: &str = "Click a day to see its sessions";
+const UI_TEXT_SESSION: &str = "{start} - {end}  {item}: {duration}";
+
+/// Calendar of the time worked per day over the last year, with the sessions of the selected day
+#[derive(GodotClass)]
+#[class(base=Panel)]
+pub struct ActivityView {
+    #[base]
+    base: Base<Panel>,
+
+    // cached internal UI elements
+    pub list_option_button: GdHolder<OptionButton>,
+    pub tag_option_button: GdHolder<OptionButton>,
+    pub heatmap: GdHolder<CalendarHeatmap>,
+    pub day_label: GdHolder<Label>,
+    pub sessions_container: GdHolder<VBoxContainer>,
+    pub session_rows: Vec<Gd<Label>>,
+    pub close_button: GdHolder<Button>,
+
+    // state
+    filter_choices: ItemFilterChoices,
+    filter: ItemFilter,
+    days: Vec<TimeBucket>,
+    day_sessions: Vec<DaySession>,
+}
+
+#[godot_api]
+impl ActivityView {
+    #[signal]
+    fn dialog_closed();
+
+    /// Reload the lists and tags to filter on, resetting the filters
+    pub fn refresh_full(&mut self) -> ArreResult<()> {
+        self.filter_choices.fill(self.list_option_button.ok_mut()?, self.tag_option_button.ok_mut()?)?;
+        self.refresh_calendar()
+    }
+
+    fn refresh_calendar(&mut self) -> ArreResult<()> {
+        self.filter = self.filter_choices.selected(self.list_option_button.ok()?, self.tag_option_button.ok()?)?;
+        self.days = activity_calendar_get(&*DB.ok()?, &self.filter, Local::now().date_naive())?;
+        self.day_sessions.clear();
+        let values = self.days.iter().map(|day| day.time_spent.num_seconds() as f64).collect();
+        self.heatmap.ok_mut()?.bind_mut().set_values(values);
+        self.day_label.ok_mut()?.set_text(UI_TEXT_NO_DAY.into());
+        self.refresh_sessions_display()
+    }
+
+    fn refresh_sessions_display(&mut self) -> ArreResult<()> {
+        self.session_rows.drain(..).for_each(|mut row| row.queue_free());
+        for day_session in self.day_sessions.iter() {
+            let session = &day_session.session;
+            let mut label = Label::new_alloc();
+            let text = UI_TEXT_SESSION
+                .replace("{start}", &session.start.with_timezone(&Local).format(UI_TEXT_TIME_FORMAT).to_string())
+                .replace("{end}", &session.end.with_timezone(&Local).format(UI_TEXT_TIME_FORMAT).to_string())
+                .replace("{item}", &day_session.item_name)
+                .replace("{duration}", &format_duration(session.duration));
+            label.set_text(text.into());
+            self.sessions_container.ok_mut()?.add_child(label.share().upcast());
+            self.session_rows.push(label);
+        }
+        Ok(())
+    }
+
+    #[func]
+    fn on_heatmap_day_selected(&mut self, day: i64) {
+        match try {
+            // The heatmap is still bound while emitting, so the day is looked up in the state of the view
+            let Some(day) = self.days.get(day as usize).cloned() else { return };
+            self.day_sessions = day_sessions_get(&*DB.ok()?, &self.filter, day.start)?;
+            let text = UI_TEXT_DAY
+                .replace("{date}", &day.start.format(UI_TEXT_DATE_FORMAT).to_string())
+                .replace("{time_spent}", &format_duration(day.time_spent))
+                .replace("{sessions}", &day.sessions_count.to_string());
+            self.day_label.ok_mut()?.set_text(text.into());
+            self.refresh_sessions_display()?;
+        } {
+            Ok(_) => {}
+            Err::<_, BoxedError>(e) => log_error(e)
+        }
+    }
+
+    #[func]
+    fn on_filter_option_button_item_selected(&mut self, _index: i64) {
+        match try {
+            self.refresh_calendar()?;
+        } {
+            Ok(_) => {}
+            Err::<_, BoxedError>(e) => log_error(e)
+        }
+    }
+
+    #[func]
+    fn on_dialog_close_button_up(&mut self) {
+        self.hide();
+        self.emit_signal("dialog_closed".into(), &[]);
+    }
+}
+
+#[godot_api]
+impl PanelVirtual for ActivityView {
+    fn init(base: Base<Self::Base>) -> Self {
+        Self {
+            base,
+
+            // cached internal UI elements
+            list_option_button: GdHolder::default(),
+            tag_option_button: GdHolder::default(),
+            heatmap: GdHolder::default(),
+            day_label: GdHolder::default(),
+            sessions_container: GdHolder::default(),
+            session_rows: vec![],
+            close_button: GdHolder::default(),
+
+            // state
+            filter_choices: ItemFilterChoices::default(),
+            filter: ItemFilter::default(),
+            days: vec![],
+            day_sessions: vec![],
+        }
+    }
+    fn ready(&mut self) {
+        match try {
+            let base = &self.base;
+
+            // cached internal UI elements
+            self.list_option_button = GdHolder::from_path(base, "VBoxContainer/FilterHBoxContainer/ListOptionButton");
+            self.list_option_button.ok_mut()?.connect(
+                "item_selected".into(),
+                base.callable("on_filter_option_button_item_selected"),
+            );
+            self.tag_option_button = GdHolder::from_path(base, "VBoxContainer/FilterHBoxContainer/TagOptionButton");
+            self.tag_option_button.ok_mut()?.connect(
+                "item_selected".into(),
+                base.callable("on_filter_option_button_item_selected"),
+            );
+            self.heatmap = GdHolder::from_path(base, "VBoxContainer/CalendarHeatmap");
+            self.heatmap.ok_mut()?.bind_mut().connect(
+                "day_selected".into(),
+                base.callable("on_heatmap_day_selected"),
+            );
+            self.day_label = GdHolder::from_path(base, "VBoxContainer/DayLabel");
+            self.sessions_container = GdHolder::from_path(base, "VBoxContainer/ScrollContainer/SessionsVBoxContainer");
+            self.close_button = GdHolder::from_path(base, "DialogCloseButton");
+            self.close_button.ok_mut()?.connect(
+                "button_up".into(),
+                base.callable("on_dialog_close_button_up"),
+            );
+        } {
+            Ok(_) => {}
+            Err::<_, BoxedError>(e) => log_error(e)
+        }
+    }
+}
@@ -1,12 +1,15 @@
-use chrono::Duration;
-use godot::engine::{Panel, PanelVirtual, LineEdit, TextEdit, Button, Label, CheckButton, SpinBox};
+use chrono::{Duration, NaiveDate};
+use godot::engine::{Panel, PanelVirtual, LineEdit, TextEdit, Button, Label, CheckBox, CheckButton, OptionButton, SpinBox};
 use godot::prelude::*;
 use crate::db::DB;
-use crate::errors::{ArreResult, BoxedError};
+use crate::errors::{ArreError, ArreResult, BoxedError};
+use crate::goal::{Goal, GoalPeriod, GoalTarget};
 use crate::godot_classes::singletons::logger::log_error;
 use crate::godot_classes::utils::{GdHolder};
 use crate::item::{Item, item_persist, item_update};
+use crate::item_availability::{item_availability_get, item_availability_update, ItemAvailability, WeekdaySet};
 use crate::item_details::{item_details_get, item_details_update, ItemDetails};
+use crate::pomodoro::PomodoroConfig;
 
 const UI_TEXT_CREATE: &str = "Create Item";
 const UI_TEXT_MODIFY: &str = "Modify Item";
@@ -28,12 +31,34 @@ pub struct ItemModifyView {
     description_text_edit: GdHolder<TextEdit>,
     session_time_check_button: GdHolder<CheckButton>,
     session_time_spin_box: GdHolder<SpinBox>,
+    auto_finish_check_box: GdHolder<CheckBox>,
+    cooldown_check_button: GdHolder<CheckButton>,
+    cooldown_spin_box: GdHolder<SpinBox>,
+    pomodoro_check_button: GdHolder<CheckButton>,
+    pomodoro_work_spin_box: GdHolder<SpinBox>,
+    pomodoro_short_break_spin_box: GdHolder<SpinBox>,
+    pomodoro_long_break_spin_box: GdHolder<SpinBox>,
+    pomodoro_long_break_every_spin_box: GdHolder<SpinBox>,
+    goal_check_button: GdHolder<CheckButton>,
+    goal_target_spin_box: GdHolder<SpinBox>,
+    goal_kind_option_button: GdHolder<OptionButton>,
+    goal_period_option_button: GdHolder<OptionButton>,
+    goal_boost_check_box: GdHolder<CheckBox>,
+    weekdays_check_button: GdHolder<CheckButton>,
+    weekday_check_boxes: [GdHolder<CheckBox>; 7], // Monday first
+    hours_check_button: GdHolder<CheckButton>,
+    hours_start_spin_box: GdHolder<SpinBox>,
+    hours_end_spin_box: GdHolder<SpinBox>,
+    dates_check_button: GdHolder<CheckButton>,
+    dates_start_line_edit: GdHolder<LineEdit>,
+    dates_end_line_edit: GdHolder<LineEdit>,
     apply_button: GdHolder<Button>,
     close_button: GdHolder<Button>,
 
     // state
     item: Item,
     item_details: ItemDetails,
+    item_availability: ItemAvailability,
     mode: Mode,
 }
 
@@ -53,6 +78,70 @@ impl ItemModifyView {
                 } else {
                     None
                 };
+            self.item_details.is_auto_finish =
+                self.item_details.session_duration.is_some() && self.auto_finish_check_box.ok()?.is_pressed();
+            self.item_details.cooldown =
+                if self.cooldown_check_button.ok()?.is_pressed() {
+                    Some(Duration::hours(self.cooldown_spin_box.ok()?.get_value() as i64))
+                } else {
+                    None
+                };
+            self.item_details.pomodoro =
+                if self.pomodoro_check_button.ok()?.is_pressed() {
+                    Some(PomodoroConfig {
+                        work: Duration::minutes(self.pomodoro_work_spin_box.ok()?.get_value() as i64),
+                        short_break: Duration::minutes(self.pomodoro_short_break_spin_box.ok()?.get_value() as i64),
+                        long_break: Duration::minutes(self.pomodoro_long_break_spin_box.ok()?.get_value() as i64),
+                        long_break_every: self.pomodoro_long_break_every_spin_box.ok()?.get_value() as u32,
+                    })
+                } else {
+                    None
+                };
+            self.item_details.goal =
+                if self.goal_check_button.ok()?.is_pressed() {
+                    let target = self.goal_target_spin_box.ok()?.get_value();
+                    Some(Goal {
+                        target: match self.goal_kind_option_button.ok()?.get_selected() {
+                            1 => GoalTarget::Sessions(target as usize),
+                            _ => GoalTarget::Time(Duration::minutes((target * 60.0) as i64)),
+                        },
+                        period: match self.goal_period_option_button.ok()?.get_selected() {
+                            1 => GoalPeriod::Month,
+                            _ => GoalPeriod::Week,
+                        },
+                        is_boost: self.goal_boost_check_box.ok()?.is_pressed(),
+                    })
+                } else {
+                    None
+                };
+            self.item_availability.weekdays =
+                if self.weekdays_check_button.ok()?.is_pressed() {
+                    let mut weekdays = WeekdaySet::default();
+                    for (weekday, check_box) in WeekdaySet::ALL_DAYS.into_iter().zip(self.weekday_check_boxes.iter()) {
+                        if check_box.ok()?.is_pressed() { weekdays.insert(weekday); }
+                    }
+                    Some(weekdays)
+                } else {
+                    None
+                };
+            self.item_availability.hours =
+                if self.hours_check_button.ok()?.is_pressed() {
+                    Some((
+                        self.hours_start_spin_box.ok()?.get_value() as u32,
+                        self.hours_end_spin_box.ok()?.get_value() as u32,
+                    ))
+                } else {
+                    None
+                };
+            self.item_availability.dates =
+                if self.dates_check_button.ok()?.is_pressed() {
+                    Some((
+                        Self::parse_date(self.dates_start_line_edit.ok()?)?,
+                        Self::parse_date(self.dates_end_line_edit.ok()?)?,
+                    ))
+                } else {
+                    None
+                };
 
             let connection = &*DB.ok()?;
             match self.mode {
@@ -61,10 +150,13 @@ impl ItemModifyView {
                     item_persist(connection, &mut self.item)?;
                     self.item_details.id = self.item.id;
                     item_details_update(connection, &self.item_details)?;
+                    self.item_availability.id = self.item.id;
+                    item_availability_update(connection, &self.item_availability)?;
                 },
                 Mode::Edit => {
                     item_update(connection, &self.item)?;
                     item_details_update(connection, &self.item_details)?;
+                    item_availability_update(connection, &self.item_availability)?;
                 }
             };
 
@@ -106,12 +198,96 @@ impl ItemModifyView {
                     "set_pressed".into() , &[false.to_variant()]
                 );
             }
+            self.auto_finish_check_box.ok_mut()?.set_pressed(self.item_details.is_auto_finish);
+            self.auto_finish_check_box.ok_mut()?.set_disabled(self.item_details.session_duration.is_none());
+            if let Some(cooldown) = self.item_details.cooldown {
+                self.cooldown_spin_box.ok_mut()?.set_value(cooldown.num_hours() as f64);
+                self.cooldown_spin_box.ok_mut()?.set_editable(true);
+                self.cooldown_check_button.ok_mut()?.call_deferred(
+                    "set_pressed".into() , &[true.to_variant()]
+                );
+            } else {
+                self.cooldown_spin_box.ok_mut()?.set_value(24.);
+                self.cooldown_spin_box.ok_mut()?.set_editable(false);
+                self.cooldown_check_button.ok_mut()?.call_deferred(
+                    "set_pressed".into() , &[false.to_variant()]
+                );
+            }
+            let is_pomodoro_set = self.item_details.pomodoro.is_some();
+            let pomodoro = self.item_details.pomodoro.unwrap_or_default();
+            self.pomodoro_work_spin_box.ok_mut()?.set_value(pomodoro.work.num_minutes() as f64);
+            self.pomodoro_short_break_spin_box.ok_mut()?.set_value(pomodoro.short_break.num_minutes() as f64);
+            self.pomodoro_long_break_spin_box.ok_mut()?.set_value(pomodoro.long_break.num_minutes() as f64);
+            self.pomodoro_long_break_every_spin_box.ok_mut()?.set_value(pomodoro.long_break_every as f64);
+            self.set_pomodoro_editable(is_pomodoro_set)?;
+            self.pomodoro_check_button.ok_mut()?.call_deferred(
+                "set_pressed".into() , &[is_pomodoro_set.to_variant()]
+            );
+            let is_goal_set = self.item_details.goal.is_some();
+            let goal = self.item_details.goal.unwrap_or_default();
+            // Time targets are edited in hours
+            let (target, kind) = match goal.target {
+                GoalTarget::Time(time) => (time.num_minutes() as f64 / 60.0, 0),
+                GoalTarget::Sessions(sessions) => (sessions as f64, 1),
+            };
+            self.goal_target_spin_box.ok_mut()?.set_value(target);
+            self.goal_kind_option_button.ok_mut()?.select(kind);
+            self.goal_period_option_button.ok_mut()?.select(match goal.period {
+                GoalPeriod::Week => 0,
+                GoalPeriod::Month => 1,
+            });
+            self.goal_boost_check_box.ok_mut()?.set_pressed(goal.is_boost);
+            self.set_goal_editable(is_goal_set)?;
+            self.goal_check_button.ok_mut()?.call_deferred(
+                "set_pressed".into() , &[is_goal_set.to_variant()]
+            );
+            self.refresh_availability_display()?;
         } {
             Ok(_) => {}
             Err::<_, BoxedError>(e) => log_error(e)
         }
     }
 
+    fn refresh_availability_display(&mut self) -> ArreResult<()> {
+        let availability = self.item_availability.clone();
+        let weekdays = availability.weekdays.unwrap_or(WeekdaySet::ALL_DAYS.into_iter().collect());
+        for (weekday, check_box) in WeekdaySet::ALL_DAYS.into_iter().zip(self.weekday_check_boxes.iter_mut()) {
+            let check_box = check_box.ok_mut()?;
+            check_box.set_pressed(weekdays.contains(weekday));
+            check_box.set_disabled(availability.weekdays.is_none());
+        }
+        self.weekdays_check_button.ok_mut()?.call_deferred(
+            "set_pressed".into() , &[availability.weekdays.is_some().to_variant()]
+        );
+
+        let (hour_start, hour_end) = availability.hours.unwrap_or((8, 18));
+        self.hours_start_spin_box.ok_mut()?.set_value(hour_start as f64);
+        self.hours_start_spin_box.ok_mut()?.set_editable(availability.hours.is_some());
+        self.hours_end_spin_box.ok_mut()?.set_value(hour_end as f64);
+        self.hours_end_spin_box.ok_mut()?.set_editable(availability.hours.is_some());
+        self.hours_check_button.ok_mut()?.call_deferred(
+            "set_pressed".into() , &[availability.hours.is_some().to_variant()]
+        );
+
+        let (date_start, date_end) = match availability.dates {
+            Some((start, end)) => (start.to_string(), end.to_string()),
+            None => (String::new(), String::new()),
+        };
+        self.dates_start_line_edit.ok_mut()?.set_text(date_start.into());
+        self.dates_start_line_edit.ok_mut()?.set_editable(availability.dates.is_some());
+        self.dates_end_line_edit.ok_mut()?.set_text(date_end.into());
+        self.dates_end_line_edit.ok_mut()?.set_editable(availability.dates.is_some());
+        self.dates_check_button.ok_mut()?.call_deferred(
+            "set_pressed".into() , &[availability.dates.is_some().to_variant()]
+        );
+        Ok(())
+    }
+
+    fn parse_date(line_edit: &LineEdit) -> ArreResult<NaiveDate> {
+        let text = line_edit.get_text().to_string();
+        text.trim().parse::<NaiveDate>().map_err(|_| ArreError::InvalidDate(text).into())
+    }
+
     #[func]
     fn on_dialog_close_button_up(&mut self) {
         self.hide();
@@ -122,6 +298,87 @@ impl ItemModifyView {
     fn on_session_time_check_button_toggled(&mut self, checked: bool) {
         match try {
         self.session_time_spin_box.ok_mut()?.set_editable(checked);
+        self.auto_finish_check_box.ok_mut()?.set_disabled(!checked);
+        } {
+            Ok(_) => {}
+            Err::<_, BoxedError>(e) => log_error(e)
+        }
+    }
+
+    #[func]
+    fn on_cooldown_check_button_toggled(&mut self, checked: bool) {
+        match try {
+            self.cooldown_spin_box.ok_mut()?.set_editable(checked);
+        } {
+            Ok(_) => {}
+            Err::<_, BoxedError>(e) => log_error(e)
+        }
+    }
+
+    #[func]
+    fn on_pomodoro_check_button_toggled(&mut self, checked: bool) {
+        match try {
+            self.set_pomodoro_editable(checked)?;
+        } {
+            Ok(_) => {}
+            Err::<_, BoxedError>(e) => log_error(e)
+        }
+    }
+
+    fn set_pomodoro_editable(&mut self, editable: bool) -> ArreResult<()> {
+        self.pomodoro_work_spin_box.ok_mut()?.set_editable(editable);
+        self.pomodoro_short_break_spin_box.ok_mut()?.set_editable(editable);
+        self.pomodoro_long_break_spin_box.ok_mut()?.set_editable(editable);
+        self.pomodoro_long_break_every_spin_box.ok_mut()?.set_editable(editable);
+        Ok(())
+    }
+
+    #[func]
+    fn on_goal_check_button_toggled(&mut self, checked: bool) {
+        match try {
+            self.set_goal_editable(checked)?;
+        } {
+            Ok(_) => {}
+            Err::<_, BoxedError>(e) => log_error(e)
+        }
+    }
+
+    fn set_goal_editable(&mut self, editable: bool) -> ArreResult<()> {
+        self.goal_target_spin_box.ok_mut()?.set_editable(editable);
+        self.goal_kind_option_button.ok_mut()?.set_disabled(!editable);
+        self.goal_period_option_button.ok_mut()?.set_disabled(!editable);
+        self.goal_boost_check_box.ok_mut()?.set_disabled(!editable);
+        Ok(())
+    }
+
+    #[func]
+    fn on_weekdays_check_button_toggled(&mut self, checked: bool) {
+        match try {
+            for check_box in self.weekday_check_boxes.iter_mut() {
+                check_box.ok_mut()?.set_disabled(!checked);
+            }
+        } {
+            Ok(_) => {}
+            Err::<_, BoxedError>(e) => log_error(e)
+        }
+    }
+
+    #[func]
+    fn on_hours_check_button_toggled(&mut self, checked: bool) {
+        match try {
+            self.hours_start_spin_box.ok_mut()?.set_editable(checked);
+            self.hours_end_spin_box.ok_mut()?.set_editable(checked);
+        } {
+            Ok(_) => {}
+            Err::<_, BoxedError>(e) => log_error(e)
+        }
+    }
+
+    #[func]
+    fn on_dates_check_button_toggled(&mut self, checked: bool) {
+        match try {
+            self.dates_start_line_edit.ok_mut()?.set_editable(checked);
+            self.dates_end_line_edit.ok_mut()?.set_editable(checked);
         } {
             Ok(_) => {}
             Err::<_, BoxedError>(e) => log_error(e)
@@ -132,6 +389,7 @@ impl ItemModifyView {
         self.mode = Mode::Add;
         self.item = Item::default();
         self.item_details = ItemDetails::default();
+        self.item_availability = ItemAvailability::default();
         self.refresh_display();
     }
 
@@ -141,6 +399,7 @@ impl ItemModifyView {
 
         let connection = &*DB.ok()?;
         self.item_details = item_details_get(connection, self.item.get_id()?)?;
+        self.item_availability = item_availability_get(connection, self.item.get_id()?)?;
 
         self.refresh_display();
         Ok(())
@@ -160,12 +419,34 @@ impl PanelVirtual for ItemModifyView {
             description_text_edit: GdHolder::default(),
             session_time_check_button: GdHolder::default(),
             session_time_spin_box: GdHolder::default(),
+            auto_finish_check_box: GdHolder::default(),
+            cooldown_check_button: GdHolder::default(),
+            cooldown_spin_box: GdHolder::default(),
+            pomodoro_check_button: GdHolder::default(),
+            pomodoro_work_spin_box: GdHolder::default(),
+            pomodoro_short_break_spin_box: GdHolder::default(),
+            pomodoro_long_break_spin_box: GdHolder::default(),
+            pomodoro_long_break_every_spin_box: GdHolder::default(),
+            goal_check_button: GdHolder::default(),
+            goal_target_spin_box: GdHolder::default(),
+            goal_kind_option_button: GdHolder::default(),
+            goal_period_option_button: GdHolder::default(),
+            goal_boost_check_box: GdHolder::default(),
+            weekdays_check_button: GdHolder::default(),
+            weekday_check_boxes: Default::default(),
+            hours_check_button: GdHolder::default(),
+            hours_start_spin_box: GdHolder::default(),
+            hours_end_spin_box: GdHolder::default(),
+            dates_check_button: GdHolder::default(),
+            dates_start_line_edit: GdHolder::default(),
+            dates_end_line_edit: GdHolder::default(),
             apply_button: GdHolder::default(),
             close_button: GdHolder::default(),
 
             // state
             item: Item::default(),
             item_details: ItemDetails::default(),
+            item_availability: ItemAvailability::default(),
             mode: Mode::Add,
         }
     }
@@ -181,6 +462,53 @@ impl PanelVirtual for ItemModifyView {
                 base.callable("on_session_time_check_button_toggled"),
             );
             self.session_time_spin_box = GdHolder::from_path(base,"VBoxContainer/CentralMarginContainer/VBoxContainer/SessionTimeHBoxContainer/SpinBox");
+            self.auto_finish_check_box = GdHolder::from_path(base,"VBoxContainer/CentralMarginContainer/VBoxContainer/SessionTimeHBoxContainer/AutoFinishCheckBox");
+            self.cooldown_check_button = GdHolder::from_path(base,"VBoxContainer/CentralMarginContainer/VBoxContainer/CooldownHBoxContainer/CheckButton");
+            self.cooldown_check_button.ok_mut()?.connect(
+                "toggled".into(),
+                base.callable("on_cooldown_check_button_toggled"),
+            );
+            self.cooldown_spin_box = GdHolder::from_path(base,"VBoxContainer/CentralMarginContainer/VBoxContainer/CooldownHBoxContainer/SpinBox");
+            self.pomodoro_check_button = GdHolder::from_path(base,"VBoxContainer/CentralMarginContainer/VBoxContainer/PomodoroHBoxContainer/CheckButton");
+            self.pomodoro_check_button.ok_mut()?.connect(
+                "toggled".into(),
+                base.callable("on_pomodoro_check_button_toggled"),
+            );
+            self.pomodoro_work_spin_box = GdHolder::from_path(base,"VBoxContainer/CentralMarginContainer/VBoxContainer/PomodoroHBoxContainer/WorkSpinBox");
+            self.pomodoro_short_break_spin_box = GdHolder::from_path(base,"VBoxContainer/CentralMarginContainer/VBoxContainer/PomodoroHBoxContainer/ShortBreakSpinBox");
+            self.pomodoro_long_break_spin_box = GdHolder::from_path(base,"VBoxContainer/CentralMarginContainer/VBoxContainer/PomodoroHBoxContainer/LongBreakSpinBox");
+            self.pomodoro_long_break_every_spin_box = GdHolder::from_path(base,"VBoxContainer/CentralMarginContainer/VBoxContainer/PomodoroHBoxContainer/LongBreakEverySpinBox");
+            self.goal_check_button = GdHolder::from_path(base,"VBoxContainer/CentralMarginContainer/VBoxContainer/GoalHBoxContainer/CheckButton");
+            self.goal_check_button.ok_mut()?.connect(
+                "toggled".into(),
+                base.callable("on_goal_check_button_toggled"),
+            );
+            self.goal_target_spin_box = GdHolder::from_path(base,"VBoxContainer/CentralMarginContainer/VBoxContainer/GoalHBoxContainer/TargetSpinBox");
+            self.goal_kind_option_button = GdHolder::from_path(base,"VBoxContainer/CentralMarginContainer/VBoxContainer/GoalHBoxContainer/KindOptionButton");
+            self.goal_period_option_button = GdHolder::from_path(base,"VBoxContainer/CentralMarginContainer/VBoxContainer/GoalHBoxContainer/PeriodOptionButton");
+            self.goal_boost_check_box = GdHolder::from_path(base,"VBoxContainer/CentralMarginContainer/VBoxContainer/GoalHBoxContainer/BoostCheckBox");
+            self.weekdays_check_button = GdHolder::from_path(base,"VBoxContainer/CentralMarginContainer/VBoxContainer/WeekdaysHBoxContainer/CheckButton");
+            self.weekdays_check_button.ok_mut()?.connect(
+                "toggled".into(),
+                base.callable("on_weekdays_check_button_toggled"),
+            );
+            for (weekday, check_box) in WeekdaySet::ALL_DAYS.into_iter().zip(self.weekday_check_boxes.iter_mut()) {
+                *check_box = GdHolder::from_path(base, format!("VBoxContainer/CentralMarginContainer/VBoxContainer/WeekdaysHBoxContainer/{}CheckBox", weekday));
+            }
+            self.hours_check_button = GdHolder::from_path(base,"VBoxContainer/CentralMarginContainer/VBoxContainer/HoursHBoxContainer/CheckButton");
+            self.hours_check_button.ok_mut()?.connect(
+                "toggled".into(),
+                base.callable("on_hours_check_button_toggled"),
+            );
+            self.hours_start_spin_box = GdHolder::from_path(base,"VBoxContainer/CentralMarginContainer/VBoxContainer/HoursHBoxContainer/StartSpinBox");
+            self.hours_end_spin_box = GdHolder::from_path(base,"VBoxContainer/CentralMarginContainer/VBoxContainer/HoursHBoxContainer/EndSpinBox");
+            self.dates_check_button = GdHolder::from_path(base,"VBoxContainer/CentralMarginContainer/VBoxContainer/DatesHBoxContainer/CheckButton");
+            self.dates_check_button.ok_mut()?.connect(
+                "toggled".into(),
+                base.callable("on_dates_check_button_toggled"),
+            );
+            self.dates_start_line_edit = GdHolder::from_path(base,"VBoxContainer/CentralMarginContainer/VBoxContainer/DatesHBoxContainer/StartLineEdit");
+            self.dates_end_line_edit = GdHolder::from_path(base,"VBoxContainer/CentralMarginContainer/VBoxContainer/DatesHBoxContainer/EndLineEdit");
             self.apply_button = GdHolder::from_path(base,"VBoxContainer/BottomMarginContainer/ItemApplyButton");
             self.apply_button.ok_mut()?.connect(
                 "button_up".into(),
@@ -0,0 +1,261 @@
+use chrono::{DateTime, Duration, Local, NaiveDateTime, TimeZone, Utc};
+use godot::engine::{BaseButton, Button, ButtonGroup, Label, LineEdit, Panel, PanelVirtual, VBoxContainer};
+use godot::prelude::*;
+use crate::db::DB;
+use crate::errors::{ArreError, ArreResult, BoxedError};
+use crate::godot_classes::singletons::logger::log_error;
+use crate::godot_classes::utils::{GdHolder};
+use crate::item::{Item, item_get, ItemId};
+use crate::session::{item_sessions_get, Session, session_delete, session_manual_create, session_pauses_get, session_update};
+use crate::utils::format_duration;
+
+const UI_TEXT_DATE_TIME_FORMAT: &str = "%Y-%m-%d %H:%M";
+const UI_TEXT_SESSION: &str = "{start} - {end}, worked {duration}";
+const UI_TEXT_OVERTIME: &str = " ({overtime} overtime)";
+const UI_TEXT_ABANDONED: &str = ", abandoned";
+const UI_TEXT_ABANDON_REASON: &str = ": {reason}";
+const UI_TEXT_ADD: &str = "Add";
+const UI_TEXT_SAVE: &str = "Save";
+
+/// Lists the past sessions of an item, to credit forgotten work or correct recorded times
+#[derive(GodotClass)]
+#[class(base=Panel)]
+pub struct ItemSessionsView {
+    #[base]
+    base: Base<Panel>,
+
+    // cached internal UI elements
+    pub item_name_label: GdHolder<Label>,
+    pub sessions_container: GdHolder<VBoxContainer>,
+    pub start_line_edit: GdHolder<LineEdit>,
+    pub end_line_edit: GdHolder<LineEdit>,
+    pub save_button: GdHolder<Button>,
+    pub new_button: GdHolder<Button>,
+    pub delete_button: GdHolder<Button>,
+    pub close_button: GdHolder<Button>,
+    pub session_buttons: Vec<Gd<Button>>,
+    pub session_button_group: Gd<ButtonGroup>,
+
+    // state
+    item: Item,
+    sessions: Vec<Session>,
+    selected_session: Option<usize>, // index in sessions, None when adding a new one
+}
+
+#[godot_api]
+impl ItemSessionsView {
+    #[signal]
+    fn dialog_closed();
+
+    pub fn set_item(&mut self, item_id: ItemId) -> ArreResult<()> {
+        self.item = item_get(&*DB.ok()?, item_id)?;
+        self.refresh_display()
+    }
+
+    pub fn refresh_display(&mut self) -> ArreResult<()> {
+        self.sessions = item_sessions_get(&*DB.ok()?, self.item.get_id()?)?;
+        self.item_name_label.ok_mut()?.set_text(self.item.name.clone().into());
+
+        self.session_buttons.drain(..).for_each(|mut button| button.queue_free());
+        // Most recent first
+        for session in self.sessions.iter().rev() {
+            let mut button = Button::new_alloc();
+            button.set_text(Self::session_text(session).into());
+            button.set_toggle_mode(true);
+            button.set_button_group(self.session_button_group.share());
+            self.sessions_container.ok_mut()?.add_child(button.share().upcast());
+            self.session_buttons.push(button);
+        }
+        self.select_session(None)
+    }
+
+    fn session_text(session: &Session) -> String {
+        let mut template = UI_TEXT_SESSION.to_string();
+        if session.overtime > Duration::zero() {
+            template.push_str(UI_TEXT_OVERTIME);
+        }
+        if session.is_abandoned {
+            template.push_str(UI_TEXT_ABANDONED);
+            if session.abandon_reason.is_some() {
+                template.push_str(UI_TEXT_ABANDON_REASON);
+            }
+        }
+        template
+            .replace("{start}", &Self::format_date_time(*session.start))
+            .replace("{end}", &Self::format_date_time(*session.end))
+            .replace("{duration}", &format_duration(session.duration))
+            .replace("{overtime}", &format_duration(session.overtime))
+            .replace("{reason}", session.abandon_reason.as_deref().unwrap_or_default())
+    }
+
+    fn format_date_time(date_time: DateTime<Utc>) -> String {
+        date_time.with_timezone(&Local).format(UI_TEXT_DATE_TIME_FORMAT).to_string()
+    }
+
+    fn parse_date_time(line_edit: &LineEdit) -> ArreResult<DateTime<Utc>> {
+        let text = line_edit.get_text().to_string();
+        NaiveDateTime::parse_from_str(text.trim(), UI_TEXT_DATE_TIME_FORMAT)
+            .ok()
+            .and_then(|date_time| Local.from_local_datetime(&date_time).single())
+            .map(|date_time| date_time.with_timezone(&Utc))
+            .ok_or(ArreError::InvalidDateTime(text).into())
+    }
+
+    /// Fill the form with the selected session, or with the last hour for a new one
+    fn select_session(&mut self, selected: Option<usize>) -> ArreResult<()> {
+        self.selected_session = selected;
+        let (start, end) = match selected.map(|idx| &self.sessions[idx]) {
+            Some(session) => (*session.start, *session.end),
+            None => {
+                let now = Utc::now();
+                (now - Duration::hours(1), now)
+            }
+        };
+        if selected.is_none() {
+            self.session_buttons.iter_mut().for_each(|button| button.set_pressed(false));
+        }
+        self.start_line_edit.ok_mut()?.set_text(Self::format_date_time(start).into());
+        self.end_line_edit.ok_mut()?.set_text(Self::format_date_time(end).into());
+        self.save_button.ok_mut()?.set_text(if selected.is_some() { UI_TEXT_SAVE } else { UI_TEXT_ADD }.into());
+        self.delete_button.ok_mut()?.set_disabled(selected.is_none());
+        Ok(())
+    }
+
+    #[func]
+    fn on_session_button_pressed(&mut self, button: Gd<BaseButton>) {
+        match try {
+            // Buttons are in reverse order of the sessions
+            let position = self.session_buttons
+                .iter()
+                .position(|session_button| session_button.instance_id() == button.instance_id())
+                .ok_or(ArreError::UnexpectedNone("ItemSessionsView::on_session_button_pressed".into()))?;
+            self.select_session(Some(self.sessions.len() - 1 - position))?;
+        } {
+            Ok(_) => {}
+            Err::<_, BoxedError>(e) => log_error(e)
+        }
+    }
+
+    #[func]
+    fn on_new_button_up(&mut self) {
+        match try {
+            self.select_session(None)?;
+        } {
+            Ok(_) => {}
+            Err::<_, BoxedError>(e) => log_error(e)
+        }
+    }
+
+    #[func]
+    fn on_save_button_up(&mut self) {
+        match try {
+            let start = Self::parse_date_time(self.start_line_edit.ok()?)?;
+            let end = Self::parse_date_time(self.end_line_edit.ok()?)?;
+            {
+                let connection = &*DB.ok()?;
+                match self.selected_session {
+                    Some(idx) => {
+                        let mut session = self.sessions[idx].clone();
+                        // Pauses taken during the session still do not count as work
+                        let paused = session_pauses_get(connection, session.get_id()?)?
+                            .iter()
+                            .fold(Duration::zero(), |paused, pause| paused + pause.duration(end));
+                        session.start = start.into();
+                        session.end = end.into();
+                        session.duration = (end - start - paused).max(Duration::zero());
+                        session_update(connection, &session)?;
+                    }
+                    None => {
+                        session_manual_create(connection, self.item.get_id()?, start, end)?;
+                    }
+                }
+            }
+            self.refresh_display()?;
+        } {
+            Ok(_) => {}
+            Err::<_, BoxedError>(e) => log_error(e)
+        }
+    }
+
+    #[func]
+    fn on_delete_button_up(&mut self) {
+        match try {
+            let Some(idx) = self.selected_session else { return };
+            session_delete(&*DB.ok()?, self.sessions[idx].get_id()?)?;
+            self.refresh_display()?;
+        } {
+            Ok(_) => {}
+            Err::<_, BoxedError>(e) => log_error(e)
+        }
+    }
+
+    #[func]
+    fn on_dialog_close_button_up(&mut self) {
+        self.hide();
+        self.emit_signal("dialog_closed".into(), &[]);
+    }
+}
+
+#[godot_api]
+impl PanelVirtual for ItemSessionsView {
+    fn init(base: Base<Self::Base>) -> Self {
+        Self {
+            base,
+
+            // cached internal UI elements
+            item_name_label: GdHolder::default(),
+            sessions_container: GdHolder::default(),
+            start_line_edit: GdHolder::default(),
+            end_line_edit: GdHolder::default(),
+            save_button: GdHolder::default(),
+            new_button: GdHolder::default(),
+            delete_button: GdHolder::default(),
+            close_button: GdHolder::default(),
+            session_buttons: vec![],
+            session_button_group: ButtonGroup::new(),
+
+            // state
+            item: Item::default(),
+            sessions: vec![],
+            selected_session: None,
+        }
+    }
+    fn ready(&mut self) {
+        match try {
+            let base = &self.base;
+
+            // cached internal UI elements
+            self.item_name_label = GdHolder::from_path(base, "VBoxContainer/ItemNameLabel");
+            self.sessions_container = GdHolder::from_path(base, "VBoxContainer/ScrollContainer/SessionsVBoxContainer");
+            self.session_button_group.connect(
+                "pressed".into(),
+                base.callable("on_session_button_pressed"),
+            );
+            self.start_line_edit = GdHolder::from_path(base, "VBoxContainer/FormHBoxContainer/StartLineEdit");
+            self.end_line_edit = GdHolder::from_path(base, "VBoxContainer/FormHBoxContainer/EndLineEdit");
+            self.save_button = GdHolder::from_path(base, "VBoxContainer/FormHBoxContainer/SaveButton");
+            self.save_button.ok_mut()?.connect(
+                "button_up".into(),
+                base.callable("on_save_button_up"),
+            );
+            self.new_button = GdHolder::from_path(base, "VBoxContainer/FormHBoxContainer/NewButton");
+            self.new_button.ok_mut()?.connect(
+                "button_up".into(),
+                base.callable("on_new_button_up"),
+            );
+            self.delete_button = GdHolder::from_path(base, "VBoxContainer/FormHBoxContainer/DeleteButton");
+            self.delete_button.ok_mut()?.connect(
+                "button_up".into(),
+                base.callable("on_delete_button_up"),
+            );
+            self.close_button = GdHolder::from_path(base, "DialogCloseButton");
+            self.close_button.ok_mut()?.connect(
+                "button_up".into(),
+                base.callable("on_dialog_close_button_up"),
+            );
+        } {
+            Ok(_) => {}
+            Err::<_, BoxedError>(e) => log_error(e)
+        }
+    }
+}
@@ -1,13 +1,31 @@
-use godot::engine::{Panel, PanelVirtual, Button, Label};
+use chrono::{Duration, Local, Utc};
+use godot::engine::{Panel, PanelVirtual, Button, Label, OptionButton};
 use godot::prelude::*;
-use crate::errors::{BoxedError};
+use crate::db::DB;
+use crate::errors::{ArreResult, BoxedError};
+use crate::godot_classes::bar_chart::{BarChart, ChartBar};
 use crate::godot_classes::singletons::logger::log_error;
 use crate::godot_classes::utils::{GdHolder};
-use crate::item_stats::ItemStats;
-use crate::utils::format_duration;
+use crate::godot_classes::views::view_item_sessions::ItemSessionsView;
+use crate::item::ItemId;
+use crate::item_stats::{item_stats_get, ItemStats, Streak};
+use crate::time_series::{BucketSize, item_time_series_get, TimeBucket, TimeRange};
+use crate::utils::{ArreDateTime, format_duration};
 
 const UI_TEXT_TIMES_WORKED: &str = "Times Worked: ";
 const UI_TEXT_TIME_SPENT: &str = "Time Spent: ";
+const UI_TEXT_TIMES_SKIPPED: &str = "Times Skipped: ";
+const UI_TEXT_FIRST_WORKED: &str = "First Worked: ";
+const UI_TEXT_LAST_WORKED: &str = "Last Worked: ";
+const UI_TEXT_AVERAGE_SESSION: &str = "Average Session: ";
+const UI_TEXT_MEDIAN_SESSION: &str = "Median Session: ";
+const UI_TEXT_LONGEST_SESSION: &str = "Longest Session: ";
+const UI_TEXT_DAILY_STREAK: &str = "Daily Streak: {current} days (longest {longest})";
+const UI_TEXT_WEEKLY_STREAK: &str = "Weekly Streak: {current} weeks (longest {longest})";
+const UI_TEXT_NEVER: &str = "Never";
+const UI_TEXT_DATE_TIME_FORMAT: &str = "%Y-%m-%d %H:%M";
+const UI_TEXT_DAY_FORMAT: &str = "%m-%d";
+const UI_TEXT_MONTH_FORMAT: &str = "%b";
 
 #[derive(GodotClass)]
 #[class(base=Panel)]
@@ -18,10 +36,24 @@ pub struct ItemStatsView {
     // cached elements
     pub times_worked_label: GdHolder<Label>,
     pub time_spent_label: GdHolder<Label>,
+    pub times_skipped_label: GdHolder<Label>,
+    pub first_worked_label: GdHolder<Label>,
+    pub last_worked_label: GdHolder<Label>,
+    pub average_session_label: GdHolder<Label>,
+    pub median_session_label: GdHolder<Label>,
+    pub longest_session_label: GdHolder<Label>,
+    pub daily_streak_label: GdHolder<Label>,
+    pub weekly_streak_label: GdHolder<Label>,
+    pub time_range_option_button: GdHolder<OptionButton>,
+    pub time_chart: GdHolder<BarChart>,
     pub close_button: GdHolder<Button>,
+    pub sessions_button: GdHolder<Button>,
+    pub item_sessions_view: GdHolder<ItemSessionsView>,
 
     // state
     pub item_stats: ItemStats,
+    time_range: TimeRange,
+    time_series: Vec<TimeBucket>,
 }
 
 #[godot_api]
@@ -29,12 +61,107 @@ impl ItemStatsView {
     #[signal]
     fn dialog_closed();
 
+    pub fn set_item(&mut self, item_id: ItemId) -> ArreResult<()> {
+        self.item_stats = item_stats_get(&*DB.ok()?, item_id)?;
+        self.refresh_time_series()?;
+        self.refresh_display();
+        Ok(())
+    }
+
+    fn refresh_time_series(&mut self) -> ArreResult<()> {
+        let today = Local::now().date_naive();
+        self.time_series = item_time_series_get(&*DB.ok()?, self.item_stats.get_id()?, self.time_range, today)?;
+        Ok(())
+    }
+
     #[func]
     pub fn refresh_display(&mut self) {
         match try {
             let time_spent = format_duration(self.item_stats.time_spent);
             self.times_worked_label.ok_mut()?.set_text(format!("{}{}", UI_TEXT_TIMES_WORKED, self.item_stats.times_worked).into());
             self.time_spent_label.ok_mut()?.set_text(format!("{}{}", UI_TEXT_TIME_SPENT, time_spent).into());
+            self.times_skipped_label.ok_mut()?.set_text(format!("{}{}", UI_TEXT_TIMES_SKIPPED, self.item_stats.times_skipped).into());
+            let first_worked = Self::format_date_time(&self.item_stats.first_worked);
+            let last_worked = Self::format_date_time(&self.item_stats.last_worked);
+            self.first_worked_label.ok_mut()?.set_text(format!("{}{}", UI_TEXT_FIRST_WORKED, first_worked).into());
+            self.last_worked_label.ok_mut()?.set_text(format!("{}{}", UI_TEXT_LAST_WORKED, last_worked).into());
+            let average_session = Self::format_session(self.item_stats.average_session);
+            let median_session = Self::format_session(self.item_stats.median_session);
+            let longest_session = Self::format_session(self.item_stats.longest_session);
+            self.average_session_label.ok_mut()?.set_text(format!("{}{}", UI_TEXT_AVERAGE_SESSION, average_session).into());
+            self.median_session_label.ok_mut()?.set_text(format!("{}{}", UI_TEXT_MEDIAN_SESSION, median_session).into());
+            self.longest_session_label.ok_mut()?.set_text(format!("{}{}", UI_TEXT_LONGEST_SESSION, longest_session).into());
+            self.daily_streak_label.ok_mut()?.set_text(Self::format_streak(UI_TEXT_DAILY_STREAK, self.item_stats.daily_streak).into());
+            self.weekly_streak_label.ok_mut()?.set_text(Self::format_streak(UI_TEXT_WEEKLY_STREAK, self.item_stats.weekly_streak).into());
+            let label_format = match self.time_range.bucket_size() {
+                BucketSize::Day | BucketSize::Week => UI_TEXT_DAY_FORMAT,
+                BucketSize::Month => UI_TEXT_MONTH_FORMAT,
+            };
+            let bars = self.time_series
+                .iter()
+                .map(|bucket| ChartBar {
+                    label: bucket.start.format(label_format).to_string(),
+                    value: bucket.time_spent.num_seconds() as f64,
+                })
+                .collect();
+            let max_time_spent = self.time_series.iter().map(|bucket| bucket.time_spent).max().unwrap_or(Duration::zero());
+            self.time_chart.ok_mut()?.bind_mut().set_bars(bars, format_duration(max_time_spent));
+        } {
+            Ok(_) => {}
+            Err::<_, BoxedError>(e) => log_error(e)
+        }
+    }
+
+    fn format_date_time(date_time: &Option<ArreDateTime<Utc>>) -> String {
+        match date_time {
+            Some(date_time) => date_time.with_timezone(&Local).format(UI_TEXT_DATE_TIME_FORMAT).to_string(),
+            None => UI_TEXT_NEVER.to_string(),
+        }
+    }
+
+    fn format_session(duration: Option<Duration>) -> String {
+        duration.map(format_duration).unwrap_or("-".to_string())
+    }
+
+    fn format_streak(template: &str, streak: Streak) -> String {
+        template
+            .replace("{current}", &streak.current.to_string())
+            .replace("{longest}", &streak.longest.to_string())
+    }
+
+    #[func]
+    fn on_time_range_option_button_item_selected(&mut self, index: i64) {
+        match try {
+            self.time_range = match index {
+                1 => TimeRange::Last12Weeks,
+                2 => TimeRange::LastYear,
+                _ => TimeRange::Last30Days,
+            };
+            self.refresh_time_series()?;
+            self.refresh_display();
+        } {
+            Ok(_) => {}
+            Err::<_, BoxedError>(e) => log_error(e)
+        }
+    }
+
+    #[func]
+    fn on_sessions_button_up(&mut self) {
+        match try {
+            let mut view = self.item_sessions_view.ok_mut()?.bind_mut();
+            view.set_item(self.item_stats.get_id()?)?;
+            view.show();
+        } {
+            Ok(_) => {}
+            Err::<_, BoxedError>(e) => log_error(e)
+        }
+    }
+
+    /// Sessions may have been added, edited or deleted, so the stats are read again
+    #[func]
+    fn on_item_sessions_view_closed(&mut self) {
+        match try {
+            self.set_item(self.item_stats.get_id()?)?;
         } {
             Ok(_) => {}
             Err::<_, BoxedError>(e) => log_error(e)
@@ -55,9 +182,23 @@ impl PanelVirtual for ItemStatsView {
             base,
             times_worked_label: GdHolder::default(),
             time_spent_label: GdHolder::default(),
+            times_skipped_label: GdHolder::default(),
+            first_worked_label: GdHolder::default(),
+            last_worked_label: GdHolder::default(),
+            average_session_label: GdHolder::default(),
+            median_session_label: GdHolder::default(),
+            longest_session_label: GdHolder::default(),
+            daily_streak_label: GdHolder::default(),
+            weekly_streak_label: GdHolder::default(),
+            time_range_option_button: GdHolder::default(),
+            time_chart: GdHolder::default(),
             close_button: GdHolder::default(),
+            sessions_button: GdHolder::default(),
+            item_sessions_view: GdHolder::default(),
 
             item_stats: ItemStats::default(),
+            time_range: TimeRange::Last30Days,
+            time_series: vec![],
         }
     }
     fn ready(&mut self) {
@@ -65,11 +206,35 @@ impl PanelVirtual for ItemStatsView {
             let base = &self.base;
             self.times_worked_label = GdHolder::from_path(base, "VBoxContainer/VBoxContainer/TimesWorkedLabel");
             self.time_spent_label = GdHolder::from_path(base, "VBoxContainer/VBoxContainer/TimeSpentLabel");
+            self.times_skipped_label = GdHolder::from_path(base, "VBoxContainer/VBoxContainer/TimesSkippedLabel");
+            self.first_worked_label = GdHolder::from_path(base, "VBoxContainer/VBoxContainer/FirstWorkedLabel");
+            self.last_worked_label = GdHolder::from_path(base, "VBoxContainer/VBoxContainer/LastWorkedLabel");
+            self.average_session_label = GdHolder::from_path(base, "VBoxContainer/VBoxContainer/AverageSessionLabel");
+            self.median_session_label = GdHolder::from_path(base, "VBoxContainer/VBoxContainer/MedianSessionLabel");
+            self.longest_session_label = GdHolder::from_path(base, "VBoxContainer/VBoxContainer/LongestSessionLabel");
+            self.daily_streak_label = GdHolder::from_path(base, "VBoxContainer/VBoxContainer/DailyStreakLabel");
+            self.weekly_streak_label = GdHolder::from_path(base, "VBoxContainer/VBoxContainer/WeeklyStreakLabel");
+            self.time_range_option_button = GdHolder::from_path(base, "VBoxContainer/VBoxContainer/TimeRangeOptionButton");
+            self.time_range_option_button.ok_mut()?.connect(
+                "item_selected".into(),
+                base.callable("on_time_range_option_button_item_selected"),
+            );
+            self.time_chart = GdHolder::from_path(base, "VBoxContainer/VBoxContainer/TimeChart");
             self.close_button = GdHolder::from_path(base,"DialogCloseButton");
             self.close_button.ok_mut()?.connect(
                 "button_up".into(),
                 base.callable("on_dialog_close_button_up"),
             );
+            self.sessions_button = GdHolder::from_path(base, "VBoxContainer/VBoxContainer/SessionsButton");
+            self.sessions_button.ok_mut()?.connect(
+                "button_up".into(),
+                base.callable("on_sessions_button_up"),
+            );
+            self.item_sessions_view = GdHolder::from_path(base, "../ItemSessionsView");
+            self.item_sessions_view.ok_mut()?.bind_mut().connect(
+                "dialog_closed".into(),
+                base.callable("on_item_sessions_view_closed"),
+            );
         } {
             Ok(_) => {}
             Err::<_, BoxedError>(e) => log_error(e)
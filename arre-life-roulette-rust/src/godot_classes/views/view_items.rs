@@ -1,17 +1,20 @@
 use bus::BusReader;
+use chrono::Utc;
 use godot::engine::{Control, ControlVirtual, Button, LineEdit};
 use godot::prelude::*;
 use crate::db::DB;
 use crate::errors::{ArreResult, BoxedError};
+use crate::goal::item_goal_progress_get;
 use crate::godot_classes::containers::cards_flow_container::CardsFlowContainer;
 use crate::godot_classes::element_card::{Content, ElementCard};
 use crate::godot_classes::singletons::logger::log_error;
 use crate::godot_classes::singletons::signals::Signals;
 use crate::godot_classes::utils::{GdHolder, get_singleton};
+use crate::godot_classes::views::view_activity::ActivityView;
 use crate::godot_classes::views::view_item_modify::ItemModifyView;
 use crate::godot_classes::views::view_item_stats::ItemStatsView;
+use crate::godot_classes::views::view_neglect::NeglectView;
 use crate::item::{Item, item_get_all, item_search};
-use crate::item_stats::item_stats_get;
 
 #[derive(GodotClass)]
 #[class(base=Control)]
@@ -21,12 +24,16 @@ pub struct ItemsView {
 
     // cached internal UI elements
     pub item_add_button: GdHolder<Button>,
+    pub neglect_button: GdHolder<Button>,
+    pub activity_button: GdHolder<Button>,
     pub cards_container: GdHolder<CardsFlowContainer>,
     pub searchbar: GdHolder<LineEdit>,
 
     // cached external UI elements
     pub item_modify_view: GdHolder<ItemModifyView>,
     pub item_stats_view: GdHolder<ItemStatsView>,
+    pub neglect_view: GdHolder<NeglectView>,
+    pub activity_view: GdHolder<ActivityView>,
 
     // observers
     observer_card_left_click: Option<BusReader<InstanceId>>,
@@ -53,6 +60,30 @@ impl ItemsView {
         }
     }
 
+    #[func]
+    fn on_neglect_button_up(&mut self) {
+        match try {
+            let mut view = self.neglect_view.ok_mut()?.bind_mut();
+            view.refresh_full()?;
+            view.show();
+        } {
+            Ok(_) => {},
+            Err::<_, BoxedError>(e) => log_error(e)
+        }
+    }
+
+    #[func]
+    fn on_activity_button_up(&mut self) {
+        match try {
+            let mut view = self.activity_view.ok_mut()?.bind_mut();
+            view.refresh_full()?;
+            view.show();
+        } {
+            Ok(_) => {},
+            Err::<_, BoxedError>(e) => log_error(e)
+        }
+    }
+
     #[func]
     fn on_view_selected(&mut self) {
         self.refresh_full();
@@ -93,7 +124,18 @@ impl ItemsView {
     #[func]
     fn refresh_display(&mut self) {
         match try {
-            self.cards_container.ok_mut()?.bind_mut().set_cards(self.items.clone());
+            let mut cards_container = self.cards_container.ok_mut()?.bind_mut();
+            cards_container.set_cards(self.items.clone());
+            let connection = &*DB.ok()?;
+            let now = Utc::now();
+            for card in cards_container.item_cards.iter_mut() {
+                let mut card = card.bind_mut();
+                let goal_progress = match &card.content {
+                    Content::Item(item) => item_goal_progress_get(connection, item.get_id()?, now)?,
+                    _ => continue,
+                };
+                card.set_goal_progress(goal_progress);
+            }
         } {
             Ok(_) => {},
             Err::<_, BoxedError>(e) => log_error(e)
@@ -105,11 +147,8 @@ impl ItemsView {
         {
             let card = card.ok_mut()?.bind();
             if let Content::Item(item) = &card.content {
-                let connection = &*DB.ok()?;
-
                 let mut view = self.item_stats_view.ok_mut()?.bind_mut();
-                view.item_stats = item_stats_get(connection, item.get_id()?)?;
-                view.refresh_display();
+                view.set_item(item.get_id()?)?;
                 view.show();
             }
         }
@@ -138,12 +177,16 @@ impl ControlVirtual for ItemsView {
 
             // cached internal UI elements
             item_add_button: GdHolder::default(),
+            neglect_button: GdHolder::default(),
+            activity_button: GdHolder::default(),
             cards_container: GdHolder::default(),
             searchbar: GdHolder::default(),
 
             // cached external UI elements
             item_modify_view: GdHolder::default(),
             item_stats_view: GdHolder::default(),
+            neglect_view: GdHolder::default(),
+            activity_view: GdHolder::default(),
 
             // observers
             observer_card_left_click: None,
@@ -157,11 +200,21 @@ impl ControlVirtual for ItemsView {
     fn ready(&mut self) {
         match try {
             let base = &self.base;
-            self.item_add_button = GdHolder::from_path(base, "VBoxContainer/MarginContainer/ItemAddDialogButton");
+            self.item_add_button = GdHolder::from_path(base, "VBoxContainer/MarginContainer/HBoxContainer/ItemAddDialogButton");
             self.item_add_button.ok_mut()?.connect(
                 "button_up".into(),
                 base.callable("on_item_add_button_up"),
             );
+            self.neglect_button = GdHolder::from_path(base, "VBoxContainer/MarginContainer/HBoxContainer/NeglectButton");
+            self.neglect_button.ok_mut()?.connect(
+                "button_up".into(),
+                base.callable("on_neglect_button_up"),
+            );
+            self.activity_button = GdHolder::from_path(base, "VBoxContainer/MarginContainer/HBoxContainer/ActivityButton");
+            self.activity_button.ok_mut()?.connect(
+                "button_up".into(),
+                base.callable("on_activity_button_up"),
+            );
             self.cards_container = GdHolder::from_path(base,"VBoxContainer/ItemsListScrollContainer/CardsFlowContainer");
             self.cards_container.ok_mut().map(|cc| {
                 let mut cc = cc.bind_mut();
@@ -180,6 +233,8 @@ impl ControlVirtual for ItemsView {
                 base.callable("refresh_full"),
             );
             self.item_stats_view = GdHolder::from_path(base, "../../ItemStatsView");
+            self.neglect_view = GdHolder::from_path(base, "../../NeglectView");
+            self.activity_view = GdHolder::from_path(base, "../../ActivityView");
 
 
             // Get singleton and connect to global signals(show / hide)
@@ -1,15 +1,20 @@
 use std::collections::HashSet;
 use bus::BusReader;
-use godot::engine::{Panel, PanelVirtual, LineEdit, TextEdit, Button, Label};
+use godot::engine::{Panel, PanelVirtual, LineEdit, TextEdit, Button, Label, CheckBox, CheckButton, SpinBox, OptionButton};
 use godot::prelude::*;
+use chrono::Duration;
 use crate::db::DB;
 use crate::errors::{ArreResult, BoxedError};
+use crate::goal::{Goal, GoalPeriod, GoalTarget};
 use crate::godot_classes::containers::cards_flow_container::CardsFlowContainer;
 use crate::godot_classes::element_card::{ElementCard, Content};
 use crate::godot_classes::singletons::logger::log_error;
 use crate::godot_classes::utils::{GdHolder};
+use crate::godot_classes::views::view_list_stats::ListStatsView;
 use crate::item::{Item, item_get_all, item_search, items_to_ids};
 use crate::list::{List, list_create, list_items_get, list_items_get_complement, list_items_update, list_update};
+use crate::list_details::{BudgetPeriod, list_details_get, list_details_update, ListDetails, RerollBudget};
+use crate::pomodoro::PomodoroConfig;
 
 const UI_TEXT_CREATE: &str = "Create List";
 const UI_TEXT_MODIFY: &str = "Modify List";
@@ -47,11 +52,32 @@ pub struct ListModifyView {
     title_label: GdHolder<Label>,
     name_line_edit: GdHolder<LineEdit>,
     description_text_edit: GdHolder<TextEdit>,
+    reroll_budget_check_button: GdHolder<CheckButton>,
+    reroll_budget_spin_box: GdHolder<SpinBox>,
+    reroll_budget_period_option_button: GdHolder<OptionButton>,
+    cooldown_check_button: GdHolder<CheckButton>,
+    cooldown_spin_box: GdHolder<SpinBox>,
+    pomodoro_check_button: GdHolder<CheckButton>,
+    pomodoro_work_spin_box: GdHolder<SpinBox>,
+    pomodoro_short_break_spin_box: GdHolder<SpinBox>,
+    pomodoro_long_break_spin_box: GdHolder<SpinBox>,
+    pomodoro_long_break_every_spin_box: GdHolder<SpinBox>,
+    goal_check_button: GdHolder<CheckButton>,
+    goal_target_spin_box: GdHolder<SpinBox>,
+    goal_kind_option_button: GdHolder<OptionButton>,
+    goal_period_option_button: GdHolder<OptionButton>,
+    goal_boost_check_box: GdHolder<CheckBox>,
+    roll_weight_spin_box: GdHolder<SpinBox>,
+    deck_mode_check_button: GdHolder<CheckButton>,
     searchbar: GdHolder<LineEdit>,
     cards_in_container: GdHolder<CardsFlowContainer>,
     cards_out_container: GdHolder<CardsFlowContainer>,
     apply_button: GdHolder<Button>,
     close_button: GdHolder<Button>,
+    stats_button: GdHolder<Button>,
+
+    // cached external UI elements
+    list_stats_view: GdHolder<ListStatsView>,
 
     // observers
     observer_card_in_left_click: Option<BusReader<InstanceId>>,
@@ -59,6 +85,7 @@ pub struct ListModifyView {
 
     // state
     list: List,
+    list_details: ListDetails,
     items_in: HashSet<Item>,
     items_out: HashSet<Item>,
     mode: Mode,
@@ -78,6 +105,54 @@ impl ListModifyView {
         match try {
             let new_name = self.name_line_edit.ok()?.get_text().to_string();
             let new_description = self.description_text_edit.ok()?.get_text().to_string();
+            self.list_details.reroll_budget =
+                if self.reroll_budget_check_button.ok()?.is_pressed() {
+                    Some(RerollBudget {
+                        tokens: self.reroll_budget_spin_box.ok()?.get_value() as usize,
+                        period: match self.reroll_budget_period_option_button.ok()?.get_selected() {
+                            1 => BudgetPeriod::Week,
+                            _ => BudgetPeriod::Day,
+                        },
+                    })
+                } else {
+                    None
+                };
+            self.list_details.cooldown =
+                if self.cooldown_check_button.ok()?.is_pressed() {
+                    Some(Duration::hours(self.cooldown_spin_box.ok()?.get_value() as i64))
+                } else {
+                    None
+                };
+            self.list_details.pomodoro =
+                if self.pomodoro_check_button.ok()?.is_pressed() {
+                    Some(PomodoroConfig {
+                        work: Duration::minutes(self.pomodoro_work_spin_box.ok()?.get_value() as i64),
+                        short_break: Duration::minutes(self.pomodoro_short_break_spin_box.ok()?.get_value() as i64),
+                        long_break: Duration::minutes(self.pomodoro_long_break_spin_box.ok()?.get_value() as i64),
+                        long_break_every: self.pomodoro_long_break_every_spin_box.ok()?.get_value() as u32,
+                    })
+                } else {
+                    None
+                };
+            self.list_details.goal =
+                if self.goal_check_button.ok()?.is_pressed() {
+                    let target = self.goal_target_spin_box.ok()?.get_value();
+                    Some(Goal {
+                        target: match self.goal_kind_option_button.ok()?.get_selected() {
+                            1 => GoalTarget::Sessions(target as usize),
+                            _ => GoalTarget::Time(Duration::minutes((target * 60.0) as i64)),
+                        },
+                        period: match self.goal_period_option_button.ok()?.get_selected() {
+                            1 => GoalPeriod::Month,
+                            _ => GoalPeriod::Week,
+                        },
+                        is_boost: self.goal_boost_check_box.ok()?.is_pressed(),
+                    })
+                } else {
+                    None
+                };
+            self.list_details.roll_weight = self.roll_weight_spin_box.ok()?.get_value() as u32;
+            self.list_details.is_deck_mode = self.deck_mode_check_button.ok()?.is_pressed();
 
             {
                 let connection = &*DB.ok()?;
@@ -86,12 +161,15 @@ impl ListModifyView {
                         let new_list = list_create(connection, new_name, new_description)?;
                         let items = items_to_ids::<_, Vec<_>>(self.items_in.iter())?;
                         list_items_update(connection, new_list.get_id()?, items)?;
+                        self.list_details.id = new_list.id;
+                        list_details_update(connection, &self.list_details)?;
                         self.set_mode_edit(new_list);
                     }
                     Mode::Edit => {
                         self.list.name = new_name;
                         self.list.description = new_description;
                         list_update(connection, &self.list)?;
+                        list_details_update(connection, &self.list_details)?;
                         let items = items_to_ids::<_, Vec<_>>(self.items_in.iter())?;
                         list_items_update(connection, self.list.get_id()?, items)?;
                     }
@@ -114,12 +192,66 @@ impl ListModifyView {
                 Mode::Add => {
                     self.title_label.ok_mut()?.set_text(UI_TEXT_CREATE.into());
                     self.apply_button.ok_mut()?.set_text(UI_TEXT_CREATE.into());
+                    self.stats_button.ok_mut()?.set_visible(false);
                 }
                 Mode::Edit => {
                     self.title_label.ok_mut()?.set_text(UI_TEXT_MODIFY.into());
                     self.apply_button.ok_mut()?.set_text(UI_TEXT_MODIFY.into());
+                    self.stats_button.ok_mut()?.set_visible(true);
                 }
             }
+            let (is_budget_set, budget) = match &self.list_details.reroll_budget {
+                Some(budget) => (true, budget.clone()),
+                None => (false, RerollBudget { tokens: 3, period: BudgetPeriod::Day }),
+            };
+            self.reroll_budget_spin_box.ok_mut()?.set_value(budget.tokens as f64);
+            self.reroll_budget_spin_box.ok_mut()?.set_editable(is_budget_set);
+            self.reroll_budget_period_option_button.ok_mut()?.select(match budget.period {
+                BudgetPeriod::Day => 0,
+                BudgetPeriod::Week => 1,
+            });
+            self.reroll_budget_period_option_button.ok_mut()?.set_disabled(!is_budget_set);
+            self.reroll_budget_check_button.ok_mut()?.call_deferred(
+                // Deferred call, as it triggers `toggle` signal, which this class in handling
+                "set_pressed".into() , &[is_budget_set.to_variant()]
+            );
+            let is_cooldown_set = self.list_details.cooldown.is_some();
+            let cooldown = self.list_details.cooldown.unwrap_or(Duration::hours(24));
+            self.cooldown_spin_box.ok_mut()?.set_value(cooldown.num_hours() as f64);
+            self.cooldown_spin_box.ok_mut()?.set_editable(is_cooldown_set);
+            self.cooldown_check_button.ok_mut()?.call_deferred(
+                "set_pressed".into() , &[is_cooldown_set.to_variant()]
+            );
+            let is_pomodoro_set = self.list_details.pomodoro.is_some();
+            let pomodoro = self.list_details.pomodoro.unwrap_or_default();
+            self.pomodoro_work_spin_box.ok_mut()?.set_value(pomodoro.work.num_minutes() as f64);
+            self.pomodoro_short_break_spin_box.ok_mut()?.set_value(pomodoro.short_break.num_minutes() as f64);
+            self.pomodoro_long_break_spin_box.ok_mut()?.set_value(pomodoro.long_break.num_minutes() as f64);
+            self.pomodoro_long_break_every_spin_box.ok_mut()?.set_value(pomodoro.long_break_every as f64);
+            self.set_pomodoro_editable(is_pomodoro_set)?;
+            self.pomodoro_check_button.ok_mut()?.call_deferred(
+                "set_pressed".into() , &[is_pomodoro_set.to_variant()]
+            );
+            let is_goal_set = self.list_details.goal.is_some();
+            let goal = self.list_details.goal.unwrap_or_default();
+            // Time targets are edited in hours
+            let (target, kind) = match goal.target {
+                GoalTarget::Time(time) => (time.num_minutes() as f64 / 60.0, 0),
+                GoalTarget::Sessions(sessions) => (sessions as f64, 1),
+            };
+            self.goal_target_spin_box.ok_mut()?.set_value(target);
+            self.goal_kind_option_button.ok_mut()?.select(kind);
+            self.goal_period_option_button.ok_mut()?.select(match goal.period {
+                GoalPeriod::Week => 0,
+                GoalPeriod::Month => 1,
+            });
+            self.goal_boost_check_box.ok_mut()?.set_pressed(goal.is_boost);
+            self.set_goal_editable(is_goal_set)?;
+            self.goal_check_button.ok_mut()?.call_deferred(
+                "set_pressed".into() , &[is_goal_set.to_variant()]
+            );
+            self.roll_weight_spin_box.ok_mut()?.set_value(self.list_details.roll_weight as f64);
+            self.deck_mode_check_button.ok_mut()?.set_pressed(self.list_details.is_deck_mode);
 
             let display_items_in = self.get_display_items_in()?;
             self.cards_in_container.ok_mut()?.bind_mut().set_cards(display_items_in);
@@ -137,6 +269,75 @@ impl ListModifyView {
         self.emit_signal("dialog_closed".into(), &[]);
     }
 
+    #[func]
+    fn on_stats_button_up(&mut self) {
+        match try {
+            let mut view = self.list_stats_view.ok_mut()?.bind_mut();
+            view.set_list(self.list.clone())?;
+            view.show();
+        } {
+            Ok(_) => {}
+            Err::<_, BoxedError>(e) => log_error(e)
+        }
+    }
+
+    #[func]
+    fn on_reroll_budget_check_button_toggled(&mut self, checked: bool) {
+        match try {
+            self.reroll_budget_spin_box.ok_mut()?.set_editable(checked);
+            self.reroll_budget_period_option_button.ok_mut()?.set_disabled(!checked);
+        } {
+            Ok(_) => {}
+            Err::<_, BoxedError>(e) => log_error(e)
+        }
+    }
+
+    #[func]
+    fn on_cooldown_check_button_toggled(&mut self, checked: bool) {
+        match try {
+            self.cooldown_spin_box.ok_mut()?.set_editable(checked);
+        } {
+            Ok(_) => {}
+            Err::<_, BoxedError>(e) => log_error(e)
+        }
+    }
+
+    #[func]
+    fn on_pomodoro_check_button_toggled(&mut self, checked: bool) {
+        match try {
+            self.set_pomodoro_editable(checked)?;
+        } {
+            Ok(_) => {}
+            Err::<_, BoxedError>(e) => log_error(e)
+        }
+    }
+
+    fn set_pomodoro_editable(&mut self, editable: bool) -> ArreResult<()> {
+        self.pomodoro_work_spin_box.ok_mut()?.set_editable(editable);
+        self.pomodoro_short_break_spin_box.ok_mut()?.set_editable(editable);
+        self.pomodoro_long_break_spin_box.ok_mut()?.set_editable(editable);
+        self.pomodoro_long_break_every_spin_box.ok_mut()?.set_editable(editable);
+        Ok(())
+    }
+
+    #[func]
+    fn on_goal_check_button_toggled(&mut self, checked: bool) {
+        match try {
+            self.set_goal_editable(checked)?;
+        } {
+            Ok(_) => {}
+            Err::<_, BoxedError>(e) => log_error(e)
+        }
+    }
+
+    fn set_goal_editable(&mut self, editable: bool) -> ArreResult<()> {
+        self.goal_target_spin_box.ok_mut()?.set_editable(editable);
+        self.goal_kind_option_button.ok_mut()?.set_disabled(!editable);
+        self.goal_period_option_button.ok_mut()?.set_disabled(!editable);
+        self.goal_boost_check_box.ok_mut()?.set_disabled(!editable);
+        Ok(())
+    }
+
     #[func]
     fn on_name_line_edit_text_set(&mut self) {
         self.deferred_actions.save_name = true;
@@ -178,11 +379,13 @@ impl ListModifyView {
             let connection = &*DB.ok()?;
             match self.mode {
                 Mode::Add => {
+                    self.list_details = ListDetails::default();
                     self.items_out = item_get_all(connection)?;
                     self.items_in = HashSet::new();
                 },
                 Mode::Edit => {
                     let list_id = self.list.get_id()?;
+                    self.list_details = list_details_get(connection, list_id)?;
                     self.items_out = list_items_get_complement(connection, list_id)?;
                     self.items_in = list_items_get(connection, list_id)?;
                 }
@@ -259,11 +462,32 @@ impl PanelVirtual for ListModifyView {
             title_label: GdHolder::default(),
             name_line_edit: GdHolder::default(),
             description_text_edit: GdHolder::default(),
+            reroll_budget_check_button: GdHolder::default(),
+            reroll_budget_spin_box: GdHolder::default(),
+            reroll_budget_period_option_button: GdHolder::default(),
+            cooldown_check_button: GdHolder::default(),
+            cooldown_spin_box: GdHolder::default(),
+            pomodoro_check_button: GdHolder::default(),
+            pomodoro_work_spin_box: GdHolder::default(),
+            pomodoro_short_break_spin_box: GdHolder::default(),
+            pomodoro_long_break_spin_box: GdHolder::default(),
+            pomodoro_long_break_every_spin_box: GdHolder::default(),
+            goal_check_button: GdHolder::default(),
+            goal_target_spin_box: GdHolder::default(),
+            goal_kind_option_button: GdHolder::default(),
+            goal_period_option_button: GdHolder::default(),
+            goal_boost_check_box: GdHolder::default(),
+            roll_weight_spin_box: GdHolder::default(),
+            deck_mode_check_button: GdHolder::default(),
             searchbar: GdHolder::default(),
             cards_in_container: GdHolder::default(),
             cards_out_container: GdHolder::default(),
             apply_button: GdHolder::default(),
             close_button: GdHolder::default(),
+            stats_button: GdHolder::default(),
+
+            // cached external UI elements
+            list_stats_view: GdHolder::default(),
 
             // observers
             observer_card_in_left_click: None,
@@ -271,6 +495,7 @@ impl PanelVirtual for ListModifyView {
 
             // state
             list: List::default(),
+            list_details: ListDetails::default(),
             items_in: HashSet::new(),
             items_out: HashSet::new(),
             mode: Mode::Add,
@@ -294,6 +519,39 @@ impl PanelVirtual for ListModifyView {
                 "text_changed".into(),
                 base.callable("on_description_text_edit_text_set"),
             );
+            self.reroll_budget_check_button = GdHolder::from_path(base, "VBoxContainer/TextMarginContainer/VBoxContainer/RerollBudgetHBoxContainer/CheckButton");
+            self.reroll_budget_check_button.ok_mut()?.connect(
+                "toggled".into(),
+                base.callable("on_reroll_budget_check_button_toggled"),
+            );
+            self.reroll_budget_spin_box = GdHolder::from_path(base, "VBoxContainer/TextMarginContainer/VBoxContainer/RerollBudgetHBoxContainer/SpinBox");
+            self.reroll_budget_period_option_button = GdHolder::from_path(base, "VBoxContainer/TextMarginContainer/VBoxContainer/RerollBudgetHBoxContainer/PeriodOptionButton");
+            self.cooldown_check_button = GdHolder::from_path(base, "VBoxContainer/TextMarginContainer/VBoxContainer/CooldownHBoxContainer/CheckButton");
+            self.cooldown_check_button.ok_mut()?.connect(
+                "toggled".into(),
+                base.callable("on_cooldown_check_button_toggled"),
+            );
+            self.cooldown_spin_box = GdHolder::from_path(base, "VBoxContainer/TextMarginContainer/VBoxContainer/CooldownHBoxContainer/SpinBox");
+            self.pomodoro_check_button = GdHolder::from_path(base, "VBoxContainer/TextMarginContainer/VBoxContainer/PomodoroHBoxContainer/CheckButton");
+            self.pomodoro_check_button.ok_mut()?.connect(
+                "toggled".into(),
+                base.callable("on_pomodoro_check_button_toggled"),
+            );
+            self.pomodoro_work_spin_box = GdHolder::from_path(base, "VBoxContainer/TextMarginContainer/VBoxContainer/PomodoroHBoxContainer/WorkSpinBox");
+            self.pomodoro_short_break_spin_box = GdHolder::from_path(base, "VBoxContainer/TextMarginContainer/VBoxContainer/PomodoroHBoxContainer/ShortBreakSpinBox");
+            self.pomodoro_long_break_spin_box = GdHolder::from_path(base, "VBoxContainer/TextMarginContainer/VBoxContainer/PomodoroHBoxContainer/LongBreakSpinBox");
+            self.pomodoro_long_break_every_spin_box = GdHolder::from_path(base, "VBoxContainer/TextMarginContainer/VBoxContainer/PomodoroHBoxContainer/LongBreakEverySpinBox");
+            self.goal_check_button = GdHolder::from_path(base, "VBoxContainer/TextMarginContainer/VBoxContainer/GoalHBoxContainer/CheckButton");
+            self.goal_check_button.ok_mut()?.connect(
+                "toggled".into(),
+                base.callable("on_goal_check_button_toggled"),
+            );
+            self.goal_target_spin_box = GdHolder::from_path(base, "VBoxContainer/TextMarginContainer/VBoxContainer/GoalHBoxContainer/TargetSpinBox");
+            self.goal_kind_option_button = GdHolder::from_path(base, "VBoxContainer/TextMarginContainer/VBoxContainer/GoalHBoxContainer/KindOptionButton");
+            self.goal_period_option_button = GdHolder::from_path(base, "VBoxContainer/TextMarginContainer/VBoxContainer/GoalHBoxContainer/PeriodOptionButton");
+            self.goal_boost_check_box = GdHolder::from_path(base, "VBoxContainer/TextMarginContainer/VBoxContainer/GoalHBoxContainer/BoostCheckBox");
+            self.roll_weight_spin_box = GdHolder::from_path(base, "VBoxContainer/TextMarginContainer/VBoxContainer/RollWeightHBoxContainer/SpinBox");
+            self.deck_mode_check_button = GdHolder::from_path(base, "VBoxContainer/TextMarginContainer/VBoxContainer/DeckModeCheckButton");
             self.searchbar = GdHolder::from_path(base, "VBoxContainer/SearchBarLineEdit");
             self.searchbar.ok_mut()?.connect(
                 "text_submitted".into(),
@@ -313,6 +571,14 @@ impl PanelVirtual for ListModifyView {
                 "button_up".into(),
                 base.callable("on_dialog_close_button_up"),
             );
+            self.stats_button = GdHolder::from_path(base, "StatsButton");
+            self.stats_button.ok_mut()?.connect(
+                "button_up".into(),
+                base.callable("on_stats_button_up"),
+            );
+
+            // cached external UI elements
+            self.list_stats_view = GdHolder::from_path(base, "../ListStatsView");
         } {
             Ok(_) => {},
             Err::<_, BoxedError>(e) => log_error(e)
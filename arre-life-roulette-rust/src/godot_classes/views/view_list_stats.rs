@@ -0,0 +1,185 @@
+use godot::engine::{Panel, PanelVirtual, Button, HBoxContainer, Label, ProgressBar, VBoxContainer};
+use godot::prelude::*;
+use crate::db::DB;
+use crate::errors::{ArreResult, BoxedError};
+use crate::fairness::{FairnessReport, roll_fairness_get};
+use crate::godot_classes::singletons::logger::log_error;
+use crate::godot_classes::utils::{GdHolder};
+use crate::list::{List, list_stats_get, ListStats};
+use crate::utils::format_duration;
+
+const UI_TEXT_TIMES_WORKED: &str = "Sessions: ";
+const UI_TEXT_TIME_SPENT: &str = "Time Spent: ";
+const UI_TEXT_ITEMS_NEVER_WORKED: &str = "Items Never Worked: {never_worked} of {items}";
+const UI_TEXT_ROLLS: &str = "Rolls Performed: ";
+const UI_TEXT_MEMBER: &str = "{item}: {time_spent} in {times_worked} sessions";
+const UI_TEXT_FAIRNESS: &str = "Fairness: χ² = {statistic} ({degrees} df), p = {p_value}";
+const UI_TEXT_FAIRNESS_NO_DRAWS: &str = "Fairness: no rolls with recorded odds yet";
+const UI_TEXT_FAIRNESS_INCONCLUSIVE: &str = ", too few rolls to conclude";
+const UI_TEXT_FAIRNESS_UNAUDITED: &str = ", {unaudited} older draws without odds left out";
+const UI_TEXT_FAIRNESS_ITEM: &str = "{item}: drawn {observed}% of {draws}, expected {expected}%";
+
+/// Aggregated stats of a list and how the time spent on it is spread across its items
+#[derive(GodotClass)]
+#[class(base=Panel)]
+pub struct ListStatsView {
+    #[base]
+    base: Base<Panel>,
+
+    // cached internal UI elements
+    pub list_name_label: GdHolder<Label>,
+    pub times_worked_label: GdHolder<Label>,
+    pub time_spent_label: GdHolder<Label>,
+    pub items_never_worked_label: GdHolder<Label>,
+    pub rolls_label: GdHolder<Label>,
+    pub members_container: GdHolder<VBoxContainer>,
+    pub member_rows: Vec<Gd<HBoxContainer>>,
+    pub fairness_label: GdHolder<Label>,
+    pub fairness_container: GdHolder<VBoxContainer>,
+    pub fairness_rows: Vec<Gd<Label>>,
+    pub close_button: GdHolder<Button>,
+
+    // state
+    list: List,
+    list_stats: ListStats,
+    fairness: Option<FairnessReport>,
+}
+
+#[godot_api]
+impl ListStatsView {
+    #[signal]
+    fn dialog_closed();
+
+    pub fn set_list(&mut self, list: List) -> ArreResult<()> {
+        {
+            let connection = &*DB.ok()?;
+            self.list_stats = list_stats_get(connection, list.get_id()?)?;
+            self.fairness = Some(roll_fairness_get(connection, list.get_id()?)?);
+        }
+        self.list = list;
+        self.refresh_display()
+    }
+
+    pub fn refresh_display(&mut self) -> ArreResult<()> {
+        let stats = &self.list_stats;
+        self.list_name_label.ok_mut()?.set_text(self.list.name.clone().into());
+        self.times_worked_label.ok_mut()?.set_text(format!("{}{}", UI_TEXT_TIMES_WORKED, stats.times_worked).into());
+        self.time_spent_label.ok_mut()?.set_text(format!("{}{}", UI_TEXT_TIME_SPENT, format_duration(stats.time_spent)).into());
+        let never_worked = UI_TEXT_ITEMS_NEVER_WORKED
+            .replace("{never_worked}", &stats.items_never_worked.to_string())
+            .replace("{items}", &stats.members.len().to_string());
+        self.items_never_worked_label.ok_mut()?.set_text(never_worked.into());
+        self.rolls_label.ok_mut()?.set_text(format!("{}{}", UI_TEXT_ROLLS, stats.rolls_count).into());
+
+        self.member_rows.drain(..).for_each(|mut row| row.queue_free());
+        for member in stats.members.iter() {
+            let mut row = HBoxContainer::new_alloc();
+            let mut share_bar = ProgressBar::new_alloc();
+            share_bar.set_custom_minimum_size(Vector2::new(200.0, 0.0));
+            share_bar.set_max(1.0);
+            share_bar.set_step(0.01);
+            share_bar.set_value(stats.time_share(member));
+            row.add_child(share_bar.upcast());
+            let mut label = Label::new_alloc();
+            let text = UI_TEXT_MEMBER
+                .replace("{item}", &member.item.name)
+                .replace("{time_spent}", &format_duration(member.time_spent))
+                .replace("{times_worked}", &member.times_worked.to_string());
+            label.set_text(text.into());
+            row.add_child(label.upcast());
+            self.members_container.ok_mut()?.add_child(row.share().upcast());
+            self.member_rows.push(row);
+        }
+        self.refresh_fairness_display()
+    }
+
+    fn refresh_fairness_display(&mut self) -> ArreResult<()> {
+        self.fairness_rows.drain(..).for_each(|mut row| row.queue_free());
+        let Some(fairness) = &self.fairness else { return Ok(()) };
+        let mut text = if fairness.draws_count == 0 {
+            UI_TEXT_FAIRNESS_NO_DRAWS.to_string()
+        } else {
+            let mut text = UI_TEXT_FAIRNESS
+                .replace("{statistic}", &format!("{:.2}", fairness.chi_square.statistic))
+                .replace("{degrees}", &fairness.chi_square.degrees_of_freedom.to_string())
+                .replace("{p_value}", &format!("{:.3}", fairness.chi_square.p_value));
+            if !fairness.is_conclusive() {
+                text.push_str(UI_TEXT_FAIRNESS_INCONCLUSIVE);
+            }
+            text
+        };
+        if fairness.unaudited_draws_count > 0 {
+            text.push_str(&UI_TEXT_FAIRNESS_UNAUDITED.replace("{unaudited}", &fairness.unaudited_draws_count.to_string()));
+        }
+        self.fairness_label.ok_mut()?.set_text(text.into());
+
+        for item in fairness.items.iter() {
+            let mut label = Label::new_alloc();
+            let text = UI_TEXT_FAIRNESS_ITEM
+                .replace("{item}", &item.item_name)
+                .replace("{observed}", &format!("{:.1}", item.observed_frequency(fairness.draws_count) * 100.0))
+                .replace("{draws}", &fairness.draws_count.to_string())
+                .replace("{expected}", &format!("{:.1}", item.expected_probability(fairness.draws_count) * 100.0));
+            label.set_text(text.into());
+            self.fairness_container.ok_mut()?.add_child(label.share().upcast());
+            self.fairness_rows.push(label);
+        }
+        Ok(())
+    }
+
+    #[func]
+    fn on_dialog_close_button_up(&mut self) {
+        self.hide();
+        self.emit_signal("dialog_closed".into(), &[]);
+    }
+}
+
+#[godot_api]
+impl PanelVirtual for ListStatsView {
+    fn init(base: Base<Self::Base>) -> Self {
+        Self {
+            base,
+
+            // cached internal UI elements
+            list_name_label: GdHolder::default(),
+            times_worked_label: GdHolder::default(),
+            time_spent_label: GdHolder::default(),
+            items_never_worked_label: GdHolder::default(),
+            rolls_label: GdHolder::default(),
+            members_container: GdHolder::default(),
+            member_rows: vec![],
+            fairness_label: GdHolder::default(),
+            fairness_container: GdHolder::default(),
+            fairness_rows: vec![],
+            close_button: GdHolder::default(),
+
+            // state
+            list: List::default(),
+            list_stats: ListStats::default(),
+            fairness: None,
+        }
+    }
+    fn ready(&mut self) {
+        match try {
+            let base = &self.base;
+
+            // cached internal UI elements
+            self.list_name_label = GdHolder::from_path(base, "VBoxContainer/ListNameLabel");
+            self.times_worked_label = GdHolder::from_path(base, "VBoxContainer/VBoxContainer/TimesWorkedLabel");
+            self.time_spent_label = GdHolder::from_path(base, "VBoxContainer/VBoxContainer/TimeSpentLabel");
+            self.items_never_worked_label = GdHolder::from_path(base, "VBoxContainer/VBoxContainer/ItemsNeverWorkedLabel");
+            self.rolls_label = GdHolder::from_path(base, "VBoxContainer/VBoxContainer/RollsLabel");
+            self.members_container = GdHolder::from_path(base, "VBoxContainer/ScrollContainer/MembersVBoxContainer");
+            self.fairness_label = GdHolder::from_path(base, "VBoxContainer/FairnessLabel");
+            self.fairness_container = GdHolder::from_path(base, "VBoxContainer/FairnessScrollContainer/FairnessVBoxContainer");
+            self.close_button = GdHolder::from_path(base, "DialogCloseButton");
+            self.close_button.ok_mut()?.connect(
+                "button_up".into(),
+                base.callable("on_dialog_close_button_up"),
+            );
+        } {
+            Ok(_) => {}
+            Err::<_, BoxedError>(e) => log_error(e)
+        }
+    }
+}
@@ -1,8 +1,10 @@
 use bus::BusReader;
-use godot::engine::{Control, ControlVirtual, Button, LineEdit};
+use chrono::Utc;
+use godot::engine::{Control, ControlVirtual, Button, CheckButton, LineEdit};
 use godot::prelude::*;
 use crate::db::DB;
 use crate::errors::{ArreResult, BoxedError};
+use crate::goal::list_goal_progress_get;
 use crate::godot_classes::containers::cards_flow_container::CardsFlowContainer;
 use crate::godot_classes::element_card::{Content, ElementCard};
 use crate::godot_classes::singletons::logger::log_error;
@@ -10,7 +12,7 @@ use crate::godot_classes::singletons::signals::Signals;
 use crate::godot_classes::utils::{GdHolder, get_singleton};
 use crate::godot_classes::views::view_list_modify::ListModifyView;
 use crate::godot_classes::views::roll::view_roll::RollView;
-use crate::list::{List, list_get_all, list_search};
+use crate::list::{List, ListId, list_get_all, list_search};
 
 #[derive(GodotClass)]
 #[class(base=Control)]
@@ -22,6 +24,9 @@ pub struct ListsView {
     pub list_add_button: GdHolder<Button>,
     pub cards_container: GdHolder<CardsFlowContainer>,
     pub searchbar: GdHolder<LineEdit>,
+    pub multi_select_check_button: GdHolder<CheckButton>,
+    pub roll_selected_button: GdHolder<Button>,
+    pub roll_all_items_button: GdHolder<Button>,
 
     // cached external UI elements
     pub list_roll_view: GdHolder<RollView>,
@@ -34,6 +39,8 @@ pub struct ListsView {
     // state
     lists: Vec<List>,
     search_term: Option<String>,
+    is_multi_select: bool, // left click selects lists to roll together instead of rolling right away
+    selected_lists: Vec<ListId>,
 }
 
 #[godot_api]
@@ -50,6 +57,44 @@ impl ListsView {
         }
     }
 
+    #[func]
+    fn on_multi_select_check_button_toggled(&mut self, checked: bool) {
+        self.is_multi_select = checked;
+        self.selected_lists.clear();
+        self.refresh_display();
+    }
+
+    #[func]
+    fn on_roll_selected_button_up(&mut self) {
+        match try {
+            // Keep the order in which lists were selected
+            let lists = self.selected_lists
+                .iter()
+                .filter_map(|list_id| self.lists.iter().find(|list| list.id.map(|id| *id) == Some(**list_id)).cloned())
+                .collect::<Vec<_>>();
+            let mut view = self.list_roll_view.ok_mut()?.bind_mut();
+            view.set_lists(lists)?;
+            view.refresh_view();
+            view.show();
+        } {
+            Ok(_) => {},
+            Err::<_, BoxedError>(e) => log_error(e)
+        }
+    }
+
+    #[func]
+    fn on_roll_all_items_button_up(&mut self) {
+        match try {
+            let mut view = self.list_roll_view.ok_mut()?.bind_mut();
+            view.set_all_items();
+            view.refresh_view();
+            view.show();
+        } {
+            Ok(_) => {},
+            Err::<_, BoxedError>(e) => log_error(e)
+        }
+    }
+
     #[func]
     fn on_view_selected(&mut self) {
         self.refresh_full();
@@ -90,20 +135,65 @@ impl ListsView {
     #[func]
     fn refresh_display(&mut self) {
         match try {
-            self.cards_container.ok_mut()?.bind_mut().set_cards(self.lists.clone());
+            {
+                let mut cards_container = self.cards_container.ok_mut()?.bind_mut();
+                cards_container.set_cards(self.lists.clone());
+                let connection = &*DB.ok()?;
+                let now = Utc::now();
+                for card in cards_container.item_cards.iter_mut() {
+                    let mut card = card.bind_mut();
+                    let goal_progress = match &card.content {
+                        Content::List(list) => list_goal_progress_get(connection, list.get_id()?, now)?,
+                        _ => continue,
+                    };
+                    card.set_goal_progress(goal_progress);
+                }
+                if self.is_multi_select {
+                    for card in cards_container.item_cards.iter_mut() {
+                        let mut card = card.bind_mut();
+                        let is_selected = match &card.content {
+                            Content::List(list) => {
+                                let list_id = list.get_id()?;
+                                self.selected_lists.iter().any(|id| **id == *list_id)
+                            },
+                            _ => continue,
+                        };
+                        card.set_modulate(Self::selection_modulate(is_selected));
+                    }
+                }
+            }
+            self.roll_selected_button.ok_mut()?.set_visible(self.is_multi_select);
+            self.roll_selected_button.ok_mut()?.set_disabled(self.selected_lists.is_empty());
         } {
             Ok(_) => {},
             Err::<_, BoxedError>(e) => log_error(e)
         }
     }
 
+    fn selection_modulate(is_selected: bool) -> Color {
+        if is_selected { Color::from_rgba(1.0, 1.0, 1.0, 1.0) } else { Color::from_rgba(1.0, 1.0, 1.0, 0.3) }
+    }
+
     fn on_list_card_left_click(&mut self, card_id: InstanceId) -> ArreResult<()> {
         let mut card = GdHolder::<ElementCard>::from_instance_id(card_id);
+        if self.is_multi_select {
+            let mut card = card.ok_mut()?.bind_mut();
+            if let Content::List(list) = &card.content {
+                let list_id = list.get_id()?;
+                let is_selected = match self.selected_lists.iter().position(|id| **id == *list_id) {
+                    Some(position) => { self.selected_lists.remove(position); false },
+                    None => { self.selected_lists.push(list_id); true },
+                };
+                card.set_modulate(Self::selection_modulate(is_selected));
+                self.roll_selected_button.ok_mut()?.set_disabled(self.selected_lists.is_empty());
+            }
+            return Ok(());
+        }
         {
             let card = card.ok_mut()?.bind();
             if let Content::List(list) = &card.content {
                 let mut view = self.list_roll_view.ok_mut()?.bind_mut();
-                view.set_list(list.clone());
+                view.set_list(list.clone())?;
                 view.refresh_view();
                 view.show();
             }
@@ -135,6 +225,9 @@ impl ControlVirtual for ListsView {
             list_add_button: GdHolder::default(),
             cards_container: GdHolder::default(),
             searchbar: GdHolder::default(),
+            multi_select_check_button: GdHolder::default(),
+            roll_selected_button: GdHolder::default(),
+            roll_all_items_button: GdHolder::default(),
 
             // cached external UI elements
             list_roll_view: GdHolder::default(),
@@ -146,6 +239,8 @@ impl ControlVirtual for ListsView {
 
             lists: vec![],
             search_term: None,
+            is_multi_select: false,
+            selected_lists: vec![],
         }
     }
     fn ready(&mut self) {
@@ -167,6 +262,21 @@ impl ControlVirtual for ListsView {
                 "text_submitted".into(),
                 base.callable("on_search_request"),
             );
+            self.multi_select_check_button = GdHolder::from_path(base, "VBoxContainer/RollHBoxContainer/MultiSelectCheckButton");
+            self.multi_select_check_button.ok_mut()?.connect(
+                "toggled".into(),
+                base.callable("on_multi_select_check_button_toggled"),
+            );
+            self.roll_selected_button = GdHolder::from_path(base, "VBoxContainer/RollHBoxContainer/RollSelectedButton");
+            self.roll_selected_button.ok_mut()?.connect(
+                "button_up".into(),
+                base.callable("on_roll_selected_button_up"),
+            );
+            self.roll_all_items_button = GdHolder::from_path(base, "VBoxContainer/RollHBoxContainer/RollAllItemsButton");
+            self.roll_all_items_button.ok_mut()?.connect(
+                "button_up".into(),
+                base.callable("on_roll_all_items_button_up"),
+            );
 
             self.list_roll_view = GdHolder::from_path(base, "../../RollView");
             self.list_roll_view.ok_mut()?.bind_mut().connect(
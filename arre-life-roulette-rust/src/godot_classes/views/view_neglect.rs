@@ -0,0 +1,167 @@
+use chrono::Utc;
+use godot::engine::{Panel, PanelVirtual, Button, Label, OptionButton, VBoxContainer};
+use godot::prelude::*;
+use crate::db::DB;
+use crate::errors::{ArreResult, BoxedError};
+use crate::godot_classes::singletons::logger::log_error;
+use crate::godot_classes::utils::{GdHolder, ItemFilterChoices};
+use crate::godot_classes::views::roll::view_roll::RollView;
+use crate::neglect::{neglect_report_get, NeglectedItem};
+
+const UI_TEXT_WORKED: &str = "{item}: last worked {days} days ago";
+const UI_TEXT_NEVER_WORKED: &str = "{item}: never worked, added {days} days ago";
+const UI_TEXT_ROLL_TITLE: &str = "Neglected Items";
+
+/// Items that were not worked on for the longest time, which can be handed over to the roll
+#[derive(GodotClass)]
+#[class(base=Panel)]
+pub struct NeglectView {
+    #[base]
+    base: Base<Panel>,
+
+    // cached internal UI elements
+    pub list_option_button: GdHolder<OptionButton>,
+    pub tag_option_button: GdHolder<OptionButton>,
+    pub items_container: GdHolder<VBoxContainer>,
+    pub item_rows: Vec<Gd<Label>>,
+    pub roll_button: GdHolder<Button>,
+    pub close_button: GdHolder<Button>,
+
+    // cached external UI elements
+    pub roll_view: GdHolder<RollView>,
+
+    // state
+    filter_choices: ItemFilterChoices,
+    report: Vec<NeglectedItem>,
+}
+
+#[godot_api]
+impl NeglectView {
+    #[signal]
+    fn dialog_closed();
+
+    /// Reload the lists and tags to filter on, resetting the filters
+    pub fn refresh_full(&mut self) -> ArreResult<()> {
+        self.filter_choices.fill(self.list_option_button.ok_mut()?, self.tag_option_button.ok_mut()?)?;
+        self.refresh_report()
+    }
+
+    fn refresh_report(&mut self) -> ArreResult<()> {
+        let filter = self.filter_choices.selected(self.list_option_button.ok()?, self.tag_option_button.ok()?)?;
+        self.report = neglect_report_get(&*DB.ok()?, &filter, Utc::now())?;
+        self.refresh_display()
+    }
+
+    fn refresh_display(&mut self) -> ArreResult<()> {
+        self.item_rows.drain(..).for_each(|mut row| row.queue_free());
+        for neglected in self.report.iter() {
+            let mut label = Label::new_alloc();
+            let text = match neglected.last_worked {
+                Some(_) => UI_TEXT_WORKED,
+                None => UI_TEXT_NEVER_WORKED,
+            };
+            let text = text
+                .replace("{item}", &neglected.item.name)
+                .replace("{days}", &neglected.days_neglected().to_string());
+            label.set_text(text.into());
+            self.items_container.ok_mut()?.add_child(label.share().upcast());
+            self.item_rows.push(label);
+        }
+        self.roll_button.ok_mut()?.set_disabled(self.report.is_empty());
+        Ok(())
+    }
+
+    #[func]
+    fn on_filter_option_button_item_selected(&mut self, _index: i64) {
+        match try {
+            self.refresh_report()?;
+        } {
+            Ok(_) => {}
+            Err::<_, BoxedError>(e) => log_error(e)
+        }
+    }
+
+    #[func]
+    fn on_roll_button_up(&mut self) {
+        match try {
+            let item_ids = self.report
+                .iter()
+                .map(|neglected| neglected.item.get_id())
+                .collect::<ArreResult<Vec<_>>>()?;
+            {
+                let mut view = self.roll_view.ok_mut()?.bind_mut();
+                view.set_items(UI_TEXT_ROLL_TITLE.to_string(), item_ids);
+                view.refresh_view();
+                view.show();
+            }
+            self.on_dialog_close_button_up();
+        } {
+            Ok(_) => {}
+            Err::<_, BoxedError>(e) => log_error(e)
+        }
+    }
+
+    #[func]
+    fn on_dialog_close_button_up(&mut self) {
+        self.hide();
+        self.emit_signal("dialog_closed".into(), &[]);
+    }
+}
+
+#[godot_api]
+impl PanelVirtual for NeglectView {
+    fn init(base: Base<Self::Base>) -> Self {
+        Self {
+            base,
+
+            // cached internal UI elements
+            list_option_button: GdHolder::default(),
+            tag_option_button: GdHolder::default(),
+            items_container: GdHolder::default(),
+            item_rows: vec![],
+            roll_button: GdHolder::default(),
+            close_button: GdHolder::default(),
+
+            // cached external UI elements
+            roll_view: GdHolder::default(),
+
+            // state
+            filter_choices: ItemFilterChoices::default(),
+            report: vec![],
+        }
+    }
+    fn ready(&mut self) {
+        match try {
+            let base = &self.base;
+
+            // cached internal UI elements
+            self.list_option_button = GdHolder::from_path(base, "VBoxContainer/FilterHBoxContainer/ListOptionButton");
+            self.list_option_button.ok_mut()?.connect(
+                "item_selected".into(),
+                base.callable("on_filter_option_button_item_selected"),
+            );
+            self.tag_option_button = GdHolder::from_path(base, "VBoxContainer/FilterHBoxContainer/TagOptionButton");
+            self.tag_option_button.ok_mut()?.connect(
+                "item_selected".into(),
+                base.callable("on_filter_option_button_item_selected"),
+            );
+            self.items_container = GdHolder::from_path(base, "VBoxContainer/ScrollContainer/ItemsVBoxContainer");
+            self.roll_button = GdHolder::from_path(base, "VBoxContainer/RollButton");
+            self.roll_button.ok_mut()?.connect(
+                "button_up".into(),
+                base.callable("on_roll_button_up"),
+            );
+            self.close_button = GdHolder::from_path(base, "DialogCloseButton");
+            self.close_button.ok_mut()?.connect(
+                "button_up".into(),
+                base.callable("on_dialog_close_button_up"),
+            );
+
+            // cached external UI elements
+            self.roll_view = GdHolder::from_path(base, "../RollView");
+        } {
+            Ok(_) => {}
+            Err::<_, BoxedError>(e) => log_error(e)
+        }
+    }
+}
@@ -0,0 +1,150 @@
+use chrono::{Local, Utc};
+use godot::engine::{Panel, PanelVirtual, Button, Label};
+use godot::prelude::*;
+use crate::db::DB;
+use crate::errors::{ArreError, ArreResult, BoxedError};
+use crate::godot_classes::singletons::logger::log_error;
+use crate::godot_classes::utils::{GdHolder};
+use crate::godot_classes::views::roll::view_roll::RollView;
+use crate::item::item_get;
+use crate::list::list_get;
+use crate::session::{active_session_discard, active_session_finish, active_session_get};
+use crate::utils::format_duration;
+
+const UI_TEXT_SESSION_IN_PROGRESS: &str = "Work on \"{item}\" started {start} and was not finished, {elapsed} ago.";
+const UI_TEXT_START_FORMAT: &str = "%a %H:%M";
+
+/// Offers to resume, finish or discard the session left in progress when the app was closed
+#[derive(GodotClass)]
+#[class(base=Panel)]
+pub struct SessionResumeView {
+    #[base]
+    base: Base<Panel>,
+
+    // cached internal UI elements
+    pub session_label: GdHolder<Label>,
+    pub resume_button: GdHolder<Button>,
+    pub finish_button: GdHolder<Button>,
+    pub discard_button: GdHolder<Button>,
+
+    // cached external UI elements
+    pub roll_view: GdHolder<RollView>,
+}
+
+#[godot_api]
+impl SessionResumeView {
+    #[signal]
+    fn dialog_closed();
+
+    /// Show the view if a session was left in progress
+    pub fn refresh_display(&mut self) -> ArreResult<()> {
+        let connection = &*DB.ok()?;
+        let Some(active) = active_session_get(connection)? else {
+            self.base.hide();
+            return Ok(());
+        };
+        let item = item_get(connection, active.item_id)?;
+        let text = UI_TEXT_SESSION_IN_PROGRESS
+            .replace("{item}", &item.name)
+            .replace("{start}", &active.start.with_timezone(&Local).format(UI_TEXT_START_FORMAT).to_string())
+            .replace("{elapsed}", &format_duration(Utc::now() - *active.start));
+        self.session_label.ok_mut()?.set_text(text.into());
+        self.base.show();
+        Ok(())
+    }
+
+    #[func]
+    fn on_resume_button_up(&mut self) {
+        match try {
+            let (item, list) = {
+                let connection = &*DB.ok()?;
+                let active = active_session_get(connection)?.ok_or(ArreError::NoActiveSession())?;
+                let list = active.list_id.map(|list_id| list_get(connection, list_id)).transpose()?;
+                (item_get(connection, active.item_id)?, list)
+            };
+            let mut view = self.roll_view.ok_mut()?.bind_mut();
+            view.resume_session(item, list)?;
+            view.show();
+            self.close_dialog();
+        } {
+            Ok(_) => {}
+            Err::<_, BoxedError>(e) => log_error(e)
+        }
+    }
+
+    #[func]
+    fn on_finish_button_up(&mut self) {
+        match try {
+            active_session_finish(&*DB.ok()?, Utc::now())?;
+            self.close_dialog();
+        } {
+            Ok(_) => {}
+            Err::<_, BoxedError>(e) => log_error(e)
+        }
+    }
+
+    #[func]
+    fn on_discard_button_up(&mut self) {
+        match try {
+            active_session_discard(&*DB.ok()?)?;
+            self.close_dialog();
+        } {
+            Ok(_) => {}
+            Err::<_, BoxedError>(e) => log_error(e)
+        }
+    }
+
+    fn close_dialog(&mut self) {
+        self.base.hide();
+        self.emit_signal("dialog_closed".into(), &[]);
+    }
+}
+
+#[godot_api]
+impl PanelVirtual for SessionResumeView {
+    fn init(base: Base<Self::Base>) -> Self {
+        Self {
+            base,
+
+            // cached internal UI elements
+            session_label: GdHolder::default(),
+            resume_button: GdHolder::default(),
+            finish_button: GdHolder::default(),
+            discard_button: GdHolder::default(),
+
+            // cached external UI elements
+            roll_view: GdHolder::default(),
+        }
+    }
+    fn ready(&mut self) {
+        match try {
+            let base = &self.base;
+
+            // cached internal UI elements
+            self.session_label = GdHolder::from_path(base, "VBoxContainer/SessionLabel");
+            self.resume_button = GdHolder::from_path(base, "VBoxContainer/HBoxContainer/ResumeButton");
+            self.resume_button.ok_mut()?.connect(
+                "button_up".into(),
+                base.callable("on_resume_button_up"),
+            );
+            self.finish_button = GdHolder::from_path(base, "VBoxContainer/HBoxContainer/FinishButton");
+            self.finish_button.ok_mut()?.connect(
+                "button_up".into(),
+                base.callable("on_finish_button_up"),
+            );
+            self.discard_button = GdHolder::from_path(base, "VBoxContainer/HBoxContainer/DiscardButton");
+            self.discard_button.ok_mut()?.connect(
+                "button_up".into(),
+                base.callable("on_discard_button_up"),
+            );
+
+            // cached external UI elements
+            self.roll_view = GdHolder::from_path(base, "../RollView");
+
+            self.refresh_display()?;
+        } {
+            Ok(_) => {}
+            Err::<_, BoxedError>(e) => log_error(e),
+        }
+    }
+}
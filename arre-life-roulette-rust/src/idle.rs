@@ -0,0 +1,66 @@
+use chrono::{DateTime, Duration, Utc};
+
+/// Tells when the user went idle and when they came back, from the moments activity is reported
+#[derive(Debug, Clone)]
+pub struct IdleDetector {
+    pub timeout: Option<Duration>, // None never considers the user idle
+    pub last_activity: DateTime<Utc>,
+    pub idle_since: Option<DateTime<Utc>>, // None while the user is active
+}
+
+impl IdleDetector {
+    pub fn new(timeout: Option<Duration>, now: DateTime<Utc>) -> Self {
+        IdleDetector { timeout, last_activity: now, idle_since: None }
+    }
+
+    /// Report the user as active, returns the start of the idle period they came back from, if any
+    pub fn activity(&mut self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        self.last_activity = now;
+        self.idle_since.take()
+    }
+
+    /// Returns the moment the user went idle, once the timeout elapsed since their last activity.
+    /// It is returned only once per idle period.
+    pub fn check(&mut self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let timeout = self.timeout?;
+        if self.idle_since.is_some() || now - self.last_activity < timeout {
+            return None;
+        }
+        self.idle_since = Some(self.last_activity);
+        self.idle_since
+    }
+
+    pub fn is_idle(&self) -> bool {
+        self.idle_since.is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+    use super::*;
+
+    #[rstest]
+    fn idle_after_timeout_until_activity() {
+        let start = Utc::now();
+        let mut detector = IdleDetector::new(Some(Duration::minutes(10)), start);
+        assert_eq!(detector.check(start + Duration::minutes(9)), None);
+        assert_eq!(detector.activity(start + Duration::minutes(9)), None, "Not idle yet, so nothing to come back from");
+        assert_eq!(detector.check(start + Duration::minutes(18)), None, "Activity restarts the timeout");
+
+        let idle_since = start + Duration::minutes(9);
+        assert_eq!(detector.check(start + Duration::minutes(19)), Some(idle_since));
+        assert!(detector.is_idle());
+        assert_eq!(detector.check(start + Duration::hours(8)), None, "Going idle is reported once");
+        assert_eq!(detector.activity(start + Duration::hours(8)), Some(idle_since));
+        assert!(!detector.is_idle());
+    }
+
+    #[rstest]
+    fn never_idle_without_timeout() {
+        let start = Utc::now();
+        let mut detector = IdleDetector::new(None, start);
+        assert_eq!(detector.check(start + Duration::days(1)), None);
+        assert!(!detector.is_idle());
+    }
+}
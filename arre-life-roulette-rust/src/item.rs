@@ -3,6 +3,8 @@ use std::hash::{Hash, Hasher};
 use chrono::{Utc};
 use rusqlite::{Connection, Result, Row};
 use crate::errors::{ArreError, ArreResult};
+use crate::list::ListId;
+use crate::tag::TagId;
 use crate::utils::{ArreDateTime, Id};
 
 pub fn item_create(conn: &Connection, name: impl AsRef<str>, description: impl AsRef<str>) -> ArreResult<Item> {
@@ -174,6 +176,13 @@ impl Hash for Item {
     }
 }
 
+/// Restricts reports to the items of a list and/or a tag.
+/// Queries using it bind `list_id` and `tag_id` as parameters checked against NULL.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ItemFilter {
+    pub list_id: Option<ListId>,
+    pub tag_id: Option<TagId>,
+}
 
 #[cfg(test)]
 mod tests {
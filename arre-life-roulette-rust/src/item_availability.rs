@@ -0,0 +1,244 @@
+use std::fmt::{Display, Formatter};
+use chrono::{Datelike, NaiveDate, NaiveDateTime, Timelike, Utc, Weekday};
+use rusqlite::{Connection, Result, Row};
+use rusqlite::types::{FromSql, FromSqlResult, ToSql, ToSqlOutput, Type, ValueRef};
+use crate::errors::{ArreError, ArreResult};
+use crate::item::ItemId;
+
+pub fn item_availability_update(conn: &Connection, availability: &ItemAvailability) -> ArreResult<()> {
+    conn.execute("
+        UPDATE item_availability
+        SET updated_date = ?2, weekdays = ?3, hour_start = ?4, hour_end = ?5, date_start = ?6, date_end = ?7
+        WHERE item_id = ?1
+    ", (
+        availability.get_id()?,
+        Utc::now().to_string(),
+        availability.weekdays,
+        availability.hours.map(|(start, _)| start),
+        availability.hours.map(|(_, end)| end),
+        availability.dates.map(|(start, _)| start.to_string()),
+        availability.dates.map(|(_, end)| end.to_string()),
+    ))?;
+    Ok(())
+}
+
+pub fn item_availability_get(conn: &Connection, id: impl Into<ItemId>) -> ArreResult<ItemAvailability> {
+    let mut stmt = conn.prepare("
+        SELECT
+         item_id, weekdays, hour_start, hour_end, date_start, date_end
+        FROM item_availability
+        WHERE item_id = ?1
+    ")?;
+    Ok(stmt.query_row([id.into()], |row| {
+        ItemAvailability::from_row(row)
+    })?)
+}
+
+/// Set of days of the week, stored as a bitmask with Monday as the lowest bit
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Default)]
+pub struct WeekdaySet(u8);
+
+impl WeekdaySet {
+    pub const ALL_DAYS: [Weekday; 7] = [
+        Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Thu, Weekday::Fri, Weekday::Sat, Weekday::Sun,
+    ];
+
+    pub fn contains(&self, weekday: Weekday) -> bool {
+        self.0 & (1 << weekday.num_days_from_monday()) != 0
+    }
+
+    pub fn insert(&mut self, weekday: Weekday) {
+        self.0 |= 1 << weekday.num_days_from_monday();
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Weekday> + '_ {
+        Self::ALL_DAYS.into_iter().filter(|weekday| self.contains(*weekday))
+    }
+}
+
+impl FromIterator<Weekday> for WeekdaySet {
+    fn from_iter<I: IntoIterator<Item = Weekday>>(iter: I) -> Self {
+        let mut set = WeekdaySet::default();
+        iter.into_iter().for_each(|weekday| set.insert(weekday));
+        set
+    }
+}
+
+impl ToSql for WeekdaySet {
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>> {
+        self.0.to_sql()
+    }
+}
+
+impl FromSql for WeekdaySet {
+    fn column_result(value: ValueRef) -> FromSqlResult<Self> {
+        u8::column_result(value).map(WeekdaySet)
+    }
+}
+
+/// Reason for an item not being available at a given moment
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Unavailability {
+    Weekdays(WeekdaySet),
+    Hours(u32, u32),
+    Dates(NaiveDate, NaiveDate),
+}
+
+impl Display for Unavailability {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Unavailability::Weekdays(weekdays) => {
+                let weekdays = weekdays.iter().map(|weekday| weekday.to_string()).collect::<Vec<_>>();
+                write!(f, "Only on {}", weekdays.join(", "))
+            }
+            Unavailability::Hours(start, end) => write!(f, "Only {:0>2}:00-{:0>2}:00", start, end),
+            Unavailability::Dates(start, end) => write!(f, "Only {} to {}", start, end),
+        }
+    }
+}
+
+/// When an item can be rolled, in local time. Every rule that is set must be met.
+#[derive(Debug, Clone, Eq, PartialEq, Default)]
+pub struct ItemAvailability {
+    pub id: Option<ItemId>, // None indicates it's not persisted
+    pub weekdays: Option<WeekdaySet>,
+    pub hours: Option<(u32, u32)>, // [start, end) hours of the day, wraps past midnight if start > end
+    pub dates: Option<(NaiveDate, NaiveDate)>, // inclusive
+}
+
+impl ItemAvailability {
+    pub fn from_row(row: &Row) -> Result<ItemAvailability> {
+        Self::from_row_at(row, 0)
+    }
+
+    /// Read the availability from columns starting at `first`, which is expected to hold the item id
+    pub fn from_row_at(row: &Row, first: usize) -> Result<ItemAvailability> {
+        let hour_start = row.get::<_, Option<u32>>(first + 2)?;
+        let hour_end = row.get::<_, Option<u32>>(first + 3)?;
+        let date_start = Self::date_from_row(row, first + 4)?;
+        let date_end = Self::date_from_row(row, first + 5)?;
+        Ok(ItemAvailability {
+            id: Some(row.get(first)?),
+            weekdays: row.get(first + 1)?,
+            hours: hour_start.zip(hour_end),
+            dates: date_start.zip(date_end),
+        })
+    }
+
+    fn date_from_row(row: &Row, idx: usize) -> Result<Option<NaiveDate>> {
+        row.get::<_, Option<String>>(idx)?
+            .map(|date| date.parse::<NaiveDate>())
+            .transpose()
+            .map_err(|e| rusqlite::Error::FromSqlConversionFailure(idx, Type::Text, Box::new(e)))
+    }
+
+    pub fn get_id(&self) -> ArreResult<ItemId> {
+        self.id.ok_or(ArreError::ItemNotPersisted().into())
+    }
+
+    /// First rule not met at the given local time, None if the item is available
+    pub fn check(&self, now: NaiveDateTime) -> Option<Unavailability> {
+        if let Some(weekdays) = self.weekdays {
+            if !weekdays.contains(now.weekday()) {
+                return Some(Unavailability::Weekdays(weekdays));
+            }
+        }
+        if let Some((start, end)) = self.hours {
+            let hour = now.hour();
+            let is_within = match start.cmp(&end) {
+                std::cmp::Ordering::Less => start <= hour && hour < end,
+                std::cmp::Ordering::Greater => start <= hour || hour < end,
+                std::cmp::Ordering::Equal => true,
+            };
+            if !is_within {
+                return Some(Unavailability::Hours(start, end));
+            }
+        }
+        if let Some((start, end)) = self.dates {
+            if now.date() < start || end < now.date() {
+                return Some(Unavailability::Dates(start, end));
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+    use rusqlite::Connection;
+    use crate::item::{item_create, item_delete};
+    use crate::test_fixtures::{conn, TestFactory};
+    use super::*;
+
+    fn at(date: (i32, u32, u32), hour: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(date.0, date.1, date.2).unwrap().and_hms_opt(hour, 30, 0).unwrap()
+    }
+
+    #[rstest]
+    fn item_creates_removes_availability(conn: Connection) -> ArreResult<()> {
+        let tf = TestFactory::new(&conn);
+        let item_id = item_create(&conn, "Name", "Description")?.get_id()?;
+        let availability = item_availability_get(&conn, item_id)?;
+        assert_eq!(availability, ItemAvailability { id: Some(item_id), ..Default::default() });
+
+        item_delete(&conn, item_id)?;
+        tf.assert_table_count("item_availability", 0)?;
+        Ok(())
+    }
+
+    #[rstest]
+    #[case(None, None, None)]
+    #[case(Some(WeekdaySet::from_iter([Weekday::Mon, Weekday::Sun])), None, None)]
+    #[case(None, Some((22, 6)), Some((NaiveDate::from_ymd_opt(2023, 7, 1).unwrap(), NaiveDate::from_ymd_opt(2023, 8, 31).unwrap())))]
+    fn update_item_availability(
+        conn: Connection,
+        #[case] weekdays: Option<WeekdaySet>,
+        #[case] hours: Option<(u32, u32)>,
+        #[case] dates: Option<(NaiveDate, NaiveDate)>,
+    ) -> ArreResult<()> {
+        let item_id = item_create(&conn, "Name", "Description")?.get_id()?;
+        let availability = ItemAvailability { id: Some(item_id), weekdays, hours, dates };
+        item_availability_update(&conn, &availability)?;
+        assert_eq!(item_availability_get(&conn, item_id)?, availability);
+        Ok(())
+    }
+
+    #[rstest]
+    fn update_on_default_fails(conn: Connection) {
+        let result = item_availability_update(&conn, &Default::default());
+        assert!(result.is_err(), "Update on default should fail");
+    }
+
+    #[rstest]
+    // 2023-07-13 is a Thursday
+    #[case(ItemAvailability::default(), at((2023, 7, 13), 12), None)]
+    #[case(
+        ItemAvailability { weekdays: Some(WeekdaySet::from_iter([Weekday::Mon, Weekday::Thu])), ..Default::default() },
+        at((2023, 7, 13), 12), None
+    )]
+    #[case(
+        ItemAvailability { weekdays: Some(WeekdaySet::from_iter([Weekday::Sat, Weekday::Sun])), ..Default::default() },
+        at((2023, 7, 13), 12), Some(Unavailability::Weekdays(WeekdaySet::from_iter([Weekday::Sat, Weekday::Sun])))
+    )]
+    #[case(ItemAvailability { hours: Some((8, 18)), ..Default::default() }, at((2023, 7, 13), 8), None)]
+    #[case(ItemAvailability { hours: Some((8, 18)), ..Default::default() }, at((2023, 7, 13), 18), Some(Unavailability::Hours(8, 18)))]
+    #[case(ItemAvailability { hours: Some((22, 6)), ..Default::default() }, at((2023, 7, 13), 23), None)]
+    #[case(ItemAvailability { hours: Some((22, 6)), ..Default::default() }, at((2023, 7, 13), 5), None)]
+    #[case(ItemAvailability { hours: Some((22, 6)), ..Default::default() }, at((2023, 7, 13), 12), Some(Unavailability::Hours(22, 6)))]
+    #[case(
+        ItemAvailability { dates: Some((NaiveDate::from_ymd_opt(2023, 7, 13).unwrap(), NaiveDate::from_ymd_opt(2023, 7, 13).unwrap())), ..Default::default() },
+        at((2023, 7, 13), 12), None
+    )]
+    #[case(
+        ItemAvailability { dates: Some((NaiveDate::from_ymd_opt(2023, 7, 14).unwrap(), NaiveDate::from_ymd_opt(2023, 8, 1).unwrap())), ..Default::default() },
+        at((2023, 7, 13), 12), Some(Unavailability::Dates(NaiveDate::from_ymd_opt(2023, 7, 14).unwrap(), NaiveDate::from_ymd_opt(2023, 8, 1).unwrap()))
+    )]
+    fn availability_check(
+        #[case] availability: ItemAvailability,
+        #[case] now: NaiveDateTime,
+        #[case] expected: Option<Unavailability>,
+    ) {
+        assert_eq!(availability.check(now), expected);
+    }
+}
@@ -1,22 +1,42 @@
 use chrono::{Duration};
 use rusqlite::{Connection, Result, Row};
 use crate::errors::{ArreError, ArreResult};
+use crate::goal::Goal;
 use crate::item::ItemId;
+use crate::pomodoro::PomodoroConfig;
 
 pub fn item_details_update(conn: &Connection, stats: &ItemDetails) -> ArreResult<()> {
+    let (pomodoro_work, pomodoro_short_break, pomodoro_long_break, pomodoro_long_break_every) = PomodoroConfig::to_columns(&stats.pomodoro);
+    let (goal_target, goal_kind, goal_period, is_goal_boost) = Goal::to_columns(&stats.goal);
     conn.execute("
         UPDATE item_details
-        SET session_duration = ?2
+        SET session_duration = ?2, cooldown = ?3, is_auto_finish = ?4,
+         pomodoro_work = ?5, pomodoro_short_break = ?6, pomodoro_long_break = ?7, pomodoro_long_break_every = ?8,
+         goal_target = ?9, goal_kind = ?10, goal_period = ?11, is_goal_boost = ?12
         WHERE item_id = ?1
-    ", (stats.get_id()?, stats.session_duration.map(|sd| sd.num_seconds())),
-    )?;
+    ", (
+        stats.get_id()?,
+        stats.session_duration.map(|sd| sd.num_seconds()),
+        stats.cooldown.map(|cd| cd.num_seconds()),
+        stats.is_auto_finish,
+        pomodoro_work,
+        pomodoro_short_break,
+        pomodoro_long_break,
+        pomodoro_long_break_every,
+        goal_target,
+        goal_kind,
+        goal_period,
+        is_goal_boost,
+    ))?;
     Ok(())
 }
 
 pub fn item_details_get(conn: &Connection, id: impl Into<ItemId>) -> ArreResult<ItemDetails> {
     let mut stmt = conn.prepare("
         SELECT
-         item_id, session_duration
+         item_id, session_duration, cooldown, is_auto_finish,
+         pomodoro_work, pomodoro_short_break, pomodoro_long_break, pomodoro_long_break_every,
+         goal_target, goal_kind, goal_period, is_goal_boost
         FROM item_details
         WHERE item_id = ?1
     ")?;
@@ -26,10 +46,14 @@ pub fn item_details_get(conn: &Connection, id: impl Into<ItemId>) -> ArreResult<
 }
 
 
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Default)]
 pub struct ItemDetails {
     pub id: Option<ItemId>, // None indicates it's not persisted
     pub session_duration: Option<Duration>, // in seconds
+    pub cooldown: Option<Duration>, // in seconds, None falls back to the list default
+    pub is_auto_finish: bool, // finish the session once session_duration is worked
+    pub pomodoro: Option<PomodoroConfig>, // None falls back to the list default
+    pub goal: Option<Goal>,
 }
 
 impl ItemDetails {
@@ -37,6 +61,10 @@ impl ItemDetails {
         Ok(ItemDetails {
             id: Some(row.get(0)?),
             session_duration: row.get::<_, Option<i64>>(1)?.map(Duration::seconds),
+            cooldown: row.get::<_, Option<i64>>(2)?.map(Duration::seconds),
+            is_auto_finish: row.get(3)?,
+            pomodoro: PomodoroConfig::from_row_at(row, 4)?,
+            goal: Goal::from_row_at(row, 8)?,
         })
     }
 
@@ -45,15 +73,6 @@ impl ItemDetails {
     }
 }
 
-impl Default for ItemDetails {
-    fn default() -> Self {
-        ItemDetails {
-            id: None,
-            session_duration: None,
-        }
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use rstest::*;
@@ -68,6 +87,8 @@ mod tests {
         let item_id = item_create(&conn, "Name", "Description")?.get_id()?;
         let details = item_details_get(&conn, item_id)?;
         assert_eq!( details.session_duration, None, "default session_duration should be None");
+        assert_eq!( details.cooldown, None, "default cooldown should be None");
+        assert!(!details.is_auto_finish, "default is_auto_finish should be false");
 
         // Delete the item and check that details were deleted as well
         item_delete(&conn, item_id)?;
@@ -89,18 +110,25 @@ mod tests {
     }
 
     #[rstest]
-    #[case(None)]
-    #[case(Some(Duration::seconds(10)))]
+    #[case(None, None, false)]
+    #[case(Some(Duration::seconds(10)), None, true)]
+    #[case(None, Some(Duration::days(2)), false)]
     fn update_item_details(
         conn: Connection,
         #[case] session_duration: Option<Duration>,
+        #[case] cooldown: Option<Duration>,
+        #[case] is_auto_finish: bool,
     ) -> ArreResult<()> {
         let item_id = item_create(&conn, "Name", "Description")?.get_id()?;
         let mut details = item_details_get(&conn, item_id)?;
         details.session_duration = session_duration;
+        details.cooldown = cooldown;
+        details.is_auto_finish = is_auto_finish;
         item_details_update(&conn, &details)?;
         let details = item_details_get(&conn, item_id)?;
         assert_eq!(details.session_duration, session_duration);
+        assert_eq!(details.cooldown, cooldown);
+        assert_eq!(details.is_auto_finish, is_auto_finish);
         Ok(())
     }
 
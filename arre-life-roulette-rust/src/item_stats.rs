@@ -1,28 +1,74 @@
-use chrono::{Duration, Utc};
+use std::collections::BTreeSet;
+use chrono::{Duration, Local, NaiveDate, Utc};
 use rusqlite::{Connection, Result, Row};
 use crate::errors::{ArreError, ArreResult};
 use crate::item::ItemId;
+use crate::session::{item_sessions_get, Session};
+use crate::utils::{ArreDateTime, week_start};
 
-pub fn item_stats_update(conn: &Connection, stats: &ItemStats) -> ArreResult<()> {
-    conn.execute("
-        UPDATE item_stats
-        SET updated_date = ?1, times_worked = ?2, time_spent = ?3
-        WHERE item_id = ?4
-    ", (Utc::now().to_string(), stats.times_worked, stats.time_spent.num_seconds(), stats.get_id()?),
+// Item stats are an aggregate of the sessions, kept in the table so they can be read cheaply.
+// Abandoned sessions only count as skips, the item was not worked on.
+const ITEM_STATS_RECOMPUTE: &str = "
+    UPDATE item_stats
+    SET
+     updated_date = ?1,
+     times_worked = (SELECT COUNT(*) FROM sessions s WHERE s.item_id = item_stats.item_id AND s.is_abandoned = 0),
+     time_spent = (SELECT COALESCE(SUM(duration), 0) FROM sessions s WHERE s.item_id = item_stats.item_id AND s.is_abandoned = 0),
+     last_worked_date = (SELECT MAX(end_date) FROM sessions s WHERE s.item_id = item_stats.item_id AND s.is_abandoned = 0),
+     times_skipped = (SELECT COUNT(*) FROM sessions s WHERE s.item_id = item_stats.item_id AND s.is_abandoned = 1)
+";
+
+/// Recompute the stats of the item from its sessions
+pub fn item_stats_recompute(conn: &Connection, id: impl Into<ItemId>) -> ArreResult<()> {
+    conn.execute(
+        &format!("{} WHERE item_id = ?2", ITEM_STATS_RECOMPUTE),
+        (ArreDateTime::now(), id.into()),
     )?;
     Ok(())
 }
 
+/// Stats of the item, those not kept in the table are derived from its sessions, streaks being counted up to today
 pub fn item_stats_get(conn: &Connection, id: impl Into<ItemId>) -> ArreResult<ItemStats> {
+    let id = id.into();
     let mut stmt = conn.prepare("
         SELECT
-         item_id, times_worked, time_spent
+         item_id, times_worked, time_spent, last_worked_date, times_skipped
         FROM item_stats
         WHERE item_id = ?1
     ")?;
-    Ok(stmt.query_row([id.into()], |row| {
+    let mut stats = stmt.query_row([id], |row| {
         ItemStats::from_row(row)
-    })?)
+    })?;
+    stats.add_sessions(&item_sessions_get::<Vec<_>>(conn, id)?, Local::now().date_naive());
+    Ok(stats)
+}
+
+/// Consecutive days or weeks during which something was worked on
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Default)]
+pub struct Streak {
+    pub current: usize, // still running if the last period worked is the current or the previous one
+    pub longest: usize,
+}
+
+impl Streak {
+    /// `periods` are the starts of the periods worked, each `step` long, `current` the start of the ongoing one
+    pub fn from_periods(periods: &BTreeSet<NaiveDate>, current: NaiveDate, step: Duration) -> Streak {
+        let mut longest = 0;
+        let mut run = 0;
+        let mut previous: Option<NaiveDate> = None;
+        for period in periods.iter() {
+            run = if previous.is_some_and(|previous| previous + step == *period) { run + 1 } else { 1 };
+            longest = longest.max(run);
+            previous = Some(*period);
+        }
+        let mut period = if periods.contains(&current) { current } else { current - step };
+        let mut current = 0;
+        while periods.contains(&period) {
+            current += 1;
+            period -= step;
+        }
+        Streak { current, longest }
+    }
 }
 
 
@@ -31,6 +77,14 @@ pub struct ItemStats {
     pub id: Option<ItemId>, // None indicates it's not persisted
     pub times_worked: usize,
     pub time_spent: Duration,
+    pub last_worked: Option<ArreDateTime<Utc>>,
+    pub times_skipped: usize, // times the item was cancelled or skipped once assigned
+    pub first_worked: Option<ArreDateTime<Utc>>,
+    pub average_session: Option<Duration>, // None until the item is worked
+    pub median_session: Option<Duration>,
+    pub longest_session: Option<Duration>,
+    pub daily_streak: Streak,
+    pub weekly_streak: Streak,
 }
 
 impl ItemStats {
@@ -39,9 +93,36 @@ impl ItemStats {
             id: Some(row.get(0)?),
             times_worked: row.get(1)?,
             time_spent: Duration::seconds(row.get(2)?),
+            last_worked: row.get(3)?,
+            times_skipped: row.get(4)?,
+            ..Default::default()
         })
     }
 
+    /// Fill in the stats derived from the sessions of the item, abandoned ones are not work
+    pub fn add_sessions(&mut self, sessions: &[Session], today: NaiveDate) {
+        let worked = sessions.iter().filter(|session| !session.is_abandoned).collect::<Vec<_>>();
+        self.first_worked = worked.iter().min_by_key(|session| *session.start).map(|session| session.start.clone());
+        self.average_session = (self.times_worked > 0).then(|| self.time_spent / self.times_worked as i32);
+
+        let mut durations = worked.iter().map(|session| session.duration).collect::<Vec<_>>();
+        durations.sort();
+        self.median_session = match durations.len() {
+            0 => None,
+            len if len % 2 == 0 => Some((durations[len / 2 - 1] + durations[len / 2]) / 2),
+            len => Some(durations[len / 2]),
+        };
+        self.longest_session = durations.last().copied();
+
+        let days = worked
+            .iter()
+            .map(|session| session.start.with_timezone(&Local).date_naive())
+            .collect::<BTreeSet<_>>();
+        let weeks = days.iter().map(|day| week_start(*day)).collect::<BTreeSet<_>>();
+        self.daily_streak = Streak::from_periods(&days, today, Duration::days(1));
+        self.weekly_streak = Streak::from_periods(&weeks, week_start(today), Duration::weeks(1));
+    }
+
     pub fn get_id(&self) -> ArreResult<ItemId> {
         self.id.ok_or(ArreError::ItemNotPersisted().into())
     }
@@ -54,6 +135,14 @@ impl Default for ItemStats {
             id: None,
             times_worked: 0,
             time_spent: Duration::zero(),
+            last_worked: None,
+            times_skipped: 0,
+            first_worked: None,
+            average_session: None,
+            median_session: None,
+            longest_session: None,
+            daily_streak: Streak::default(),
+            weekly_streak: Streak::default(),
         }
     }
 }
@@ -63,6 +152,7 @@ mod tests {
     use rstest::*;
     use rusqlite::Connection;
     use crate::item::{Item, item_create, item_delete, item_persist};
+    use crate::session::{Session, session_delete, session_persist};
     use crate::test_fixtures::{conn, TestFactory};
     use super::*;
 
@@ -73,6 +163,7 @@ mod tests {
         let stats = item_stats_get(&conn, item_id)?;
         assert_eq!( stats.times_worked, 0, "times_worked of fresh Item should be 0");
         assert_eq!( stats.time_spent.num_seconds(), 0, "time_spent of fresh Item should be 0");
+        assert_eq!( stats.last_worked, None, "last_worked of fresh Item should be None");
 
         // Delete the item and check that stats were deleted as well
         item_delete(&conn, item_id)?;
@@ -95,24 +186,68 @@ mod tests {
     }
 
     #[rstest]
-    fn update_item_stats(
+    fn stats_follow_sessions(
         conn: Connection,
     ) -> ArreResult<()> {
         let item_id = item_create(&conn, "Name", "Description")?.get_id()?;
-        let mut stats = item_stats_get(&conn, item_id)?;
-        stats.times_worked = 5;
-        stats.time_spent = Duration::seconds(10);
-        item_stats_update(&conn, &stats)?;
+        let last_worked = Utc::now();
+        let mut sessions = [
+            Session::new(item_id, None, last_worked - Duration::hours(3), last_worked - Duration::hours(2)),
+            Session::new(item_id, None, last_worked - Duration::seconds(10), last_worked),
+        ];
+        for session in sessions.iter_mut() {
+            session_persist(&conn, session)?;
+        }
         let stats = item_stats_get(&conn, item_id)?;
-        assert_eq!(stats.times_worked, 5);
-        assert_eq!(stats.time_spent.num_seconds(), 10);
+        assert_eq!(stats.times_worked, 2);
+        assert_eq!(stats.time_spent.num_seconds(), 3610);
+        assert_eq!(stats.last_worked, Some(ArreDateTime::new(last_worked)));
+
+        session_delete(&conn, sessions[1].get_id()?)?;
+        let stats = item_stats_get(&conn, item_id)?;
+        assert_eq!(stats.times_worked, 1);
+        assert_eq!(stats.time_spent, Duration::hours(1));
+        assert_eq!(stats.last_worked, Some(ArreDateTime::new(last_worked - Duration::hours(2))));
+        Ok(())
+    }
+
+    #[rstest]
+    fn session_lengths_and_first_worked(conn: Connection) -> ArreResult<()> {
+        let item_id = item_create(&conn, "Name", "Description")?.get_id()?;
+        assert_eq!(item_stats_get(&conn, item_id)?.median_session, None);
+        let first = Utc::now() - Duration::days(10);
+        for (days, minutes) in [(0, 10), (1, 20), (2, 60), (3, 30)] {
+            let start = first + Duration::days(days);
+            session_persist(&conn, &mut Session::new(item_id, None, start, start + Duration::minutes(minutes)))?;
+        }
+        let stats = item_stats_get(&conn, item_id)?;
+        assert_eq!(stats.first_worked, Some(ArreDateTime::new(first)));
+        assert_eq!(stats.average_session, Some(Duration::minutes(30)));
+        assert_eq!(stats.median_session, Some(Duration::minutes(25)));
+        assert_eq!(stats.longest_session, Some(Duration::minutes(60)));
         Ok(())
     }
 
     #[rstest]
-    fn update_on_default_fails(conn: Connection) {
-        let item_stats = Default::default();
-        let result = item_stats_update(&conn, &item_stats);
-        assert!(result.is_err(), "Update on default should fail");
+    #[case::none(&[], 0, 0)]
+    #[case::today(&[0], 1, 1)]
+    #[case::until_yesterday(&[1, 2, 3], 3, 3)]
+    #[case::broken(&[2, 3], 0, 2)]
+    #[case::longest_in_the_past(&[0, 1, 5, 6, 7, 8], 2, 4)]
+    fn daily_streaks(#[case] days_ago: &[i64], #[case] current: usize, #[case] longest: usize) {
+        let today = NaiveDate::from_ymd_opt(2023, 6, 14).unwrap();
+        let days = days_ago.iter().map(|days| today - Duration::days(*days)).collect::<BTreeSet<_>>();
+        assert_eq!(Streak::from_periods(&days, today, Duration::days(1)), Streak { current, longest });
+    }
+
+    #[rstest]
+    fn weekly_streaks_count_weeks_from_monday() {
+        // Wednesday
+        let today = NaiveDate::from_ymd_opt(2023, 6, 14).unwrap();
+        assert_eq!(week_start(today), NaiveDate::from_ymd_opt(2023, 6, 12).unwrap());
+        // Sunday of the previous week and Monday of the one before
+        let days = [NaiveDate::from_ymd_opt(2023, 6, 11).unwrap(), NaiveDate::from_ymd_opt(2023, 5, 29).unwrap()];
+        let weeks = days.iter().map(|day| week_start(*day)).collect::<BTreeSet<_>>();
+        assert_eq!(Streak::from_periods(&weeks, week_start(today), Duration::weeks(1)), Streak { current: 2, longest: 2 });
     }
 }
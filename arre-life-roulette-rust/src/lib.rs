@@ -10,13 +10,28 @@ mod godot_classes;
 mod errors;
 mod item_stats;
 mod item_details;
+mod item_availability;
+mod list_details;
+mod reroll;
+mod roll;
+mod deck;
+mod simulation;
+mod session;
+mod pomodoro;
+mod settings;
+mod idle;
+mod time_series;
+mod goal;
+mod neglect;
+mod fairness;
 
 use godot::engine::class_macros::auto_register_classes;
-use godot::engine::Engine;
+use godot::engine::{Engine, ProjectSettings};
 use godot::prelude::*;
 use crate::db::set_db_connection;
+use crate::godot_classes::resources::DATABASE_FILE;
 use crate::godot_classes::singletons::buses::Buses;
-use crate::godot_classes::singletons::logger::Logger;
+use crate::godot_classes::singletons::logger::{log_error, Logger};
 use crate::godot_classes::singletons::signals::Signals;
 
 struct LifeRoulette;
@@ -38,7 +53,11 @@ impl ExtensionLayer for DefaultLayer {
         Engine::singleton().register_singleton("Signals".into(), Gd::<Signals>::new_default().upcast());
         Engine::singleton().register_singleton("Logger".into(), Gd::<Logger>::new_default().upcast());
 
-        set_db_connection();
+        let database_path = ProjectSettings::singleton().globalize_path(DATABASE_FILE.into());
+        // Only development builds start with demo content, release builds start empty
+        if let Err(e) = set_db_connection(database_path.to_string(), cfg!(debug_assertions)) {
+            log_error(e);
+        }
     }
 
     fn deinitialize(&mut self) {}
@@ -1,9 +1,10 @@
 use std::borrow::Borrow;
 use std::collections::HashSet;
-use chrono::Utc;
+use chrono::{Duration, Utc};
 use rusqlite::{Connection, Result, Row};
 use crate::errors::{ArreError, ArreResult};
 use crate::item::{Item, ItemId};
+use crate::roll::roll_count;
 use crate::utils::{ArreDateTime, Id};
 
 pub fn list_create(conn: &Connection, name: impl AsRef<str>, description: impl AsRef<str>) -> ArreResult<List> {
@@ -198,6 +199,31 @@ pub fn list_items_delete(conn: &Connection, list_id: ListId, items: impl Iterato
     Ok(())
 }
 
+/// Stats of the list members aggregated, along with the rolls made on the list alone
+pub fn list_stats_get(conn: &Connection, list_id: ListId) -> ArreResult<ListStats> {
+    let mut stmt = conn.prepare("
+        SELECT
+         i.item_id, i.created_date, i.updated_date, i.name, i.description, i.is_suspended, i.is_finished,
+         s.times_worked, s.time_spent
+        FROM items i
+        JOIN item_list_map ilm ON i.item_id = ilm.item_id
+        JOIN item_stats s ON i.item_id = s.item_id
+        WHERE ilm.list_id = ?1
+        ORDER BY s.time_spent DESC, i.name
+    ")?;
+    let members = stmt.query_map([list_id], |row| {
+        ListMemberStats::from_row(row)
+    })?.collect::<Result<Vec<_>>>()?;
+    Ok(ListStats {
+        id: Some(list_id),
+        times_worked: members.iter().map(|member| member.times_worked).sum(),
+        time_spent: members.iter().fold(Duration::zero(), |total, member| total + member.time_spent),
+        items_never_worked: members.iter().filter(|member| member.times_worked == 0).count(),
+        rolls_count: roll_count(conn, list_id)?,
+        members,
+    })
+}
+
 pub type ListId = Id<List>;
 /// A list is a collection of items.
 #[derive(Debug, Clone)]
@@ -245,11 +271,68 @@ impl Default for List {
     }
 }
 
+#[derive(Debug, Clone)]
+pub struct ListStats {
+    pub id: Option<ListId>, // None indicates it's not persisted
+    pub times_worked: usize,
+    pub time_spent: Duration,
+    pub items_never_worked: usize,
+    pub rolls_count: usize,
+    pub members: Vec<ListMemberStats>, // most worked first
+}
+
+impl ListStats {
+    /// Part of the time spent on the list that went to the member, from 0 to 1
+    pub fn time_share(&self, member: &ListMemberStats) -> f64 {
+        if self.time_spent.is_zero() {
+            return 0.0;
+        }
+        member.time_spent.num_seconds() as f64 / self.time_spent.num_seconds() as f64
+    }
+
+    pub fn get_id(&self) -> ArreResult<ListId> {
+        self.id.ok_or(ArreError::ItemNotPersisted().into())
+    }
+}
+
+impl Default for ListStats {
+    fn default() -> Self {
+        Self {
+            id: None,
+            times_worked: 0,
+            time_spent: Duration::zero(),
+            items_never_worked: 0,
+            rolls_count: 0,
+            members: vec![],
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ListMemberStats {
+    pub item: Item,
+    pub times_worked: usize,
+    pub time_spent: Duration,
+}
+
+impl ListMemberStats {
+    /// Expects item columns followed by the times worked and the time spent in seconds
+    pub fn from_row(row: &Row) -> Result<ListMemberStats> {
+        Ok(ListMemberStats {
+            item: Item::from_row(row)?,
+            times_worked: row.get(7)?,
+            time_spent: Duration::seconds(row.get(8)?),
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use rstest::*;
     use rusqlite::Connection;
     use crate::item::{items_to_ids, item_create};
+    use crate::roll::roll_record;
+    use crate::session::{Session, session_persist};
     use crate::test_fixtures::{conn, TestFactory};
     use super::*;
 
@@ -392,4 +475,31 @@ mod tests {
         Ok(())
     }
 
+    #[rstest]
+    fn list_stats_aggregate_members(conn: Connection) -> ArreResult<()> {
+        let mut tf = TestFactory::new(&conn);
+        let list_id = tf.create_lists(1)?[0].get_id()?;
+        let items = tf.create_items(4)?;
+        let item_ids = items_to_ids::<_, Vec<_>>(items.iter())?;
+        list_items_add(&conn, list_id, item_ids[0..3].iter())?;
+        let start = Utc::now() - Duration::hours(5);
+        for (item_id, minutes) in [(item_ids[0], 30), (item_ids[1], 60), (item_ids[1], 30), (item_ids[3], 120)] {
+            session_persist(&conn, &mut Session::new(item_id, Some(list_id), start, start + Duration::minutes(minutes)))?;
+        }
+        let candidates = item_ids.iter().map(|item_id| (*item_id, 1)).collect::<Vec<_>>();
+        roll_record(&conn, Some(list_id), &candidates[0..3], &item_ids[0..2])?;
+        // Rolls made on several lists or all items do not count for the list
+        roll_record(&conn, None, &candidates, &item_ids[0..1])?;
+
+        let stats = list_stats_get(&conn, list_id)?;
+        assert_eq!(stats.times_worked, 3);
+        assert_eq!(stats.time_spent, Duration::minutes(120), "Items out of the list are not counted");
+        assert_eq!(stats.items_never_worked, 1);
+        assert_eq!(stats.rolls_count, 2);
+        let member_ids = stats.members.iter().map(|member| member.item.get_id()).collect::<ArreResult<Vec<_>>>()?;
+        assert_eq!(member_ids, vec![item_ids[1], item_ids[0], item_ids[2]]);
+        assert_eq!(stats.time_share(&stats.members[0]), 0.75);
+        assert_eq!(stats.time_share(&stats.members[2]), 0.0);
+        Ok(())
+    }
 }
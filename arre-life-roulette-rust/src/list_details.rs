@@ -0,0 +1,222 @@
+use chrono::{DateTime, Duration, Local, Utc};
+use rusqlite::{Connection, Result, Row};
+use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSql, ToSqlOutput, ValueRef};
+use crate::errors::{ArreError, ArreResult};
+use crate::goal::Goal;
+use crate::list::ListId;
+use crate::pomodoro::PomodoroConfig;
+use crate::utils::{local_day_start, local_week_start};
+
+pub fn list_details_update(conn: &Connection, details: &ListDetails) -> ArreResult<()> {
+    let (pomodoro_work, pomodoro_short_break, pomodoro_long_break, pomodoro_long_break_every) = PomodoroConfig::to_columns(&details.pomodoro);
+    let (goal_target, goal_kind, goal_period, is_goal_boost) = Goal::to_columns(&details.goal);
+    conn.execute("
+        UPDATE list_details
+        SET updated_date = ?2, reroll_budget = ?3, reroll_budget_period = ?4, cooldown = ?5, roll_weight = ?6, is_deck_mode = ?7,
+         pomodoro_work = ?8, pomodoro_short_break = ?9, pomodoro_long_break = ?10, pomodoro_long_break_every = ?11,
+         goal_target = ?12, goal_kind = ?13, goal_period = ?14, is_goal_boost = ?15
+        WHERE list_id = ?1
+    ", (
+        details.get_id()?,
+        Utc::now().to_string(),
+        details.reroll_budget.as_ref().map(|budget| budget.tokens),
+        details.reroll_budget.as_ref().map(|budget| budget.period).unwrap_or_default(),
+        details.cooldown.map(|cd| cd.num_seconds()),
+        details.roll_weight,
+        details.is_deck_mode,
+        pomodoro_work,
+        pomodoro_short_break,
+        pomodoro_long_break,
+        pomodoro_long_break_every,
+        goal_target,
+        goal_kind,
+        goal_period,
+        is_goal_boost,
+    ))?;
+    Ok(())
+}
+
+pub fn list_details_get(conn: &Connection, id: ListId) -> ArreResult<ListDetails> {
+    let mut stmt = conn.prepare("
+        SELECT
+         list_id, reroll_budget, reroll_budget_period, cooldown, roll_weight, is_deck_mode,
+         pomodoro_work, pomodoro_short_break, pomodoro_long_break, pomodoro_long_break_every,
+         goal_target, goal_kind, goal_period, is_goal_boost
+        FROM list_details
+        WHERE list_id = ?1
+    ")?;
+    Ok(stmt.query_row([id], |row| {
+        ListDetails::from_row(row)
+    })?)
+}
+
+/// Time window after which the reroll tokens are replenished
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Default)]
+pub enum BudgetPeriod {
+    #[default]
+    Day,
+    Week,
+}
+
+impl BudgetPeriod {
+    /// Start of the period containing `now`, using local day boundaries. Weeks start on Monday.
+    pub fn period_start(&self, now: DateTime<Local>) -> DateTime<Utc> {
+        match self {
+            BudgetPeriod::Day => local_day_start(now.date_naive()),
+            BudgetPeriod::Week => local_week_start(now.date_naive()),
+        }
+    }
+}
+
+impl ToSql for BudgetPeriod {
+    fn to_sql(&self) -> Result<ToSqlOutput<'_>> {
+        Ok(match self {
+            BudgetPeriod::Day => "day",
+            BudgetPeriod::Week => "week",
+        }.into())
+    }
+}
+
+impl FromSql for BudgetPeriod {
+    fn column_result(value: ValueRef) -> FromSqlResult<Self> {
+        match value.as_str()? {
+            "day" => Ok(BudgetPeriod::Day),
+            "week" => Ok(BudgetPeriod::Week),
+            _ => Err(FromSqlError::InvalidType),
+        }
+    }
+}
+
+/// Number of rerolls allowed within a single period
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct RerollBudget {
+    pub tokens: usize,
+    pub period: BudgetPeriod,
+}
+
+#[derive(Debug, Clone)]
+pub struct ListDetails {
+    pub id: Option<ListId>, // None indicates it's not persisted
+    pub reroll_budget: Option<RerollBudget>, // None means unlimited rerolls
+    pub cooldown: Option<Duration>, // default cooldown of the list items, in seconds
+    pub roll_weight: u32, // relative likelihood of the list items when rolling across several lists
+    pub is_deck_mode: bool, // every item is drawn once before any of them repeats
+    pub pomodoro: Option<PomodoroConfig>, // default pomodoro cycle of the list items
+    pub goal: Option<Goal>, // reached by working on any of the list items
+}
+
+impl ListDetails {
+    pub fn from_row(row: &Row) -> Result<ListDetails> {
+        let period = row.get(2)?;
+        Ok(ListDetails {
+            id: Some(row.get(0)?),
+            reroll_budget: row.get::<_, Option<usize>>(1)?.map(|tokens| RerollBudget { tokens, period }),
+            cooldown: row.get::<_, Option<i64>>(3)?.map(Duration::seconds),
+            roll_weight: row.get(4)?,
+            is_deck_mode: row.get(5)?,
+            pomodoro: PomodoroConfig::from_row_at(row, 6)?,
+            goal: Goal::from_row_at(row, 10)?,
+        })
+    }
+
+    pub fn get_id(&self) -> ArreResult<ListId> {
+        self.id.ok_or(ArreError::ItemNotPersisted().into())
+    }
+}
+
+impl Default for ListDetails {
+    fn default() -> Self {
+        ListDetails {
+            id: None,
+            reroll_budget: None,
+            cooldown: None,
+            roll_weight: 1,
+            is_deck_mode: false,
+            pomodoro: None,
+            goal: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDate, NaiveTime, TimeZone};
+    use rstest::*;
+    use rusqlite::Connection;
+    use crate::list::{List, list_create, list_delete, list_persist};
+    use crate::test_fixtures::{conn, TestFactory};
+    use super::*;
+
+    #[rstest]
+    fn list_creates_removes_details(conn: Connection) -> ArreResult<()> {
+        let tf = TestFactory::new(&conn);
+        let list_id = list_create(&conn, "Name", "Description")?.get_id()?;
+        let details = list_details_get(&conn, list_id)?;
+        assert_eq!(details.reroll_budget, None, "default reroll_budget should be None");
+        assert_eq!(details.cooldown, None, "default cooldown should be None");
+        assert_eq!(details.roll_weight, 1, "default roll_weight should be 1");
+        assert!(!details.is_deck_mode, "default is_deck_mode should be false");
+
+        // Delete the list and check that details were deleted as well
+        list_delete(&conn, list_id)?;
+        tf.assert_table_count("list_details", 0)?;
+        assert!(list_details_get(&conn, list_id).is_err(), "List details should have been deleted");
+        Ok(())
+    }
+
+    #[rstest]
+    fn list_persist_creates_details(conn: Connection) -> ArreResult<()> {
+        let mut list = List::default();
+        list_persist(&conn, &mut list)?;
+
+        let details = list_details_get(&conn, list.get_id()?)?;
+        assert_eq!(details.reroll_budget, None, "default reroll_budget should be None");
+        Ok(())
+    }
+
+    #[rstest]
+    #[case(None, None, 1, false)]
+    #[case(Some(RerollBudget { tokens: 0, period: BudgetPeriod::Day }), None, 2, true)]
+    #[case(Some(RerollBudget { tokens: 3, period: BudgetPeriod::Week }), Some(Duration::hours(12)), 5, false)]
+    fn update_list_details(
+        conn: Connection,
+        #[case] reroll_budget: Option<RerollBudget>,
+        #[case] cooldown: Option<Duration>,
+        #[case] roll_weight: u32,
+        #[case] is_deck_mode: bool,
+    ) -> ArreResult<()> {
+        let list_id = list_create(&conn, "Name", "Description")?.get_id()?;
+        let mut details = list_details_get(&conn, list_id)?;
+        details.reroll_budget = reroll_budget.clone();
+        details.cooldown = cooldown;
+        details.roll_weight = roll_weight;
+        details.is_deck_mode = is_deck_mode;
+        list_details_update(&conn, &details)?;
+        let details = list_details_get(&conn, list_id)?;
+        assert_eq!(details.reroll_budget, reroll_budget);
+        assert_eq!(details.cooldown, cooldown);
+        assert_eq!(details.roll_weight, roll_weight);
+        assert_eq!(details.is_deck_mode, is_deck_mode);
+        Ok(())
+    }
+
+    #[rstest]
+    fn update_on_default_fails(conn: Connection) {
+        let list_details = Default::default();
+        let result = list_details_update(&conn, &list_details);
+        assert!(result.is_err(), "Update on default should fail");
+    }
+
+    #[rstest]
+    #[case(BudgetPeriod::Day, NaiveDate::from_ymd_opt(2023, 7, 13).unwrap())] // Thursday
+    #[case(BudgetPeriod::Week, NaiveDate::from_ymd_opt(2023, 7, 10).unwrap())] // Monday
+    fn period_start_is_local_midnight(
+        #[case] period: BudgetPeriod,
+        #[case] expected_date: NaiveDate,
+    ) {
+        let now = Local.with_ymd_and_hms(2023, 7, 13, 15, 30, 0).unwrap();
+        let start = period.period_start(now).with_timezone(&Local);
+        assert_eq!(start.date_naive(), expected_date);
+        assert_eq!(start.time(), NaiveTime::MIN);
+    }
+}
@@ -0,0 +1,109 @@
+use chrono::{DateTime, Duration, Utc};
+use rusqlite::{Connection, Result, Row};
+use crate::errors::ArreResult;
+use crate::item::{Item, ItemFilter};
+use crate::utils::ArreDateTime;
+
+/// Items that can still be worked on, the longest neglected first.
+/// Items never worked count as neglected since they were created and come first on ties.
+pub fn neglect_report_get(conn: &Connection, filter: &ItemFilter, now: DateTime<Utc>) -> ArreResult<Vec<NeglectedItem>> {
+    let mut stmt = conn.prepare("
+        SELECT
+         i.item_id, i.created_date, i.updated_date, i.name, i.description, i.is_suspended, i.is_finished,
+         s.last_worked_date
+        FROM items i
+        JOIN item_stats s ON i.item_id = s.item_id
+        WHERE i.is_suspended = 0 AND i.is_finished = 0
+         AND (?1 IS NULL OR i.item_id IN (SELECT item_id FROM item_list_map WHERE list_id = ?1))
+         AND (?2 IS NULL OR i.item_id IN (SELECT item_id FROM item_tag_map WHERE tag_id = ?2))
+    ")?;
+    let mut report = stmt.query_map((filter.list_id, filter.tag_id), |row| {
+        NeglectedItem::from_row(row, now)
+    })?.collect::<Result<Vec<_>>>()?;
+    report.sort_by(|a, b| {
+        b.neglected_for.cmp(&a.neglected_for)
+            .then_with(|| a.last_worked.is_some().cmp(&b.last_worked.is_some()))
+    });
+    Ok(report)
+}
+
+#[derive(Debug, Clone)]
+pub struct NeglectedItem {
+    pub item: Item,
+    pub last_worked: Option<ArreDateTime<Utc>>, // None if the item was never worked
+    pub neglected_for: Duration, // since the item was last worked, or created if never worked
+}
+
+impl NeglectedItem {
+    /// Expects item columns followed by the last worked date
+    pub fn from_row(row: &Row, now: DateTime<Utc>) -> Result<NeglectedItem> {
+        let item = Item::from_row(row)?;
+        let last_worked = row.get::<_, Option<ArreDateTime<Utc>>>(7)?;
+        let since = last_worked.as_ref().map_or(*item.created_date, |last_worked| **last_worked);
+        Ok(NeglectedItem {
+            item,
+            last_worked,
+            neglected_for: now - since,
+        })
+    }
+
+    pub fn days_neglected(&self) -> i64 {
+        self.neglected_for.num_days()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+    use rusqlite::Connection;
+    use crate::item::item_update;
+    use crate::list::list_items_add;
+    use crate::session::{Session, session_persist};
+    use crate::test_fixtures::{conn, TestFactory};
+    use super::*;
+
+    #[rstest]
+    fn items_are_ranked_by_time_since_last_worked(conn: Connection) -> ArreResult<()> {
+        let mut tf = TestFactory::new(&conn);
+        let items = tf.create_items(5)?;
+        let start = Utc::now();
+        let now = start + Duration::days(100);
+        session_persist(&conn, &mut Session::new(items[0].get_id()?, None, start, start + Duration::days(1)))?;
+        session_persist(&conn, &mut Session::new(items[1].get_id()?, None, start, start + Duration::days(2)))?;
+        let mut suspended = items[3].clone();
+        suspended.is_suspended = true;
+        item_update(&conn, &suspended)?;
+        let mut finished = items[4].clone();
+        finished.is_finished = true;
+        item_update(&conn, &finished)?;
+
+        let report = neglect_report_get(&conn, &ItemFilter::default(), now)?;
+        let ranked = report.iter().map(|neglected| neglected.item.get_id()).collect::<ArreResult<Vec<_>>>()?;
+        assert_eq!(ranked, vec![items[2].get_id()?, items[0].get_id()?, items[1].get_id()?]);
+        assert!(report[0].last_worked.is_none());
+        assert_eq!(report[1].days_neglected(), 99);
+        Ok(())
+    }
+
+    #[rstest]
+    fn report_is_filtered_by_list_and_tag(conn: Connection) -> ArreResult<()> {
+        let mut tf = TestFactory::new(&conn);
+        let items = tf.create_items(3)?;
+        let list_id = tf.create_lists(1)?[0].get_id()?;
+        let tag_id = tf.create_tags(1)?[0].get_id()?;
+        list_items_add(&conn, list_id, [items[0].get_id()?, items[1].get_id()?].iter())?;
+        conn.execute(
+            "INSERT INTO item_tag_map (tag_id, item_id) VALUES (?1, ?2), (?1, ?3)",
+            (tag_id, items[1].get_id()?, items[2].get_id()?),
+        )?;
+
+        let in_list = neglect_report_get(&conn, &ItemFilter { list_id: Some(list_id), tag_id: None }, Utc::now())?;
+        assert_eq!(in_list.len(), 2);
+        let tagged = neglect_report_get(&conn, &ItemFilter { list_id: None, tag_id: Some(tag_id) }, Utc::now())?;
+        assert_eq!(tagged.len(), 2);
+        let both = neglect_report_get(&conn, &ItemFilter { list_id: Some(list_id), tag_id: Some(tag_id) }, Utc::now())?;
+        assert_eq!(both.len(), 1);
+        assert_eq!(both[0].item.get_id()?, items[1].get_id()?);
+        Ok(())
+    }
+}
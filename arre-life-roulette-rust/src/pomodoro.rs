@@ -0,0 +1,208 @@
+use chrono::{DateTime, Duration, Utc};
+use rusqlite::{Connection, Result, Row};
+use crate::errors::ArreResult;
+use crate::item::ItemId;
+use crate::item_details::item_details_get;
+use crate::list::ListId;
+use crate::list_details::list_details_get;
+
+/// Pomodoro cycle used for the item, its own one or else the one of the list it was rolled from
+pub fn pomodoro_config_get(conn: &Connection, item_id: ItemId, list_id: Option<ListId>) -> ArreResult<Option<PomodoroConfig>> {
+    if let Some(config) = item_details_get(conn, item_id)?.pomodoro {
+        return Ok(Some(config));
+    }
+    match list_id {
+        Some(list_id) => Ok(list_details_get(conn, list_id)?.pomodoro),
+        None => Ok(None),
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct PomodoroConfig {
+    pub work: Duration,
+    pub short_break: Duration,
+    pub long_break: Duration,
+    pub long_break_every: u32, // a long break replaces every Nth short one
+}
+
+impl PomodoroConfig {
+    /// Expects the work, short break, long break durations in seconds and the long break frequency
+    pub fn from_row_at(row: &Row, first: usize) -> Result<Option<PomodoroConfig>> {
+        let Some(work) = row.get::<_, Option<i64>>(first)? else { return Ok(None) };
+        Ok(Some(PomodoroConfig {
+            work: Duration::seconds(work),
+            short_break: Duration::seconds(row.get(first + 1)?),
+            long_break: Duration::seconds(row.get(first + 2)?),
+            long_break_every: row.get(first + 3)?,
+        }))
+    }
+
+    /// Column values in the order expected by `from_row_at`, all NULL when there is no cycle
+    pub fn to_columns(config: &Option<PomodoroConfig>) -> (Option<i64>, Option<i64>, Option<i64>, Option<u32>) {
+        (
+            config.map(|c| c.work.num_seconds()),
+            config.map(|c| c.short_break.num_seconds()),
+            config.map(|c| c.long_break.num_seconds()),
+            config.map(|c| c.long_break_every),
+        )
+    }
+
+    /// Break following the `completed`th pomodoro
+    pub fn break_after(&self, completed: u32) -> PomodoroBreak {
+        if self.long_break_every > 0 && completed.is_multiple_of(self.long_break_every) {
+            PomodoroBreak::Long
+        } else {
+            PomodoroBreak::Short
+        }
+    }
+
+    pub fn break_duration(&self, pomodoro_break: PomodoroBreak) -> Duration {
+        match pomodoro_break {
+            PomodoroBreak::Short => self.short_break,
+            PomodoroBreak::Long => self.long_break,
+        }
+    }
+}
+
+impl Default for PomodoroConfig {
+    fn default() -> Self {
+        PomodoroConfig {
+            work: Duration::minutes(25),
+            short_break: Duration::minutes(5),
+            long_break: Duration::minutes(15),
+            long_break_every: 4,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum PomodoroBreak {
+    Short,
+    Long,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum PomodoroPhase {
+    Work{worked_at_start: Duration}, // time worked on the session when the interval started
+    Break{kind: PomodoroBreak, end: DateTime<Utc>},
+}
+
+/// Change of phase the work session has to follow, breaks are taken as session pauses
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum PomodoroEvent {
+    BreakStarted(PomodoroBreak),
+    BreakEnded,
+}
+
+/// Progress through the cycle of a single work session
+#[derive(Debug, Clone)]
+pub struct Pomodoro {
+    pub config: PomodoroConfig,
+    pub completed: u32,
+    pub phase: PomodoroPhase,
+}
+
+impl Pomodoro {
+    /// Start with a work interval, `worked` being the time already worked on the session
+    pub fn new(config: PomodoroConfig, completed: u32, worked: Duration) -> Self {
+        Pomodoro { config, completed, phase: PomodoroPhase::Work{worked_at_start: worked} }
+    }
+
+    /// Move to the next phase once the current one is over.
+    /// `worked` excludes pauses, so pausing during a work interval extends it.
+    pub fn advance(&mut self, worked: Duration, now: DateTime<Utc>) -> Option<PomodoroEvent> {
+        match self.phase {
+            PomodoroPhase::Work{worked_at_start} if worked - worked_at_start >= self.config.work => {
+                self.completed += 1;
+                let kind = self.config.break_after(self.completed);
+                self.phase = PomodoroPhase::Break{kind, end: now + self.config.break_duration(kind)};
+                Some(PomodoroEvent::BreakStarted(kind))
+            }
+            PomodoroPhase::Break{end, ..} if end <= now => {
+                self.skip_break(worked);
+                Some(PomodoroEvent::BreakEnded)
+            }
+            _ => None,
+        }
+    }
+
+    /// Go back to work before the break is over
+    pub fn skip_break(&mut self, worked: Duration) {
+        self.phase = PomodoroPhase::Work{worked_at_start: worked};
+    }
+
+    pub fn is_break(&self) -> bool {
+        matches!(self.phase, PomodoroPhase::Break{..})
+    }
+
+    /// Time left in the current phase
+    pub fn time_left(&self, worked: Duration, now: DateTime<Utc>) -> Duration {
+        let left = match self.phase {
+            PomodoroPhase::Work{worked_at_start} => self.config.work - (worked - worked_at_start),
+            PomodoroPhase::Break{end, ..} => end - now,
+        };
+        left.max(Duration::zero())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+    use rusqlite::Connection;
+    use crate::item_details::item_details_update;
+    use crate::list_details::list_details_update;
+    use crate::test_fixtures::{conn, TestFactory};
+    use super::*;
+
+    #[rstest]
+    fn item_config_overrides_list_one(conn: Connection) -> ArreResult<()> {
+        let mut tf = TestFactory::new(&conn);
+        let item_id = tf.create_items(1)?[0].get_id()?;
+        let list_id = tf.create_lists(1)?[0].get_id()?;
+        assert_eq!(pomodoro_config_get(&conn, item_id, Some(list_id))?, None);
+
+        let list_config = PomodoroConfig::default();
+        let mut list_details = list_details_get(&conn, list_id)?;
+        list_details.pomodoro = Some(list_config);
+        list_details_update(&conn, &list_details)?;
+        assert_eq!(pomodoro_config_get(&conn, item_id, Some(list_id))?, Some(list_config));
+        assert_eq!(pomodoro_config_get(&conn, item_id, None)?, None, "List config only applies when rolled from the list");
+
+        let item_config = PomodoroConfig { work: Duration::minutes(50), long_break_every: 2, ..list_config };
+        let mut item_details = item_details_get(&conn, item_id)?;
+        item_details.pomodoro = Some(item_config);
+        item_details_update(&conn, &item_details)?;
+        assert_eq!(pomodoro_config_get(&conn, item_id, Some(list_id))?, Some(item_config));
+        assert_eq!(pomodoro_config_get(&conn, item_id, None)?, Some(item_config));
+        Ok(())
+    }
+
+    #[rstest]
+    fn cycle_alternates_work_and_breaks() {
+        let config = PomodoroConfig { long_break_every: 2, ..PomodoroConfig::default() };
+        let start = Utc::now();
+        let mut pomodoro = Pomodoro::new(config, 0, Duration::zero());
+        assert_eq!(pomodoro.advance(Duration::minutes(24), start + Duration::minutes(24)), None);
+
+        // First pomodoro done, short break
+        let now = start + Duration::minutes(25);
+        assert_eq!(pomodoro.advance(Duration::minutes(25), now), Some(PomodoroEvent::BreakStarted(PomodoroBreak::Short)));
+        assert_eq!(pomodoro.completed, 1);
+        assert_eq!(pomodoro.time_left(Duration::minutes(25), now + Duration::minutes(2)), Duration::minutes(3));
+        // Time worked does not move during the break
+        assert_eq!(pomodoro.advance(Duration::minutes(25), now + Duration::minutes(4)), None);
+        let now = now + Duration::minutes(5);
+        assert_eq!(pomodoro.advance(Duration::minutes(25), now), Some(PomodoroEvent::BreakEnded));
+
+        // A pause in the middle of the work interval extends it
+        assert_eq!(pomodoro.advance(Duration::minutes(49), now + Duration::minutes(40)), None);
+        let now = now + Duration::minutes(41);
+        assert_eq!(pomodoro.advance(Duration::minutes(50), now), Some(PomodoroEvent::BreakStarted(PomodoroBreak::Long)));
+        assert_eq!(pomodoro.completed, 2);
+        assert_eq!(pomodoro.time_left(Duration::minutes(50), now), Duration::minutes(15));
+
+        pomodoro.skip_break(Duration::minutes(50));
+        assert!(!pomodoro.is_break());
+        assert_eq!(pomodoro.time_left(Duration::minutes(60), now), Duration::minutes(15));
+    }
+}
@@ -0,0 +1,96 @@
+use chrono::{DateTime, Local, Utc};
+use rusqlite::Connection;
+use crate::errors::ArreResult;
+use crate::list::ListId;
+use crate::list_details::list_details_get;
+use crate::utils::ArreDateTime;
+
+/// Register a reroll, i.e. an assigned item being dismissed in order to roll again.
+/// Rerolls made with the override are recorded, but do not consume the budget.
+pub fn reroll_record(conn: &Connection, list_id: ListId, is_override: bool) -> ArreResult<()> {
+    conn.execute("
+        INSERT INTO rerolls (list_id, created_date, is_override) VALUES (?1, ?2, ?3)
+        ", (list_id, ArreDateTime::now(), is_override),
+    )?;
+    Ok(())
+}
+
+/// Number of rerolls consuming the budget made on the list since the given moment
+pub fn reroll_count_since(conn: &Connection, list_id: ListId, since: DateTime<Utc>) -> ArreResult<usize> {
+    let mut stmt = conn.prepare("
+        SELECT COUNT(*)
+        FROM rerolls
+        WHERE list_id = ?1 AND is_override = 0 AND created_date >= ?2
+    ")?;
+    Ok(stmt.query_row((list_id, ArreDateTime::new(since)), |row| row.get(0))?)
+}
+
+/// Rerolls still available in the current period. None indicates the list has no budget set.
+pub fn reroll_tokens_left(conn: &Connection, list_id: ListId, now: DateTime<Local>) -> ArreResult<Option<usize>> {
+    let details = list_details_get(conn, list_id)?;
+    let Some(budget) = details.reroll_budget else { return Ok(None) };
+    let used = reroll_count_since(conn, list_id, budget.period.period_start(now))?;
+    Ok(Some(budget.tokens.saturating_sub(used)))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+    use rstest::*;
+    use rusqlite::Connection;
+    use crate::list_details::{BudgetPeriod, list_details_update, RerollBudget};
+    use crate::test_fixtures::{conn, TestFactory};
+    use super::*;
+
+    fn set_budget(conn: &Connection, list_id: ListId, tokens: usize, period: BudgetPeriod) -> ArreResult<()> {
+        let mut details = list_details_get(conn, list_id)?;
+        details.reroll_budget = Some(RerollBudget { tokens, period });
+        list_details_update(conn, &details)
+    }
+
+    #[rstest]
+    fn no_budget_means_unlimited(conn: Connection) -> ArreResult<()> {
+        let mut tf = TestFactory::new(&conn);
+        let list_id = tf.create_lists(1)?[0].get_id()?;
+        reroll_record(&conn, list_id, false)?;
+        assert_eq!(reroll_tokens_left(&conn, list_id, Local::now())?, None);
+        Ok(())
+    }
+
+    #[rstest]
+    #[case(BudgetPeriod::Day)]
+    #[case(BudgetPeriod::Week)]
+    fn rerolls_consume_tokens(
+        conn: Connection,
+        #[case] period: BudgetPeriod,
+    ) -> ArreResult<()> {
+        let mut tf = TestFactory::new(&conn);
+        let lists = tf.create_lists(2)?;
+        let (list_id, other_list_id) = (lists[0].get_id()?, lists[1].get_id()?);
+        set_budget(&conn, list_id, 2, period)?;
+        assert_eq!(reroll_tokens_left(&conn, list_id, Local::now())?, Some(2));
+
+        reroll_record(&conn, list_id, false)?;
+        assert_eq!(reroll_tokens_left(&conn, list_id, Local::now())?, Some(1));
+        // Rerolls on other lists and overrides are not counted
+        reroll_record(&conn, other_list_id, false)?;
+        reroll_record(&conn, list_id, true)?;
+        assert_eq!(reroll_tokens_left(&conn, list_id, Local::now())?, Some(1));
+
+        reroll_record(&conn, list_id, false)?;
+        reroll_record(&conn, list_id, false)?;
+        assert_eq!(reroll_tokens_left(&conn, list_id, Local::now())?, Some(0));
+        Ok(())
+    }
+
+    #[rstest]
+    fn tokens_replenish_in_next_period(conn: Connection) -> ArreResult<()> {
+        let mut tf = TestFactory::new(&conn);
+        let list_id = tf.create_lists(1)?[0].get_id()?;
+        set_budget(&conn, list_id, 1, BudgetPeriod::Day)?;
+        reroll_record(&conn, list_id, false)?;
+        assert_eq!(reroll_tokens_left(&conn, list_id, Local::now())?, Some(0));
+        assert_eq!(reroll_tokens_left(&conn, list_id, Local::now() + Duration::days(1))?, Some(1));
+        Ok(())
+    }
+}
@@ -0,0 +1,616 @@
+use std::collections::HashMap;
+use chrono::{DateTime, Duration, Local, Utc};
+use rand::Rng;
+use rand::distributions::{Distribution, WeightedIndex};
+use rusqlite::{Connection, Result, Row};
+use crate::errors::{ArreError, ArreResult};
+use crate::goal::{GoalProgress, item_boost_goals_progress_get, list_goal_progress_get};
+use crate::item::{Item, ItemId};
+use crate::item_availability::{ItemAvailability, Unavailability};
+use crate::list::ListId;
+use crate::simulation::roll_probabilities;
+use crate::utils::ArreDateTime;
+
+/// Where the roll draws its items from
+#[derive(Debug, Clone)]
+pub enum RollSource {
+    /// Every item, whether it belongs to a list or not. All items are equally likely.
+    AllItems,
+    /// Union of the lists, each weighted by its `roll_weight`
+    Lists(Vec<ListId>),
+    /// A hand picked set of items, e.g. from a report. All items are equally likely.
+    Items(Vec<ItemId>),
+}
+
+impl RollSource {
+    /// The list the roll is made on, if it is made on exactly one
+    pub fn single_list(&self) -> Option<ListId> {
+        match self {
+            RollSource::Lists(list_ids) if list_ids.len() == 1 => Some(list_ids[0]),
+            _ => None,
+        }
+    }
+}
+
+/// Weight multiplier of the items behind on a goal set to boost them
+pub const GOAL_BOOST: u32 = 2;
+
+/// Get all items of the source along with the information needed to decide whether and how likely they can be rolled.
+/// Items belonging to several of the lists are returned once, with the highest weight and the longest cooldown.
+/// Items behind on their goal, or on the goal of the list they are rolled from, are boosted at `now`.
+pub fn roll_candidates_get(conn: &Connection, source: &RollSource, now: DateTime<Utc>) -> ArreResult<Vec<RollCandidate>> {
+    let mut candidates = Vec::<RollCandidate>::new();
+    let mut positions = HashMap::<ItemId, usize>::new();
+    let mut merge = |candidate: RollCandidate| -> ArreResult<()> {
+        let item_id = candidate.item.get_id()?;
+        match positions.get(&item_id) {
+            Some(&position) => {
+                let existing = &mut candidates[position];
+                existing.weight = existing.weight.max(candidate.weight);
+                existing.cooldown_end = existing.cooldown_end.max(candidate.cooldown_end);
+            }
+            None => {
+                positions.insert(item_id, candidates.len());
+                candidates.push(candidate);
+            }
+        }
+        Ok(())
+    };
+    match source {
+        RollSource::AllItems => {
+            for mut candidate in roll_candidates_query(conn, None)? {
+                candidate.weight = 1;
+                merge(candidate)?;
+            }
+        }
+        RollSource::Items(item_ids) => {
+            for mut candidate in roll_candidates_query(conn, None)? {
+                if item_ids.contains(&candidate.item.get_id()?) {
+                    candidate.weight = 1;
+                    merge(candidate)?;
+                }
+            }
+        }
+        RollSource::Lists(list_ids) => {
+            for list_id in list_ids {
+                let boost = goal_boost(list_goal_progress_get(conn, *list_id, now)?.as_ref(), now);
+                for mut candidate in roll_candidates_query(conn, Some(*list_id))? {
+                    candidate.weight *= boost;
+                    merge(candidate)?;
+                }
+            }
+        }
+    }
+    let goals_progress = item_boost_goals_progress_get(conn, now)?;
+    for candidate in candidates.iter_mut() {
+        candidate.weight *= goal_boost(goals_progress.get(&candidate.item.get_id()?), now);
+    }
+    Ok(candidates)
+}
+
+fn goal_boost(progress: Option<&GoalProgress>, now: DateTime<Utc>) -> u32 {
+    match progress {
+        Some(progress) if progress.goal.is_boost && progress.is_behind(now) => GOAL_BOOST,
+        _ => 1,
+    }
+}
+
+/// One row per item and list it belongs to. Without `list_id` all items are returned, including those in no list.
+fn roll_candidates_query(conn: &Connection, list_id: Option<ListId>) -> ArreResult<Vec<RollCandidate>> {
+    let mut stmt = conn.prepare("
+        SELECT
+         i.item_id, i.created_date, i.updated_date, i.name, i.description, i.is_suspended, i.is_finished,
+         s.last_worked_date, COALESCE(d.cooldown, ld.cooldown), COALESCE(ld.roll_weight, 1), d.session_duration,
+         a.item_id, a.weekdays, a.hour_start, a.hour_end, a.date_start, a.date_end
+        FROM items i
+        JOIN item_stats s ON i.item_id = s.item_id
+        JOIN item_details d ON i.item_id = d.item_id
+        JOIN item_availability a ON i.item_id = a.item_id
+        LEFT JOIN item_list_map ilm ON i.item_id = ilm.item_id
+        LEFT JOIN list_details ld ON ilm.list_id = ld.list_id
+        WHERE ?1 IS NULL OR ilm.list_id = ?1
+    ")?;
+    let result = stmt.query_map([list_id], |row| {
+        RollCandidate::from_row(row)
+    })?.collect::<Result<_>>()?;
+    Ok(result)
+}
+
+/// Get the items of the source that can be rolled at the given moment and within the time budget, if any
+pub fn roll_eligible_items_get(
+    conn: &Connection,
+    source: &RollSource,
+    now: DateTime<Utc>,
+    time_budget: Option<&TimeBudget>,
+) -> ArreResult<Vec<Item>> {
+    Ok(roll_candidates_get(conn, source, now)?
+        .into_iter()
+        .filter(|candidate| candidate.is_eligible(now) && candidate.unavailability(now).is_none())
+        .filter(|candidate| time_budget.is_none_or(|budget| candidate.fits_time_budget(budget)))
+        .map(|candidate| candidate.item)
+        .collect())
+}
+
+#[derive(Debug, Clone)]
+pub struct RollCandidate {
+    pub item: Item,
+    pub cooldown_end: Option<DateTime<Utc>>, // None if the item was never worked or has no cooldown
+    pub weight: u32,
+    pub session_duration: Option<Duration>,
+    pub availability: ItemAvailability,
+}
+
+impl RollCandidate {
+    /// Expects item columns followed by the last worked date, the effective cooldown in seconds, the weight
+    /// the session duration in seconds and the availability columns
+    pub fn from_row(row: &Row) -> Result<RollCandidate> {
+        let last_worked = row.get::<_, Option<ArreDateTime<Utc>>>(7)?;
+        let cooldown = row.get::<_, Option<i64>>(8)?.map(Duration::seconds);
+        Ok(RollCandidate {
+            item: Item::from_row(row)?,
+            cooldown_end: last_worked.zip(cooldown).map(|(last_worked, cooldown)| *last_worked + cooldown),
+            weight: row.get(9)?,
+            session_duration: row.get::<_, Option<i64>>(10)?.map(Duration::seconds),
+            availability: ItemAvailability::from_row_at(row, 11)?,
+        })
+    }
+
+    /// Time left until the item can be rolled again, None if it is not cooling down
+    pub fn cooldown_left(&self, now: DateTime<Utc>) -> Option<Duration> {
+        self.cooldown_end
+            .filter(|cooldown_end| *cooldown_end > now)
+            .map(|cooldown_end| cooldown_end - now)
+    }
+
+    pub fn is_eligible(&self, now: DateTime<Utc>) -> bool {
+        self.cooldown_left(now).is_none()
+    }
+
+    /// Why the item cannot be rolled at the given moment, evaluated in local time. None if it is available.
+    pub fn unavailability(&self, now: DateTime<Utc>) -> Option<Unavailability> {
+        self.availability.check(now.with_timezone(&Local).naive_local())
+    }
+
+    pub fn fits_time_budget(&self, budget: &TimeBudget) -> bool {
+        budget.fits(self.session_duration)
+    }
+}
+
+/// What to do with items that have no session duration when rolling with a time budget
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Default)]
+pub enum NoDurationPolicy {
+    #[default]
+    Include,
+    Exclude,
+}
+
+/// Time available for the work, only items whose session fits in it can be rolled
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct TimeBudget {
+    pub available: Duration,
+    pub no_duration_policy: NoDurationPolicy,
+}
+
+impl TimeBudget {
+    pub fn fits(&self, session_duration: Option<Duration>) -> bool {
+        match session_duration {
+            Some(session_duration) => session_duration <= self.available,
+            None => self.no_duration_policy == NoDurationPolicy::Include,
+        }
+    }
+}
+
+/// Keep track of the items drawn by a roll, in the order of the draw, along with the odds every candidate had at each draw.
+/// `list_id` is only set for rolls made on a single list.
+/// The roll is recorded in one transaction, so that no draw is ever left without its odds.
+pub fn roll_record(
+    conn: &Connection,
+    list_id: Option<ListId>,
+    candidates: &[(ItemId, u32)],
+    drawn: &[ItemId],
+) -> ArreResult<()> {
+    let tx = conn.unchecked_transaction()?;
+    roll_insert(&tx, list_id, candidates, drawn)?;
+    tx.commit()?;
+    Ok(())
+}
+
+/// Statements of `roll_record`, for callers recording the roll in a transaction of their own
+pub fn roll_insert(
+    conn: &Connection,
+    list_id: Option<ListId>,
+    candidates: &[(ItemId, u32)],
+    drawn: &[ItemId],
+) -> ArreResult<()> {
+    let odds = roll_draw_odds(candidates, drawn)?;
+    let mut stmt = conn.prepare("INSERT INTO rolls (list_id, item_id, created_date) VALUES (?1, ?2, ?3)")?;
+    let mut odds_stmt = conn.prepare("INSERT INTO roll_odds (roll_id, item_id, probability) VALUES (?1, ?2, ?3)")?;
+    let now = ArreDateTime::now();
+    for (item_id, draw_odds) in drawn.iter().zip(odds) {
+        stmt.execute((list_id, *item_id, now.clone()))?;
+        let roll_id = conn.last_insert_rowid();
+        for (candidate_id, probability) in draw_odds {
+            odds_stmt.execute((roll_id, candidate_id, probability))?;
+        }
+    }
+    Ok(())
+}
+
+/// Probability of each candidate still in the pool to be drawn, for every draw of `drawn`.
+/// Like in `roll_draw`, drawn candidates leave the pool and candidates without weight are never drawn.
+pub fn roll_draw_odds(candidates: &[(ItemId, u32)], drawn: &[ItemId]) -> ArreResult<Vec<Vec<(ItemId, f64)>>> {
+    let mut pool = candidates
+        .iter()
+        .filter(|(_, weight)| *weight > 0)
+        .copied()
+        .collect::<Vec<_>>();
+    let mut odds = Vec::with_capacity(drawn.len());
+    for item_id in drawn {
+        let probabilities = roll_probabilities(&pool)?;
+        odds.push(pool.iter().map(|(candidate_id, _)| *candidate_id).zip(probabilities).collect());
+        pool.retain(|(candidate_id, _)| candidate_id != item_id);
+    }
+    Ok(odds)
+}
+
+/// Number of items drawn by the rolls made on the list alone
+pub fn roll_count(conn: &Connection, list_id: ListId) -> ArreResult<usize> {
+    let mut stmt = conn.prepare("SELECT COUNT(*) FROM rolls WHERE list_id = ?1")?;
+    Ok(stmt.query_row([list_id], |row| row.get(0))?)
+}
+
+/// Draw up to `count` distinct candidates, each paired with its weight.
+/// The order of the result is the order of the draw.
+pub fn roll_draw<T: Clone>(rng: &mut impl Rng, candidates: &[(T, u32)], count: usize) -> ArreResult<Vec<T>> {
+    let mut pool = candidates
+        .iter()
+        .filter(|(_, weight)| *weight > 0)
+        .cloned()
+        .collect::<Vec<_>>();
+    if pool.is_empty() || count == 0 {
+        return Err(ArreError::ItemsSelectionIsEmpty().into());
+    }
+    let mut drawn = Vec::with_capacity(count.min(pool.len()));
+    while drawn.len() < count && !pool.is_empty() {
+        let index = WeightedIndex::new(pool.iter().map(|(_, weight)| *weight))?;
+        let (candidate, _) = pool.swap_remove(index.sample(rng));
+        drawn.push(candidate);
+    }
+    Ok(drawn)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use rstest::*;
+    use rusqlite::Connection;
+    use chrono::Datelike;
+    use crate::goal::{Goal, GoalPeriod, GoalTarget};
+    use crate::item::items_to_ids;
+    use crate::item_availability::{item_availability_get, item_availability_update, WeekdaySet};
+    use crate::item_details::{item_details_get, item_details_update};
+    use crate::session::{Session, session_persist};
+    use crate::list::list_items_add;
+    use crate::list_details::{list_details_get, list_details_update};
+    use crate::test_fixtures::{conn, TestFactory};
+    use super::*;
+
+    fn set_last_worked(conn: &Connection, item: &Item, last_worked: DateTime<Utc>) -> ArreResult<()> {
+        session_persist(conn, &mut Session::new(item.get_id()?, None, last_worked, last_worked))
+    }
+
+    fn set_item_cooldown(conn: &Connection, item: &Item, cooldown: Duration) -> ArreResult<()> {
+        let mut details = item_details_get(conn, item.get_id()?)?;
+        details.cooldown = Some(cooldown);
+        item_details_update(conn, &details)
+    }
+
+    #[rstest]
+    fn cooldown_excludes_recently_worked_items(conn: Connection) -> ArreResult<()> {
+        let mut tf = TestFactory::new(&conn);
+        let list_id = tf.create_lists(1)?[0].get_id()?;
+        let items = tf.create_items(4)?;
+        list_items_add(&conn, list_id, items_to_ids::<_, Vec<_>>(items.iter())?)?;
+        let now = Utc::now();
+        // 0: cooldown set, worked recently -> cooling down
+        set_item_cooldown(&conn, &items[0], Duration::days(2))?;
+        set_last_worked(&conn, &items[0], now - Duration::days(1))?;
+        // 1: cooldown set, worked long ago -> eligible
+        set_item_cooldown(&conn, &items[1], Duration::days(2))?;
+        set_last_worked(&conn, &items[1], now - Duration::days(3))?;
+        // 2: cooldown set, never worked -> eligible
+        set_item_cooldown(&conn, &items[2], Duration::days(2))?;
+        // 3: no cooldown, worked recently -> eligible
+        set_last_worked(&conn, &items[3], now - Duration::minutes(1))?;
+
+        let eligible = roll_eligible_items_get(&conn, &RollSource::Lists(vec![list_id]), now, None)?;
+        let eligible_ids = items_to_ids::<_, HashSet<_>>(eligible.iter())?;
+        let expected_ids = items_to_ids::<_, HashSet<_>>(items[1..].iter())?;
+        assert_eq!(eligible_ids, expected_ids);
+
+        let cooling = roll_candidates_get(&conn, &RollSource::Lists(vec![list_id]), now)?
+            .into_iter()
+            .find(|candidate| candidate.item.id == items[0].id)
+            .unwrap();
+        assert_eq!(cooling.cooldown_left(now), Some(Duration::days(1)));
+        Ok(())
+    }
+
+    #[rstest]
+    fn cooldown_falls_back_to_list_default(conn: Connection) -> ArreResult<()> {
+        let mut tf = TestFactory::new(&conn);
+        let lists = tf.create_lists(2)?;
+        let items = tf.create_items(2)?;
+        let item_ids = items_to_ids::<_, Vec<_>>(items.iter())?;
+        for list in lists.iter() {
+            list_items_add(&conn, list.get_id()?, item_ids.iter())?;
+        }
+        // Only the first list has a default cooldown, and the second item overrides it with a shorter one
+        let mut list_details = list_details_get(&conn, lists[0].get_id()?)?;
+        list_details.cooldown = Some(Duration::days(2));
+        list_details_update(&conn, &list_details)?;
+        set_item_cooldown(&conn, &items[1], Duration::hours(1))?;
+        let now = Utc::now();
+        for item in items.iter() {
+            set_last_worked(&conn, item, now - Duration::hours(2))?;
+        }
+
+        let eligible = roll_eligible_items_get(&conn, &RollSource::Lists(vec![lists[0].get_id()?]), now, None)?;
+        assert_eq!(items_to_ids::<_, Vec<_>>(eligible.iter())?, vec![item_ids[1]]);
+        let eligible = roll_eligible_items_get(&conn, &RollSource::Lists(vec![lists[1].get_id()?]), now, None)?;
+        assert_eq!(eligible.len(), 2);
+        Ok(())
+    }
+
+    #[rstest]
+    #[case(NoDurationPolicy::Include, vec![0, 1, 3])]
+    #[case(NoDurationPolicy::Exclude, vec![0, 1])]
+    fn time_budget_restricts_items(
+        conn: Connection,
+        #[case] no_duration_policy: NoDurationPolicy,
+        #[case] expected: Vec<usize>,
+    ) -> ArreResult<()> {
+        let mut tf = TestFactory::new(&conn);
+        let list_id = tf.create_lists(1)?[0].get_id()?;
+        let items = tf.create_items(4)?;
+        list_items_add(&conn, list_id, items_to_ids::<_, Vec<_>>(items.iter())?)?;
+        // 0: shorter than the budget, 1: exactly the budget, 2: longer than the budget, 3: no duration
+        for (item, session_duration) in items.iter().zip([10, 25, 40]) {
+            let mut details = item_details_get(&conn, item.get_id()?)?;
+            details.session_duration = Some(Duration::minutes(session_duration));
+            item_details_update(&conn, &details)?;
+        }
+
+        let budget = TimeBudget { available: Duration::minutes(25), no_duration_policy };
+        let eligible = roll_eligible_items_get(&conn, &RollSource::Lists(vec![list_id]), Utc::now(), Some(&budget))?;
+        let eligible_ids = items_to_ids::<_, HashSet<_>>(eligible.iter())?;
+        let expected_ids = items_to_ids::<_, HashSet<_>>(expected.into_iter().map(|idx| &items[idx]))?;
+        assert_eq!(eligible_ids, expected_ids);
+        Ok(())
+    }
+
+    #[rstest]
+    fn unavailable_items_are_excluded(conn: Connection) -> ArreResult<()> {
+        let mut tf = TestFactory::new(&conn);
+        let list_id = tf.create_lists(1)?[0].get_id()?;
+        let items = tf.create_items(2)?;
+        list_items_add(&conn, list_id, items_to_ids::<_, Vec<_>>(items.iter())?)?;
+        let now = Utc::now();
+        let today = now.with_timezone(&Local).weekday();
+        // Item 0 is available every day but today
+        let mut availability = item_availability_get(&conn, items[0].get_id()?)?;
+        let weekdays = WeekdaySet::ALL_DAYS.into_iter().filter(|weekday| *weekday != today).collect::<WeekdaySet>();
+        availability.weekdays = Some(weekdays);
+        item_availability_update(&conn, &availability)?;
+
+        let eligible = roll_eligible_items_get(&conn, &RollSource::Lists(vec![list_id]), now, None)?;
+        assert_eq!(items_to_ids::<_, Vec<_>>(eligible.iter())?, vec![items[1].get_id()?]);
+        let candidate = roll_candidates_get(&conn, &RollSource::Lists(vec![list_id]), now)?
+            .into_iter()
+            .find(|candidate| candidate.item.id == items[0].id)
+            .unwrap();
+        assert_eq!(candidate.unavailability(now), Some(Unavailability::Weekdays(weekdays)));
+        Ok(())
+    }
+
+    #[rstest]
+    fn lists_union_deduplicates_items(conn: Connection) -> ArreResult<()> {
+        let mut tf = TestFactory::new(&conn);
+        let lists = tf.create_lists(2)?;
+        let items = tf.create_items(3)?;
+        let item_ids = items_to_ids::<_, Vec<_>>(items.iter())?;
+        // Item 1 belongs to both lists
+        list_items_add(&conn, lists[0].get_id()?, item_ids[0..2].iter())?;
+        list_items_add(&conn, lists[1].get_id()?, item_ids[1..3].iter())?;
+        let mut list_details = list_details_get(&conn, lists[1].get_id()?)?;
+        list_details.roll_weight = 3;
+        list_details_update(&conn, &list_details)?;
+
+        let source = RollSource::Lists(vec![lists[0].get_id()?, lists[1].get_id()?]);
+        let candidates = roll_candidates_get(&conn, &source, Utc::now())?;
+        assert_eq!(candidates.len(), 3, "Items shared by lists should be returned once");
+        let weights = candidates
+            .iter()
+            .map(|candidate| Ok((candidate.item.get_id()?, candidate.weight)))
+            .collect::<ArreResult<HashMap<_, _>>>()?;
+        assert_eq!(weights[&item_ids[0]], 1);
+        assert_eq!(weights[&item_ids[1]], 3, "Shared items should keep the highest weight");
+        assert_eq!(weights[&item_ids[2]], 3);
+        Ok(())
+    }
+
+    #[rstest]
+    fn all_items_include_items_without_list(conn: Connection) -> ArreResult<()> {
+        let mut tf = TestFactory::new(&conn);
+        let lists = tf.create_lists(2)?;
+        let items = tf.create_items(3)?;
+        let item_ids = items_to_ids::<_, Vec<_>>(items.iter())?;
+        list_items_add(&conn, lists[0].get_id()?, item_ids[0..2].iter())?;
+        list_items_add(&conn, lists[1].get_id()?, item_ids[0..1].iter())?;
+
+        let candidates = roll_candidates_get(&conn, &RollSource::AllItems, Utc::now())?;
+        let candidate_ids = candidates
+            .iter()
+            .map(|candidate| candidate.item.get_id())
+            .collect::<ArreResult<HashSet<_>>>()?;
+        assert_eq!(candidates.len(), 3);
+        assert_eq!(candidate_ids, item_ids.into_iter().collect());
+        assert!(candidates.iter().all(|candidate| candidate.weight == 1));
+        Ok(())
+    }
+
+    #[rstest]
+    fn items_source_only_includes_the_given_items(conn: Connection) -> ArreResult<()> {
+        let mut tf = TestFactory::new(&conn);
+        let lists = tf.create_lists(1)?;
+        let items = tf.create_items(3)?;
+        let item_ids = items_to_ids::<_, Vec<_>>(items.iter())?;
+        list_items_add(&conn, lists[0].get_id()?, item_ids[0..2].iter())?;
+        let mut details = list_details_get(&conn, lists[0].get_id()?)?;
+        details.roll_weight = 5;
+        list_details_update(&conn, &details)?;
+
+        let source = RollSource::Items(vec![item_ids[1], item_ids[2]]);
+        let candidates = roll_candidates_get(&conn, &source, Utc::now())?;
+        let candidate_ids = candidates
+            .iter()
+            .map(|candidate| candidate.item.get_id())
+            .collect::<ArreResult<HashSet<_>>>()?;
+        assert_eq!(candidate_ids, item_ids[1..3].iter().copied().collect());
+        assert!(candidates.iter().all(|candidate| candidate.weight == 1), "List weights should not apply");
+        Ok(())
+    }
+
+    #[rstest]
+    fn items_behind_on_boosted_goals_are_more_likely(conn: Connection) -> ArreResult<()> {
+        let mut tf = TestFactory::new(&conn);
+        let lists = tf.create_lists(2)?;
+        let items = tf.create_items(4)?;
+        let item_ids = items_to_ids::<_, Vec<_>>(items.iter())?;
+        list_items_add(&conn, lists[0].get_id()?, item_ids[0..3].iter())?;
+        list_items_add(&conn, lists[1].get_id()?, item_ids[3..4].iter())?;
+        // Middle of the week, nothing done yet
+        let now = GoalPeriod::Week.bounds(Utc::now()).0 + Duration::days(3);
+        let goal = Goal { target: GoalTarget::Sessions(2), period: GoalPeriod::Week, is_boost: true };
+        // 0: boosted goal, behind
+        let mut details = item_details_get(&conn, item_ids[0])?;
+        details.goal = Some(goal);
+        item_details_update(&conn, &details)?;
+        // 1: goal without boost
+        let mut details = item_details_get(&conn, item_ids[1])?;
+        details.goal = Some(Goal { is_boost: false, ..goal });
+        item_details_update(&conn, &details)?;
+        // 2: boosted goal, already reached
+        let mut details = item_details_get(&conn, item_ids[2])?;
+        details.goal = Some(goal);
+        item_details_update(&conn, &details)?;
+        for _ in 0..2 {
+            session_persist(&conn, &mut Session::new(item_ids[2], None, now - Duration::hours(2), now - Duration::hours(1)))?;
+        }
+        // 3: in a list with a boosted goal, behind
+        let mut list_details = list_details_get(&conn, lists[1].get_id()?)?;
+        list_details.goal = Some(goal);
+        list_details_update(&conn, &list_details)?;
+
+        let source = RollSource::Lists(vec![lists[0].get_id()?, lists[1].get_id()?]);
+        let weights = roll_candidates_get(&conn, &source, now)?
+            .iter()
+            .map(|candidate| Ok((candidate.item.get_id()?, candidate.weight)))
+            .collect::<ArreResult<HashMap<_, _>>>()?;
+        assert_eq!(weights[&item_ids[0]], GOAL_BOOST);
+        assert_eq!(weights[&item_ids[1]], 1);
+        assert_eq!(weights[&item_ids[2]], 1);
+        assert_eq!(weights[&item_ids[3]], GOAL_BOOST);
+        Ok(())
+    }
+
+    #[rstest]
+    #[case(10, 1, 1)]
+    #[case(10, 4, 4)]
+    #[case(10, 10, 10)]
+    #[case(3, 5, 3)]
+    fn roll_draw_distinct_items(
+        #[case] candidates_nb: usize,
+        #[case] count: usize,
+        #[case] expected_nb: usize,
+    ) -> ArreResult<()> {
+        let mut rng = StdRng::seed_from_u64(7);
+        let candidates = (0..candidates_nb).map(|c| (c, 1)).collect::<Vec<_>>();
+        let drawn = roll_draw(&mut rng, &candidates, count)?;
+        assert_eq!(drawn.len(), expected_nb);
+        assert_eq!(drawn.iter().collect::<HashSet<_>>().len(), expected_nb, "Drawn items should be distinct");
+        Ok(())
+    }
+
+    #[rstest]
+    #[case(0, 1)]
+    #[case(3, 0)]
+    fn roll_draw_nothing_fails(
+        #[case] candidates_nb: usize,
+        #[case] count: usize,
+    ) {
+        let mut rng = StdRng::seed_from_u64(7);
+        let candidates = (0..candidates_nb).map(|c| (c, 1)).collect::<Vec<_>>();
+        assert!(roll_draw(&mut rng, &candidates, count).is_err());
+    }
+
+    #[rstest]
+    fn roll_draw_respects_weights() -> ArreResult<()> {
+        let mut rng = StdRng::seed_from_u64(7);
+        let candidates = vec![("light", 1), ("heavy", 9), ("never", 0)];
+        let mut heavy_nb = 0;
+        for _ in 0..1000 {
+            let drawn = roll_draw(&mut rng, &candidates, 1)?;
+            assert_ne!(drawn[0], "never", "Zero weight candidates should never be drawn");
+            if drawn[0] == "heavy" { heavy_nb += 1; }
+        }
+        assert!((850..950).contains(&heavy_nb), "Heavy candidate drawn {} times out of 1000", heavy_nb);
+        // Zero weight candidates are not drawn even when more items are requested than available
+        assert_eq!(roll_draw(&mut rng, &candidates, 3)?.len(), 2);
+        Ok(())
+    }
+
+    #[rstest]
+    fn roll_draw_order_is_random() -> ArreResult<()> {
+        // With 5 candidates drawn fully, the draw order should not always be the input order
+        let mut rng = StdRng::seed_from_u64(7);
+        let candidates = (0..5).map(|c| (c, 1)).collect::<Vec<_>>();
+        let input_order = (0..5).collect::<Vec<_>>();
+        let reordered = (0..20)
+            .map(|_| roll_draw(&mut rng, &candidates, 5))
+            .collect::<ArreResult<Vec<_>>>()?
+            .into_iter()
+            .any(|drawn| drawn != input_order);
+        assert!(reordered);
+        Ok(())
+    }
+
+    #[rstest]
+    fn roll_draw_odds_follow_the_pool(conn: Connection) -> ArreResult<()> {
+        let mut tf = TestFactory::new(&conn);
+        let item_ids = items_to_ids::<_, Vec<_>>(tf.create_items(3)?.iter())?;
+        let candidates = vec![(item_ids[0], 1), (item_ids[1], 3), (item_ids[2], 0)];
+        let odds = roll_draw_odds(&candidates, &[item_ids[1], item_ids[0]])?;
+        assert_eq!(odds[0], vec![(item_ids[0], 0.25), (item_ids[1], 0.75)], "Zero weight candidates have no odds");
+        assert_eq!(odds[1], vec![(item_ids[0], 1.0)], "Drawn candidates leave the pool");
+
+        roll_record(&conn, None, &candidates, &[item_ids[1], item_ids[0]])?;
+        tf.assert_table_count("rolls", 2)?;
+        tf.assert_table_count("roll_odds", 3)?;
+        Ok(())
+    }
+
+    #[rstest]
+    fn failed_roll_record_leaves_nothing(conn: Connection) -> ArreResult<()> {
+        let mut tf = TestFactory::new(&conn);
+        let item_ids = items_to_ids::<_, Vec<_>>(tf.create_items(2)?.iter())?;
+        // The odds of the missing item fail to be stored once the first draw is
+        let candidates = vec![(item_ids[0], 1), (item_ids[1], 1), (ItemId::new(9999), 1)];
+        assert!(roll_record(&conn, None, &candidates, &item_ids).is_err());
+        tf.assert_table_count("rolls", 0)?;
+        tf.assert_table_count("roll_odds", 0)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,816 @@
+use chrono::{DateTime, Duration, Utc};
+use rusqlite::{Connection, Result, Row};
+use crate::errors::{ArreError, ArreResult};
+use crate::item::{item_get, item_update, ItemId};
+use crate::item_details::item_details_get;
+use crate::item_stats::item_stats_recompute;
+use crate::list::ListId;
+use crate::utils::{ArreDateTime, Id};
+
+/// Record a session and refresh the stats of its item
+pub fn session_persist(conn: &Connection, session: &mut Session) -> ArreResult<()> {
+    conn.execute("
+        INSERT INTO sessions (item_id, list_id, start_date, end_date, duration, planned_duration, overtime, pomodoros, is_abandoned, abandon_reason) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10);
+        ", (
+            session.item_id,
+            session.list_id,
+            &session.start,
+            &session.end,
+            session.duration.num_seconds(),
+            session.planned_duration.map(|pd| pd.num_seconds()),
+            session.overtime.num_seconds(),
+            session.pomodoros,
+            session.is_abandoned,
+            &session.abandon_reason,
+        ),
+    )?;
+    session.id = Some(SessionId::new(conn.last_insert_rowid()));
+    item_stats_recompute(conn, session.item_id)
+}
+
+/// Credit time worked outside of a roll, e.g. when the roulette was not started before working
+pub fn session_manual_create(conn: &Connection, item_id: ItemId, start: DateTime<Utc>, end: DateTime<Utc>) -> ArreResult<Session> {
+    let mut session = Session::new(item_id, None, start, end);
+    session_validate(conn, &session)?;
+    session_persist(conn, &mut session)?;
+    Ok(session)
+}
+
+/// Change the times of a past session, its overtime and item stats are recomputed
+pub fn session_update(conn: &Connection, session: &Session) -> ArreResult<()> {
+    session_validate(conn, session)?;
+    let overtime = Session::overtime_of(session.duration, session.planned_duration);
+    conn.execute("
+        UPDATE sessions
+        SET start_date = ?2, end_date = ?3, duration = ?4, overtime = ?5
+        WHERE session_id = ?1
+    ", (session.get_id()?, &session.start, &session.end, session.duration.num_seconds(), overtime.num_seconds()),
+    )?;
+    item_stats_recompute(conn, session.item_id)
+}
+
+/// Check the session times are consistent and that it does not overlap another session
+pub fn session_validate(conn: &Connection, session: &Session) -> ArreResult<()> {
+    if *session.end < *session.start || session.duration < Duration::zero() || session.duration > *session.end - *session.start {
+        return Err(ArreError::SessionTimesInvalid().into());
+    }
+    let mut stmt = conn.prepare("
+        SELECT
+         session_id, item_id, list_id, start_date, end_date, duration, planned_duration, overtime, pomodoros, is_abandoned, abandon_reason,
+         notes, satisfaction, difficulty, progress, is_item_finished
+        FROM sessions
+        WHERE session_id IS NOT ?1 AND start_date < ?3 AND end_date > ?2
+        ORDER BY start_date
+        LIMIT 1
+    ")?;
+    let mut overlapping = stmt.query_map((session.id, &session.start, &session.end), |row| {
+        Session::from_row(row)
+    })?;
+    if let Some(other) = overlapping.next().transpose()? {
+        return Err(ArreError::SessionOverlap(other.start.to_string(), other.end.to_string()).into());
+    }
+    Ok(())
+}
+
+pub fn session_get(conn: &Connection, id: impl Into<SessionId>) -> ArreResult<Session> {
+    let mut stmt = conn.prepare("
+        SELECT
+         session_id, item_id, list_id, start_date, end_date, duration, planned_duration, overtime, pomodoros, is_abandoned, abandon_reason,
+         notes, satisfaction, difficulty, progress, is_item_finished
+        FROM sessions
+        WHERE session_id = ?1
+    ")?;
+    Ok(stmt.query_row([id.into()], |row| {
+        Session::from_row(row)
+    })?)
+}
+
+/// Sessions of the item, the oldest first
+pub fn item_sessions_get<C>(conn: &Connection, item_id: impl Into<ItemId>) -> ArreResult<C>
+where C: FromIterator<Session>
+{
+    let mut stmt = conn.prepare("
+        SELECT
+         session_id, item_id, list_id, start_date, end_date, duration, planned_duration, overtime, pomodoros, is_abandoned, abandon_reason,
+         notes, satisfaction, difficulty, progress, is_item_finished
+        FROM sessions
+        WHERE item_id = ?1
+        ORDER BY start_date
+    ")?;
+    let result = stmt.query_map([item_id.into()], |row| {
+        Session::from_row(row)
+    })?.collect::<Result<C>>()?;
+    Ok(result)
+}
+
+/// Store how the session went. Marking the item as finished finishes the item itself too.
+pub fn session_outcome_update(conn: &Connection, id: impl Into<SessionId>, outcome: &SessionOutcome) -> ArreResult<()> {
+    let session = session_get(conn, id)?;
+    conn.execute("
+        UPDATE sessions
+        SET notes = ?2, satisfaction = ?3, difficulty = ?4, progress = ?5, is_item_finished = ?6
+        WHERE session_id = ?1
+    ", (
+        session.get_id()?,
+        &outcome.notes,
+        outcome.satisfaction,
+        outcome.difficulty,
+        outcome.progress,
+        outcome.is_item_finished,
+    ))?;
+    if outcome.is_item_finished {
+        let mut item = item_get(conn, session.item_id)?;
+        item.is_finished = true;
+        item_update(conn, &item)?;
+    }
+    Ok(())
+}
+
+pub fn session_delete(conn: &Connection, id: impl Into<SessionId>) -> ArreResult<()> {
+    let session = session_get(conn, id)?;
+    conn.execute("DELETE FROM sessions WHERE session_id = ?1;", (session.get_id()?,))?;
+    item_stats_recompute(conn, session.item_id)
+}
+
+/// Persist the session being worked on, so it survives the app being closed.
+/// There is at most one active session, starting a new one replaces it.
+pub fn active_session_start(conn: &Connection, session: &ActiveSession) -> ArreResult<()> {
+    active_session_discard(conn)?;
+    conn.execute("
+        INSERT OR REPLACE INTO active_session (active_session_id, item_id, list_id, start_date) VALUES (1, ?1, ?2, ?3);
+        ", (session.item_id, session.list_id, &session.start),
+    )?;
+    Ok(())
+}
+
+pub fn active_session_get(conn: &Connection) -> ArreResult<Option<ActiveSession>> {
+    let mut stmt = conn.prepare("
+        SELECT
+         item_id, list_id, start_date, pomodoros
+        FROM active_session
+    ")?;
+    let mut rows = stmt.query_map([], |row| {
+        ActiveSession::from_row(row)
+    })?;
+    let Some(mut active) = rows.next().transpose()? else { return Ok(None) };
+    active.pauses = session_pauses_query(conn, None)?;
+    Ok(Some(active))
+}
+
+/// Stop counting time toward the active session, does nothing if it is already paused
+pub fn active_session_pause(conn: &Connection, now: DateTime<Utc>) -> ArreResult<()> {
+    let active = active_session_get(conn)?.ok_or(ArreError::NoActiveSession())?;
+    if !active.is_paused() {
+        conn.execute("
+            INSERT INTO session_pauses (session_id, start_date) VALUES (NULL, ?1);
+            ", (ArreDateTime::new(now),),
+        )?;
+    }
+    Ok(())
+}
+
+/// Count time toward the active session again, does nothing if it is not paused
+pub fn active_session_resume(conn: &Connection, now: DateTime<Utc>) -> ArreResult<()> {
+    active_session_get(conn)?.ok_or(ArreError::NoActiveSession())?;
+    conn.execute("
+        UPDATE session_pauses SET end_date = ?1 WHERE session_id IS NULL AND end_date IS NULL
+        ", (ArreDateTime::new(now),),
+    )?;
+    Ok(())
+}
+
+/// End the ongoing pause as if it never happened, so the paused time counts as work
+pub fn active_session_pause_revert(conn: &Connection) -> ArreResult<()> {
+    active_session_get(conn)?.ok_or(ArreError::NoActiveSession())?;
+    conn.execute("DELETE FROM session_pauses WHERE session_id IS NULL AND end_date IS NULL", ())?;
+    Ok(())
+}
+
+/// Count a pomodoro completed during the active session
+pub fn active_session_pomodoro_complete(conn: &Connection) -> ArreResult<()> {
+    let updated = conn.execute("UPDATE active_session SET pomodoros = pomodoros + 1", ())?;
+    if updated == 0 {
+        return Err(ArreError::NoActiveSession().into());
+    }
+    Ok(())
+}
+
+/// Count a pomodoro completed during the active session and start its break at `now`, breaks being pauses.
+/// Both are recorded in one transaction, so that no pomodoro is counted without its break.
+pub fn active_session_pomodoro_break(conn: &Connection, now: DateTime<Utc>) -> ArreResult<()> {
+    let tx = conn.unchecked_transaction()?;
+    active_session_pomodoro_complete(&tx)?;
+    active_session_pause(&tx, now)?;
+    tx.commit()?;
+    Ok(())
+}
+
+/// Record the active session as worked until `end`, an ongoing pause ends with it.
+/// Time worked beyond the session duration planned for the item is recorded as overtime.
+pub fn active_session_finish(conn: &Connection, end: DateTime<Utc>) -> ArreResult<Session> {
+    active_session_record(conn, end, false, None)
+}
+
+/// Record the active session as given up at `end`, it counts as a skip of the item rather than work
+pub fn active_session_abandon(conn: &Connection, end: DateTime<Utc>, reason: Option<String>) -> ArreResult<Session> {
+    active_session_record(conn, end, true, reason)
+}
+
+// Recorded in one transaction, so that a session is never both recorded and still active
+fn active_session_record(conn: &Connection, end: DateTime<Utc>, is_abandoned: bool, abandon_reason: Option<String>) -> ArreResult<Session> {
+    let tx = conn.unchecked_transaction()?;
+    let conn = &*tx;
+    active_session_resume(conn, end)?;
+    let active = active_session_get(conn)?.ok_or(ArreError::NoActiveSession())?;
+    let mut session = Session::new(active.item_id, active.list_id, *active.start, end);
+    session.is_abandoned = is_abandoned;
+    session.abandon_reason = abandon_reason;
+    session.duration = active.active_duration(end);
+    session.planned_duration = item_details_get(conn, active.item_id)?.session_duration;
+    session.overtime = Session::overtime_of(session.duration, session.planned_duration);
+    session.pomodoros = active.pomodoros;
+    session_persist(conn, &mut session)?;
+    conn.execute("
+        UPDATE session_pauses SET session_id = ?1 WHERE session_id IS NULL
+        ", (session.get_id()?,),
+    )?;
+    active_session_discard(conn)?;
+    tx.commit()?;
+    Ok(session)
+}
+
+/// Forget the active session without recording it, its pauses are deleted along with it
+pub fn active_session_discard(conn: &Connection) -> ArreResult<()> {
+    conn.execute("DELETE FROM active_session", ())?;
+    Ok(())
+}
+
+/// Pauses taken during the session, the oldest first
+pub fn session_pauses_get(conn: &Connection, session_id: SessionId) -> ArreResult<Vec<SessionPause>> {
+    session_pauses_query(conn, Some(session_id))
+}
+
+// None stands for the pauses of the active session
+fn session_pauses_query(conn: &Connection, session_id: Option<SessionId>) -> ArreResult<Vec<SessionPause>> {
+    let mut stmt = conn.prepare("
+        SELECT
+         start_date, end_date
+        FROM session_pauses
+        WHERE session_id IS ?1
+        ORDER BY start_date
+    ")?;
+    let result = stmt.query_map([session_id], |row| {
+        SessionPause::from_row(row)
+    })?.collect::<Result<Vec<_>>>()?;
+    Ok(result)
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct SessionPause {
+    pub start: ArreDateTime<Utc>,
+    pub end: Option<ArreDateTime<Utc>>, // None while the pause is ongoing
+}
+
+impl SessionPause {
+    pub fn from_row(row: &Row) -> Result<SessionPause> {
+        Ok(SessionPause {
+            start: row.get(0)?,
+            end: row.get(1)?,
+        })
+    }
+
+    /// Length of the pause, an ongoing one lasts until `now`
+    pub fn duration(&self, now: DateTime<Utc>) -> Duration {
+        self.end.as_deref().copied().unwrap_or(now) - *self.start
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ActiveSession {
+    pub item_id: ItemId,
+    pub list_id: Option<ListId>,
+    pub start: ArreDateTime<Utc>,
+    pub pauses: Vec<SessionPause>,
+    pub pomodoros: u32, // pomodoros completed so far
+}
+
+impl ActiveSession {
+    pub fn new(item_id: ItemId, list_id: Option<ListId>, start: DateTime<Utc>) -> Self {
+        ActiveSession { item_id, list_id, start: start.into(), pauses: vec![], pomodoros: 0 }
+    }
+
+    pub fn from_row(row: &Row) -> Result<ActiveSession> {
+        Ok(ActiveSession {
+            item_id: row.get(0)?,
+            list_id: row.get(1)?,
+            start: row.get(2)?,
+            pauses: vec![],
+            pomodoros: row.get(3)?,
+        })
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.pauses.iter().any(|pause| pause.end.is_none())
+    }
+
+    /// Time worked until `now`, pauses excluded
+    pub fn active_duration(&self, now: DateTime<Utc>) -> Duration {
+        let paused = self.pauses
+            .iter()
+            .fold(Duration::zero(), |paused, pause| paused + pause.duration(now));
+        now - *self.start - paused
+    }
+
+    /// Moment at which `planned` time is worked, None if the session is paused before that
+    pub fn limit_reached_at(&self, planned: Duration) -> Option<DateTime<Utc>> {
+        let mut worked_since = *self.start;
+        let mut remaining = planned;
+        for pause in self.pauses.iter() {
+            let worked = *pause.start - worked_since;
+            if worked >= remaining {
+                break;
+            }
+            remaining -= worked;
+            worked_since = pause.end.as_deref().copied()?;
+        }
+        Some(worked_since + remaining)
+    }
+}
+
+pub type SessionId = Id<Session>;
+#[derive(Debug, Clone)]
+pub struct Session {
+    pub id: Option<SessionId>, // None indicates it's not persisted
+    pub item_id: ItemId,
+    pub list_id: Option<ListId>, // list the item was rolled from, None when it was rolled from several lists or all items
+    pub start: ArreDateTime<Utc>,
+    pub end: ArreDateTime<Utc>,
+    pub duration: Duration, // time actually worked
+    pub planned_duration: Option<Duration>, // session duration of the item when it was worked on
+    pub overtime: Duration, // part of the duration worked beyond planned_duration
+    pub pomodoros: u32, // pomodoros completed during the session, its breaks are recorded as pauses
+    pub is_abandoned: bool, // cancelled or skipped, only counts as a skip of the item
+    pub abandon_reason: Option<String>,
+    pub outcome: SessionOutcome,
+}
+
+/// How the session went, filled in once it is finished
+#[derive(Debug, Clone, Eq, PartialEq, Default)]
+pub struct SessionOutcome {
+    pub notes: Option<String>,
+    pub satisfaction: Option<u8>, // 1 to 5
+    pub difficulty: Option<u8>, // 1 to 5
+    pub progress: Option<u8>, // percentage of the item done
+    pub is_item_finished: bool,
+}
+
+impl SessionOutcome {
+    pub fn from_row_at(row: &Row, first: usize) -> Result<SessionOutcome> {
+        Ok(SessionOutcome {
+            notes: row.get(first)?,
+            satisfaction: row.get(first + 1)?,
+            difficulty: row.get(first + 2)?,
+            progress: row.get(first + 3)?,
+            is_item_finished: row.get(first + 4)?,
+        })
+    }
+}
+
+impl Session {
+    pub fn new(item_id: ItemId, list_id: Option<ListId>, start: DateTime<Utc>, end: DateTime<Utc>) -> Self {
+        Session {
+            id: None,
+            item_id,
+            list_id,
+            start: start.into(),
+            end: end.into(),
+            duration: end - start,
+            planned_duration: None,
+            overtime: Duration::zero(),
+            pomodoros: 0,
+            is_abandoned: false,
+            abandon_reason: None,
+            outcome: SessionOutcome::default(),
+        }
+    }
+
+    pub fn from_row(row: &Row) -> Result<Session> {
+        Ok(Session {
+            id: Some(row.get(0)?),
+            item_id: row.get(1)?,
+            list_id: row.get(2)?,
+            start: row.get(3)?,
+            end: row.get(4)?,
+            duration: Duration::seconds(row.get(5)?),
+            planned_duration: row.get::<_, Option<i64>>(6)?.map(Duration::seconds),
+            overtime: Duration::seconds(row.get(7)?),
+            pomodoros: row.get(8)?,
+            is_abandoned: row.get(9)?,
+            abandon_reason: row.get(10)?,
+            outcome: SessionOutcome::from_row_at(row, 11)?,
+        })
+    }
+
+    pub fn overtime_of(duration: Duration, planned_duration: Option<Duration>) -> Duration {
+        planned_duration
+            .map(|planned| (duration - planned).max(Duration::zero()))
+            .unwrap_or(Duration::zero())
+    }
+
+    pub fn get_id(&self) -> ArreResult<SessionId> {
+        self.id.ok_or(ArreError::ItemNotPersisted().into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+    use rusqlite::Connection;
+    use crate::item::item_delete;
+    use crate::item_details::item_details_update;
+    use crate::item_stats::item_stats_get;
+    use crate::list::list_delete;
+    use crate::test_fixtures::{conn, TestFactory};
+    use super::*;
+
+    #[rstest]
+    fn persist_get_delete_session(conn: Connection) -> ArreResult<()> {
+        let mut tf = TestFactory::new(&conn);
+        let item_id = tf.create_items(1)?[0].get_id()?;
+        let list_id = tf.create_lists(1)?[0].get_id()?;
+        let end = Utc::now();
+        let mut session = Session::new(item_id, Some(list_id), end - Duration::minutes(25), end);
+        session_persist(&conn, &mut session)?;
+        let session_id = session.get_id()?;
+
+        let stored = session_get(&conn, session_id)?;
+        assert_eq!(stored.item_id, item_id);
+        assert_eq!(stored.list_id.map(|id| *id), Some(*list_id));
+        assert_eq!(stored.duration, Duration::minutes(25));
+        assert_eq!(stored.end, ArreDateTime::new(end));
+
+        session_delete(&conn, session_id)?;
+        tf.assert_table_count("sessions", 0)?;
+        assert!(session_get(&conn, session_id).is_err(), "Session should have been deleted");
+        Ok(())
+    }
+
+    #[rstest]
+    fn manual_sessions_are_credited(conn: Connection) -> ArreResult<()> {
+        let mut tf = TestFactory::new(&conn);
+        let item_id = tf.create_items(1)?[0].get_id()?;
+        let start = Utc::now() - Duration::days(1);
+        let session = session_manual_create(&conn, item_id, start, start + Duration::hours(2))?;
+        assert_eq!(item_stats_get(&conn, item_id)?.time_spent, Duration::hours(2));
+
+        let mut edited = session_get(&conn, session.get_id()?)?;
+        edited.end = (start + Duration::hours(1)).into();
+        edited.duration = Duration::hours(1);
+        session_update(&conn, &edited)?;
+        let stats = item_stats_get(&conn, item_id)?;
+        assert_eq!(stats.time_spent, Duration::hours(1));
+        assert_eq!(stats.last_worked, Some(ArreDateTime::new(start + Duration::hours(1))));
+
+        session_delete(&conn, session.get_id()?)?;
+        assert_eq!(item_stats_get(&conn, item_id)?.times_worked, 0);
+        Ok(())
+    }
+
+    #[rstest]
+    #[case::inside(Duration::minutes(30), Duration::minutes(60), false)]
+    #[case::covering(Duration::minutes(-30), Duration::minutes(150), false)]
+    #[case::overlapping_end(Duration::minutes(90), Duration::minutes(180), false)]
+    #[case::touching_end(Duration::minutes(120), Duration::minutes(180), true)]
+    #[case::touching_start(Duration::minutes(-60), Duration::zero(), true)]
+    #[case::end_before_start(Duration::minutes(200), Duration::minutes(180), false)]
+    fn manual_sessions_cannot_overlap(
+        conn: Connection,
+        #[case] start_offset: Duration,
+        #[case] end_offset: Duration,
+        #[case] is_valid: bool,
+    ) -> ArreResult<()> {
+        let mut tf = TestFactory::new(&conn);
+        let item_ids = tf.create_items(2)?.iter().map(|item| item.get_id()).collect::<ArreResult<Vec<_>>>()?;
+        let start = Utc::now() - Duration::days(1);
+        session_manual_create(&conn, item_ids[0], start, start + Duration::hours(2))?;
+        // Overlaps are checked across all items, one cannot work on two items at once
+        let result = session_manual_create(&conn, item_ids[1], start + start_offset, start + end_offset);
+        assert_eq!(result.is_ok(), is_valid, "{:?}", result.err());
+        Ok(())
+    }
+
+    #[rstest]
+    fn edited_session_does_not_overlap_itself(conn: Connection) -> ArreResult<()> {
+        let mut tf = TestFactory::new(&conn);
+        let item_id = tf.create_items(1)?[0].get_id()?;
+        let start = Utc::now() - Duration::days(1);
+        let mut session = session_manual_create(&conn, item_id, start, start + Duration::hours(2))?;
+        let other = session_manual_create(&conn, item_id, start + Duration::hours(3), start + Duration::hours(4))?;
+        session.start = (start + Duration::minutes(30)).into();
+        session.duration = Duration::minutes(90);
+        session_update(&conn, &session)?;
+
+        session.end = (start + Duration::minutes(200)).into();
+        assert!(session_update(&conn, &session).is_err(), "Edited session should not overlap the other one");
+        session.end = (start + Duration::hours(2)).into();
+        session.duration = Duration::hours(3);
+        assert!(session_update(&conn, &session).is_err(), "Duration cannot exceed the session time span");
+        assert_eq!(session_get(&conn, other.get_id()?)?.duration, Duration::hours(1));
+        Ok(())
+    }
+
+    #[rstest]
+    fn outcome_is_stored_with_session(conn: Connection) -> ArreResult<()> {
+        let mut tf = TestFactory::new(&conn);
+        let item_id = tf.create_items(1)?[0].get_id()?;
+        let now = Utc::now();
+        let mut session = Session::new(item_id, None, now - Duration::minutes(30), now);
+        session_persist(&conn, &mut session)?;
+        assert_eq!(session_get(&conn, session.get_id()?)?.outcome, SessionOutcome::default());
+
+        let outcome = SessionOutcome {
+            notes: Some("Halfway through chapter 3".into()),
+            satisfaction: Some(4),
+            difficulty: Some(2),
+            progress: Some(50),
+            is_item_finished: false,
+        };
+        session_outcome_update(&conn, session.get_id()?, &outcome)?;
+        assert_eq!(session_get(&conn, session.get_id()?)?.outcome, outcome);
+        assert!(!item_get(&conn, item_id)?.is_finished);
+
+        let invalid = SessionOutcome { satisfaction: Some(6), ..outcome.clone() };
+        assert!(session_outcome_update(&conn, session.get_id()?, &invalid).is_err(), "Ratings are from 1 to 5");
+
+        let finished = SessionOutcome { progress: Some(100), is_item_finished: true, ..outcome };
+        session_outcome_update(&conn, session.get_id()?, &finished)?;
+        assert!(item_get(&conn, item_id)?.is_finished, "Item should be finished along with the session");
+        Ok(())
+    }
+
+    #[rstest]
+    fn persist_on_persisted_item_only(conn: Connection) {
+        let now = Utc::now();
+        let mut session = Session::new(ItemId::new(1), None, now, now);
+        assert!(session_persist(&conn, &mut session).is_err(), "Session of a non existing item should fail");
+    }
+
+    #[rstest]
+    fn deleting_item_or_list_keeps_sessions_consistent(conn: Connection) -> ArreResult<()> {
+        let mut tf = TestFactory::new(&conn);
+        let item_ids = tf.create_items(2)?.iter().map(|item| item.get_id()).collect::<ArreResult<Vec<_>>>()?;
+        let list_id = tf.create_lists(1)?[0].get_id()?;
+        let now = Utc::now();
+        for item_id in item_ids.iter() {
+            session_persist(&conn, &mut Session::new(*item_id, Some(list_id), now, now))?;
+        }
+
+        list_delete(&conn, list_id)?;
+        let sessions = item_sessions_get::<Vec<_>>(&conn, item_ids[0])?;
+        assert!(sessions[0].list_id.is_none(), "Deleting the list should only forget where the session was rolled from");
+
+        item_delete(&conn, item_ids[0])?;
+        tf.assert_table_count("sessions", 1)?;
+        Ok(())
+    }
+
+    #[rstest]
+    fn active_session_finish_or_discard(conn: Connection) -> ArreResult<()> {
+        let mut tf = TestFactory::new(&conn);
+        let item_ids = tf.create_items(2)?.iter().map(|item| item.get_id()).collect::<ArreResult<Vec<_>>>()?;
+        assert!(active_session_get(&conn)?.is_none());
+        assert!(active_session_finish(&conn, Utc::now()).is_err(), "Finishing without an active session should fail");
+
+        let start = Utc::now() - Duration::hours(2);
+        active_session_start(&conn, &ActiveSession::new(item_ids[0], None, start))?;
+        // Only one session can be active
+        active_session_start(&conn, &ActiveSession::new(item_ids[1], None, start))?;
+        tf.assert_table_count("active_session", 1)?;
+        let active = active_session_get(&conn)?.expect("Session should be active");
+        assert_eq!(active.item_id, item_ids[1]);
+        assert_eq!(active.start, ArreDateTime::new(start));
+
+        let session = active_session_finish(&conn, start + Duration::hours(2))?;
+        assert_eq!(session.duration, Duration::hours(2));
+        assert!(active_session_get(&conn)?.is_none());
+        assert_eq!(item_stats_get(&conn, item_ids[1])?.time_spent, Duration::hours(2));
+
+        active_session_start(&conn, &ActiveSession::new(item_ids[0], None, start))?;
+        active_session_discard(&conn)?;
+        assert!(active_session_get(&conn)?.is_none());
+        tf.assert_table_count("sessions", 1)?;
+        Ok(())
+    }
+
+    #[rstest]
+    fn failed_finish_keeps_session_active(conn: Connection) -> ArreResult<()> {
+        let mut tf = TestFactory::new(&conn);
+        let item_id = tf.create_items(1)?[0].get_id()?;
+        let start = Utc::now() - Duration::hours(2);
+        active_session_start(&conn, &ActiveSession::new(item_id, None, start))?;
+        active_session_pause(&conn, start + Duration::hours(1))?;
+        conn.execute_batch("
+            CREATE TEMP TRIGGER fail_discard BEFORE DELETE ON active_session BEGIN
+              SELECT RAISE(ABORT, 'discard failed');
+            END;
+        ")?;
+
+        assert!(active_session_finish(&conn, start + Duration::hours(2)).is_err());
+        tf.assert_table_count("sessions", 0)?;
+        let active = active_session_get(&conn)?.expect("Session should still be active");
+        assert!(active.is_paused(), "The pause should neither end nor move to a recorded session");
+        assert_eq!(item_stats_get(&conn, item_id)?.times_worked, 0);
+        Ok(())
+    }
+
+    #[rstest]
+    fn abandoned_sessions_count_as_skips(conn: Connection) -> ArreResult<()> {
+        let mut tf = TestFactory::new(&conn);
+        let item_id = tf.create_items(1)?[0].get_id()?;
+        let start = Utc::now() - Duration::hours(2);
+        active_session_start(&conn, &ActiveSession::new(item_id, None, start))?;
+        active_session_finish(&conn, start + Duration::minutes(30))?;
+        active_session_start(&conn, &ActiveSession::new(item_id, None, start + Duration::hours(1)))?;
+        let abandoned = active_session_abandon(&conn, start + Duration::minutes(70), Some("Too tired".into()))?;
+        assert!(active_session_get(&conn)?.is_none());
+
+        let stored = session_get(&conn, abandoned.get_id()?)?;
+        assert!(stored.is_abandoned);
+        assert_eq!(stored.abandon_reason, Some("Too tired".to_string()));
+        assert_eq!(stored.duration, Duration::minutes(10), "Elapsed time is kept with the abandoned session");
+
+        let stats = item_stats_get(&conn, item_id)?;
+        assert_eq!(stats.times_worked, 1);
+        assert_eq!(stats.times_skipped, 1);
+        assert_eq!(stats.time_spent, Duration::minutes(30), "Abandoned sessions do not count as work");
+        assert_eq!(stats.last_worked, Some(ArreDateTime::new(start + Duration::minutes(30))));
+        Ok(())
+    }
+
+    #[rstest]
+    fn pauses_do_not_count_as_work(conn: Connection) -> ArreResult<()> {
+        let mut tf = TestFactory::new(&conn);
+        let item_id = tf.create_items(1)?[0].get_id()?;
+        let start = Utc::now() - Duration::hours(3);
+        assert!(active_session_pause(&conn, start).is_err(), "Pausing without an active session should fail");
+        active_session_start(&conn, &ActiveSession::new(item_id, None, start))?;
+
+        // Lunch break of an hour, pausing twice does not restart the pause
+        active_session_pause(&conn, start + Duration::hours(1))?;
+        active_session_pause(&conn, start + Duration::minutes(90))?;
+        let active = active_session_get(&conn)?.expect("Session should be active");
+        assert!(active.is_paused());
+        assert_eq!(active.active_duration(start + Duration::hours(2)), Duration::hours(1));
+        active_session_resume(&conn, start + Duration::hours(2))?;
+        assert!(!active_session_get(&conn)?.expect("Session should be active").is_paused());
+
+        // Finishing during a pause ends the pause as well
+        active_session_pause(&conn, start + Duration::minutes(150))?;
+        let session = active_session_finish(&conn, start + Duration::hours(3))?;
+        assert_eq!(session.duration, Duration::minutes(90));
+        assert_eq!(session.end, ArreDateTime::new(start + Duration::hours(3)));
+        assert_eq!(item_stats_get(&conn, item_id)?.time_spent, Duration::minutes(90));
+
+        let pauses = session_pauses_get(&conn, session.get_id()?)?;
+        assert_eq!(pauses.len(), 2);
+        assert_eq!(pauses[1].end, Some(ArreDateTime::new(start + Duration::hours(3))));
+        Ok(())
+    }
+
+    #[rstest]
+    #[case::no_limit(None, Duration::zero())]
+    #[case::within_limit(Some(Duration::hours(3)), Duration::zero())]
+    #[case::beyond_limit(Some(Duration::minutes(30)), Duration::minutes(90))]
+    fn overtime_is_recorded_separately(
+        conn: Connection,
+        #[case] session_duration: Option<Duration>,
+        #[case] overtime: Duration,
+    ) -> ArreResult<()> {
+        let mut tf = TestFactory::new(&conn);
+        let item_id = tf.create_items(1)?[0].get_id()?;
+        let mut details = item_details_get(&conn, item_id)?;
+        details.session_duration = session_duration;
+        item_details_update(&conn, &details)?;
+
+        let start = Utc::now() - Duration::hours(2);
+        active_session_start(&conn, &ActiveSession::new(item_id, None, start))?;
+        let session = active_session_finish(&conn, start + Duration::hours(2))?;
+        assert_eq!(session.duration, Duration::hours(2), "Overtime is still part of the time worked");
+        let stored = session_get(&conn, session.get_id()?)?;
+        assert_eq!(stored.planned_duration, session_duration);
+        assert_eq!(stored.overtime, overtime);
+
+        // Shortening the session shortens its overtime
+        let mut edited = stored.clone();
+        edited.end = (start + Duration::hours(1)).into();
+        edited.duration = Duration::hours(1);
+        session_update(&conn, &edited)?;
+        assert_eq!(session_get(&conn, session.get_id()?)?.overtime, Session::overtime_of(Duration::hours(1), session_duration));
+        Ok(())
+    }
+
+    #[rstest]
+    fn pomodoros_are_recorded_with_session(conn: Connection) -> ArreResult<()> {
+        let mut tf = TestFactory::new(&conn);
+        let item_id = tf.create_items(1)?[0].get_id()?;
+        assert!(active_session_pomodoro_complete(&conn).is_err(), "Pomodoros need an active session");
+        let start = Utc::now() - Duration::hours(1);
+        active_session_start(&conn, &ActiveSession::new(item_id, None, start))?;
+        // The break is a pause, so it does not count as work
+        active_session_pomodoro_break(&conn, start + Duration::minutes(25))?;
+        assert!(active_session_get(&conn)?.expect("Session should be active").is_paused());
+        active_session_resume(&conn, start + Duration::minutes(30))?;
+        active_session_pomodoro_complete(&conn)?;
+        assert_eq!(active_session_get(&conn)?.expect("Session should be active").pomodoros, 2);
+
+        let session = active_session_finish(&conn, start + Duration::minutes(55))?;
+        assert_eq!(session.duration, Duration::minutes(50));
+        assert_eq!(session_get(&conn, session.get_id()?)?.pomodoros, 2);
+        assert_eq!(item_stats_get(&conn, item_id)?.time_spent, Duration::minutes(50));
+        Ok(())
+    }
+
+    #[rstest]
+    fn failed_pomodoro_break_does_not_count_pomodoro(conn: Connection) -> ArreResult<()> {
+        let mut tf = TestFactory::new(&conn);
+        let item_id = tf.create_items(1)?[0].get_id()?;
+        active_session_start(&conn, &ActiveSession::new(item_id, None, Utc::now() - Duration::hours(1)))?;
+        conn.execute_batch("
+            CREATE TEMP TRIGGER fail_pause BEFORE INSERT ON session_pauses BEGIN
+              SELECT RAISE(ABORT, 'pause failed');
+            END;
+        ")?;
+
+        assert!(active_session_pomodoro_break(&conn, Utc::now()).is_err());
+        let active = active_session_get(&conn)?.expect("Session should be active");
+        assert_eq!(active.pomodoros, 0);
+        assert!(!active.is_paused());
+        Ok(())
+    }
+
+    #[rstest]
+    fn limit_is_reached_later_when_paused(conn: Connection) -> ArreResult<()> {
+        let mut tf = TestFactory::new(&conn);
+        let item_id = tf.create_items(1)?[0].get_id()?;
+        let start = Utc::now() - Duration::hours(3);
+        active_session_start(&conn, &ActiveSession::new(item_id, None, start))?;
+        let active = active_session_get(&conn)?.expect("Session should be active");
+        assert_eq!(active.limit_reached_at(Duration::minutes(30)), Some(start + Duration::minutes(30)));
+
+        active_session_pause(&conn, start + Duration::minutes(20))?;
+        let active = active_session_get(&conn)?.expect("Session should be active");
+        assert_eq!(active.limit_reached_at(Duration::minutes(10)), Some(start + Duration::minutes(10)));
+        assert_eq!(active.limit_reached_at(Duration::minutes(30)), None, "Limit is not reached while paused");
+
+        active_session_resume(&conn, start + Duration::minutes(50))?;
+        let active = active_session_get(&conn)?.expect("Session should be active");
+        assert_eq!(active.limit_reached_at(Duration::minutes(30)), Some(start + Duration::minutes(60)));
+        Ok(())
+    }
+
+    #[rstest]
+    fn reverted_pause_counts_as_work(conn: Connection) -> ArreResult<()> {
+        let mut tf = TestFactory::new(&conn);
+        let item_id = tf.create_items(1)?[0].get_id()?;
+        let start = Utc::now() - Duration::hours(3);
+        active_session_start(&conn, &ActiveSession::new(item_id, None, start))?;
+        active_session_pause(&conn, start + Duration::minutes(30))?;
+        active_session_resume(&conn, start + Duration::minutes(40))?;
+        // Idle since the second hour, but it turns out the user was working away from the app
+        active_session_pause(&conn, start + Duration::hours(1))?;
+        active_session_pause_revert(&conn)?;
+        let active = active_session_get(&conn)?.expect("Session should be active");
+        assert!(!active.is_paused());
+        assert_eq!(active.pauses.len(), 1, "Only the ongoing pause is reverted");
+        assert_eq!(active.active_duration(start + Duration::hours(3)), Duration::minutes(170));
+        Ok(())
+    }
+
+    #[rstest]
+    fn discarding_active_session_drops_its_pauses(conn: Connection) -> ArreResult<()> {
+        let mut tf = TestFactory::new(&conn);
+        let item_id = tf.create_items(1)?[0].get_id()?;
+        let start = Utc::now();
+        active_session_start(&conn, &ActiveSession::new(item_id, None, start))?;
+        active_session_pause(&conn, start)?;
+        // Starting another session drops the pauses of the replaced one
+        active_session_start(&conn, &ActiveSession::new(item_id, None, start))?;
+        assert!(!active_session_get(&conn)?.expect("Session should be active").is_paused());
+        active_session_pause(&conn, start)?;
+        active_session_discard(&conn)?;
+        tf.assert_table_count("session_pauses", 0)?;
+        Ok(())
+    }
+
+    #[rstest]
+    fn deleting_item_discards_its_active_session(conn: Connection) -> ArreResult<()> {
+        let mut tf = TestFactory::new(&conn);
+        let item_id = tf.create_items(1)?[0].get_id()?;
+        active_session_start(&conn, &ActiveSession::new(item_id, None, Utc::now()))?;
+        active_session_pause(&conn, Utc::now())?;
+        item_delete(&conn, item_id)?;
+        assert!(active_session_get(&conn)?.is_none());
+        tf.assert_table_count("session_pauses", 0)?;
+        Ok(())
+    }
+}
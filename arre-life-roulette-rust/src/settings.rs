@@ -0,0 +1,71 @@
+use chrono::Duration;
+use rusqlite::{Connection, Result, Row};
+use crate::errors::ArreResult;
+use crate::utils::ArreDateTime;
+
+pub fn settings_update(conn: &Connection, settings: &Settings) -> ArreResult<()> {
+    conn.execute("
+        UPDATE settings
+        SET updated_date = ?1, idle_timeout = ?2
+        WHERE settings_id = 1
+    ", (ArreDateTime::now(), settings.idle_timeout.map(|it| it.num_seconds())),
+    )?;
+    Ok(())
+}
+
+pub fn settings_get(conn: &Connection) -> ArreResult<Settings> {
+    let mut stmt = conn.prepare("
+        SELECT
+         idle_timeout
+        FROM settings
+        WHERE settings_id = 1
+    ")?;
+    Ok(stmt.query_row([], |row| {
+        Settings::from_row(row)
+    })?)
+}
+
+/// Application wide preferences, the table holds a single row
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Settings {
+    pub idle_timeout: Option<Duration>, // inactivity after which a work session is paused, None never pauses
+}
+
+impl Settings {
+    pub fn from_row(row: &Row) -> Result<Settings> {
+        Ok(Settings {
+            idle_timeout: row.get::<_, Option<i64>>(0)?.map(Duration::seconds),
+        })
+    }
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            idle_timeout: Some(Duration::hours(1)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+    use rusqlite::Connection;
+    use crate::test_fixtures::conn;
+    use super::*;
+
+    #[rstest]
+    fn settings_start_with_defaults(conn: Connection) -> ArreResult<()> {
+        assert_eq!(settings_get(&conn)?, Settings::default());
+        Ok(())
+    }
+
+    #[rstest]
+    #[case(None)]
+    #[case(Some(Duration::minutes(5)))]
+    fn update_settings(conn: Connection, #[case] idle_timeout: Option<Duration>) -> ArreResult<()> {
+        settings_update(&conn, &Settings { idle_timeout })?;
+        assert_eq!(settings_get(&conn)?.idle_timeout, idle_timeout);
+        Ok(())
+    }
+}
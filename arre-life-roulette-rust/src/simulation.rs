@@ -0,0 +1,154 @@
+use std::cmp::Reverse;
+use rand::Rng;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use crate::errors::{ArreError, ArreResult};
+use crate::roll::roll_draw;
+
+pub const SIMULATION_ROLLS: usize = 10_000;
+// Enough for a preview that is stable to about one percent
+const PREVIEW_ROLLS: usize = 2_000;
+
+/// Exact probability of each candidate being drawn first, i.e. its share of the total weight
+pub fn roll_probabilities<T>(candidates: &[(T, u32)]) -> ArreResult<Vec<f64>> {
+    let total_weight = candidates.iter().map(|(_, weight)| *weight as f64).sum::<f64>();
+    if total_weight == 0. {
+        return Err(ArreError::ItemsSelectionIsEmpty().into());
+    }
+    Ok(candidates.iter().map(|(_, weight)| *weight as f64 / total_weight).collect())
+}
+
+/// Probability of each candidate being part of a roll of `count` items.
+/// Exact when it is known in closed form, otherwise estimated with a seeded simulation so that it is stable between calls.
+pub fn roll_inclusion_probabilities<T: Clone>(candidates: &[(T, u32)], count: usize) -> ArreResult<Vec<f64>> {
+    let drawable_nb = candidates.iter().filter(|(_, weight)| *weight > 0).count();
+    if count == 1 {
+        return roll_probabilities(candidates);
+    }
+    if count >= drawable_nb {
+        return Ok(candidates.iter().map(|(_, weight)| if *weight > 0 { 1. } else { 0. }).collect());
+    }
+    let mut rng = StdRng::seed_from_u64(0);
+    let report = simulate_rolls(&mut rng, candidates, count, PREVIEW_ROLLS)?;
+    Ok(report.entries.iter().map(|entry| entry.drawn_share(report.rolls)).collect())
+}
+
+/// Draw `rolls` times from the same candidates with the roll engine and count the outcomes
+pub fn simulate_rolls<T: Clone>(
+    rng: &mut impl Rng,
+    candidates: &[(T, u32)],
+    count: usize,
+    rolls: usize,
+) -> ArreResult<SimulationReport<T>> {
+    let expected = roll_probabilities(candidates)?;
+    let mut entries = candidates
+        .iter()
+        .zip(expected)
+        .map(|((candidate, _), expected)| SimulationEntry {
+            candidate: candidate.clone(),
+            expected,
+            first_count: 0,
+            drawn_count: 0,
+        })
+        .collect::<Vec<_>>();
+    // Draw indices, so candidates do not need to be comparable
+    let indexed = candidates
+        .iter()
+        .enumerate()
+        .map(|(idx, (_, weight))| (idx, *weight))
+        .collect::<Vec<_>>();
+    for _ in 0..rolls {
+        let drawn = roll_draw(rng, &indexed, count)?;
+        entries[drawn[0]].first_count += 1;
+        drawn.into_iter().for_each(|idx| entries[idx].drawn_count += 1);
+    }
+    Ok(SimulationReport { rolls, count, entries })
+}
+
+#[derive(Debug, Clone)]
+pub struct SimulationEntry<T> {
+    pub candidate: T,
+    pub expected: f64, // exact probability of being drawn first
+    pub first_count: usize,
+    pub drawn_count: usize,
+}
+
+impl<T> SimulationEntry<T> {
+    pub fn first_share(&self, rolls: usize) -> f64 {
+        self.first_count as f64 / rolls as f64
+    }
+
+    pub fn drawn_share(&self, rolls: usize) -> f64 {
+        self.drawn_count as f64 / rolls as f64
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SimulationReport<T> {
+    pub rolls: usize,
+    pub count: usize, // items drawn per roll
+    pub entries: Vec<SimulationEntry<T>>, // in the order of the candidates
+}
+
+impl<T> SimulationReport<T> {
+    /// Human readable distribution, most frequently drawn candidates first
+    pub fn format(&self, name: impl Fn(&T) -> String) -> String {
+        let mut entries = self.entries.iter().collect::<Vec<_>>();
+        entries.sort_by_key(|entry| Reverse(entry.drawn_count));
+        let mut lines = vec![format!("{} simulated rolls of {} item(s)", self.rolls, self.count)];
+        lines.extend(entries.into_iter().map(|entry| format!(
+            "{}: first {:.1}% (expected {:.1}%), drawn {:.1}%",
+            name(&entry.candidate),
+            entry.first_share(self.rolls) * 100.,
+            entry.expected * 100.,
+            entry.drawn_share(self.rolls) * 100.,
+        )));
+        lines.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+    use super::*;
+
+    #[rstest]
+    fn probabilities_follow_weights() -> ArreResult<()> {
+        let probabilities = roll_probabilities(&[("a", 1), ("b", 3), ("c", 0)])?;
+        assert_eq!(probabilities, vec![0.25, 0.75, 0.]);
+        assert!(roll_probabilities(&[("a", 0)]).is_err(), "Zero total weight should fail");
+        Ok(())
+    }
+
+    #[rstest]
+    fn simulation_matches_probabilities() -> ArreResult<()> {
+        let mut rng = StdRng::seed_from_u64(7);
+        let candidates = [("a", 1), ("b", 3), ("c", 0)];
+        let report = simulate_rolls(&mut rng, &candidates, 1, SIMULATION_ROLLS)?;
+        assert_eq!(report.entries.iter().map(|entry| entry.first_count).sum::<usize>(), SIMULATION_ROLLS);
+        for entry in report.entries.iter() {
+            assert!(
+                (entry.first_share(report.rolls) - entry.expected).abs() < 0.02,
+                "{} drawn {} times, expected {}", entry.candidate, entry.first_count, entry.expected,
+            );
+        }
+        assert!(report.format(|name| name.to_string()).starts_with("10000 simulated rolls"));
+        Ok(())
+    }
+
+    #[rstest]
+    #[case(vec![("a", 1), ("b", 1)], 2, vec![1., 1.])]
+    #[case(vec![("a", 1), ("b", 1), ("c", 0)], 5, vec![1., 1., 0.])]
+    #[case(vec![("a", 1), ("b", 1), ("c", 1), ("d", 1)], 2, vec![0.5, 0.5, 0.5, 0.5])]
+    fn inclusion_probabilities(
+        #[case] candidates: Vec<(&str, u32)>,
+        #[case] count: usize,
+        #[case] expected: Vec<f64>,
+    ) -> ArreResult<()> {
+        let probabilities = roll_inclusion_probabilities(&candidates, count)?;
+        for (probability, expected) in probabilities.into_iter().zip(expected) {
+            assert!((probability - expected).abs() < 0.05, "{} instead of {}", probability, expected);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,256 @@
+use chrono::{DateTime, Duration, Local, Months, NaiveDate, Utc};
+use rusqlite::Connection;
+use crate::errors::ArreResult;
+use crate::item::{ItemFilter, ItemId};
+use crate::session::Session;
+use crate::utils::{ArreDateTime, local_day_start, month_start, week_start};
+
+/// Weeks covered by the activity calendar, the last one containing today
+pub const CALENDAR_WEEKS: i64 = 53;
+
+/// Time spent on the item per bucket of the range, oldest first
+pub fn item_time_series_get(
+    conn: &Connection,
+    item_id: ItemId,
+    range: TimeRange,
+    today: NaiveDate,
+) -> ArreResult<Vec<TimeBucket>> {
+    let starts = range.bucket_starts(today);
+    let mut stmt = conn.prepare("
+        SELECT start_date, duration
+        FROM sessions
+        WHERE item_id = ?1 AND is_abandoned = 0 AND start_date >= ?2
+    ")?;
+    let sessions = stmt.query_map((item_id, ArreDateTime::new(local_day_start(starts[0]))), |row| {
+        Ok((*row.get::<_, ArreDateTime<Utc>>(0)?, Duration::seconds(row.get(1)?)))
+    })?.collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(time_buckets(range.bucket_size(), &starts, sessions))
+}
+
+/// Time spent per day on the items of the filter, from the Monday `CALENDAR_WEEKS` weeks ago up to `today`
+pub fn activity_calendar_get(conn: &Connection, filter: &ItemFilter, today: NaiveDate) -> ArreResult<Vec<TimeBucket>> {
+    let first = week_start(today) - Duration::weeks(CALENDAR_WEEKS - 1);
+    let starts = first.iter_days().take_while(|day| *day <= today).collect::<Vec<_>>();
+    let mut stmt = conn.prepare("
+        SELECT start_date, duration
+        FROM sessions
+        WHERE is_abandoned = 0 AND start_date >= ?1
+         AND (?2 IS NULL OR item_id IN (SELECT item_id FROM item_list_map WHERE list_id = ?2))
+         AND (?3 IS NULL OR item_id IN (SELECT item_id FROM item_tag_map WHERE tag_id = ?3))
+    ")?;
+    let sessions = stmt.query_map((ArreDateTime::new(local_day_start(first)), filter.list_id, filter.tag_id), |row| {
+        Ok((*row.get::<_, ArreDateTime<Utc>>(0)?, Duration::seconds(row.get(1)?)))
+    })?.collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(time_buckets(BucketSize::Day, &starts, sessions))
+}
+
+/// Sessions on the items of the filter started on the local date, the oldest first
+pub fn day_sessions_get(conn: &Connection, filter: &ItemFilter, date: NaiveDate) -> ArreResult<Vec<DaySession>> {
+    let next_date = date.succ_opt().unwrap_or(date);
+    let mut stmt = conn.prepare("
+        SELECT
+         s.session_id, s.item_id, s.list_id, s.start_date, s.end_date, s.duration,
+         s.planned_duration, s.overtime, s.pomodoros, s.is_abandoned, s.abandon_reason,
+         s.notes, s.satisfaction, s.difficulty, s.progress, s.is_item_finished,
+         i.name
+        FROM sessions s
+        JOIN items i ON s.item_id = i.item_id
+        WHERE s.is_abandoned = 0 AND s.start_date >= ?1 AND s.start_date < ?2
+         AND (?3 IS NULL OR s.item_id IN (SELECT item_id FROM item_list_map WHERE list_id = ?3))
+         AND (?4 IS NULL OR s.item_id IN (SELECT item_id FROM item_tag_map WHERE tag_id = ?4))
+        ORDER BY s.start_date
+    ")?;
+    let params = (
+        ArreDateTime::new(local_day_start(date)),
+        ArreDateTime::new(local_day_start(next_date)),
+        filter.list_id,
+        filter.tag_id,
+    );
+    let result = stmt.query_map(params, |row| {
+        Ok(DaySession { session: Session::from_row(row)?, item_name: row.get(16)? })
+    })?.collect::<rusqlite::Result<_>>()?;
+    Ok(result)
+}
+
+/// Sum the sessions into the buckets starting at `starts`, by the local date they started on.
+/// Sessions out of the buckets are ignored.
+pub fn time_buckets(
+    size: BucketSize,
+    starts: &[NaiveDate],
+    sessions: impl IntoIterator<Item=(DateTime<Utc>, Duration)>,
+) -> Vec<TimeBucket> {
+    let mut buckets = starts
+        .iter()
+        .map(|start| TimeBucket { start: *start, time_spent: Duration::zero(), sessions_count: 0 })
+        .collect::<Vec<_>>();
+    for (start, duration) in sessions {
+        let bucket_start = size.bucket_start(start.with_timezone(&Local).date_naive());
+        if let Some(bucket) = buckets.iter_mut().find(|bucket| bucket.start == bucket_start) {
+            bucket.time_spent += duration;
+            bucket.sessions_count += 1;
+        }
+    }
+    buckets
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum TimeRange {
+    Last30Days,
+    Last12Weeks,
+    LastYear,
+}
+
+impl TimeRange {
+    pub fn bucket_size(&self) -> BucketSize {
+        match self {
+            TimeRange::Last30Days => BucketSize::Day,
+            TimeRange::Last12Weeks => BucketSize::Week,
+            TimeRange::LastYear => BucketSize::Month,
+        }
+    }
+
+    /// Starts of the buckets covering the range, oldest first, the last one containing `today`
+    pub fn bucket_starts(&self, today: NaiveDate) -> Vec<NaiveDate> {
+        let current = self.bucket_size().bucket_start(today);
+        let mut starts = match self {
+            TimeRange::Last30Days => (0..30).map(|days| current - Duration::days(days)).collect::<Vec<_>>(),
+            TimeRange::Last12Weeks => (0..12).map(|weeks| current - Duration::weeks(weeks)).collect(),
+            TimeRange::LastYear => (0..12).filter_map(|months| current.checked_sub_months(Months::new(months))).collect(),
+        };
+        starts.reverse();
+        starts
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum BucketSize {
+    Day,
+    Week, // starting on Monday
+    Month,
+}
+
+impl BucketSize {
+    pub fn bucket_start(&self, date: NaiveDate) -> NaiveDate {
+        match self {
+            BucketSize::Day => date,
+            BucketSize::Week => week_start(date),
+            BucketSize::Month => month_start(date),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct TimeBucket {
+    pub start: NaiveDate,
+    pub time_spent: Duration,
+    pub sessions_count: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct DaySession {
+    pub session: Session,
+    pub item_name: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Datelike, Weekday};
+    use rstest::*;
+    use rusqlite::Connection;
+    use crate::item::item_create;
+    use crate::list::list_items_add;
+    use crate::test_fixtures::TestFactory;
+    use crate::session::{Session, session_persist};
+    use crate::test_fixtures::conn;
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[rstest]
+    #[case::days(TimeRange::Last30Days, 30, date(2023, 5, 16))]
+    #[case::weeks(TimeRange::Last12Weeks, 12, date(2023, 3, 27))]
+    #[case::months(TimeRange::LastYear, 12, date(2022, 7, 1))]
+    fn ranges_end_with_the_current_bucket(#[case] range: TimeRange, #[case] count: usize, #[case] first: NaiveDate) {
+        // Wednesday
+        let today = date(2023, 6, 14);
+        let starts = range.bucket_starts(today);
+        assert_eq!(starts.len(), count);
+        assert_eq!(starts[0], first);
+        assert_eq!(*starts.last().unwrap(), range.bucket_size().bucket_start(today));
+    }
+
+    #[rstest]
+    fn sessions_are_summed_per_bucket() {
+        let starts = TimeRange::LastYear.bucket_starts(date(2023, 6, 14));
+        let at = |date: NaiveDate| local_day_start(date) + Duration::hours(12);
+        let buckets = time_buckets(BucketSize::Month, &starts, [
+            (at(date(2023, 6, 1)), Duration::minutes(30)),
+            (at(date(2023, 6, 13)), Duration::minutes(45)),
+            (at(date(2023, 5, 31)), Duration::minutes(10)),
+            // Too old
+            (at(date(2022, 6, 30)), Duration::minutes(60)),
+        ]);
+        assert_eq!(buckets.len(), 12);
+        assert_eq!(buckets[11], TimeBucket { start: date(2023, 6, 1), time_spent: Duration::minutes(75), sessions_count: 2 });
+        assert_eq!(buckets[10].time_spent, Duration::minutes(10));
+        assert!(buckets[..10].iter().all(|bucket| bucket.sessions_count == 0));
+    }
+
+    #[rstest]
+    fn item_time_series_skips_abandoned_sessions(conn: Connection) -> ArreResult<()> {
+        let item_id = item_create(&conn, "Name", "Description")?.get_id()?;
+        let today = Local::now().date_naive();
+        let start = local_day_start(today) + Duration::minutes(1);
+        session_persist(&conn, &mut Session::new(item_id, None, start, start + Duration::minutes(20)))?;
+        let mut abandoned = Session::new(item_id, None, start, start + Duration::minutes(5));
+        abandoned.is_abandoned = true;
+        session_persist(&conn, &mut abandoned)?;
+
+        let buckets = item_time_series_get(&conn, item_id, TimeRange::Last30Days, today)?;
+        assert_eq!(buckets.last().unwrap().time_spent, Duration::minutes(20));
+        assert_eq!(buckets.iter().map(|bucket| bucket.sessions_count).sum::<usize>(), 1);
+        Ok(())
+    }
+
+    #[rstest]
+    fn activity_calendar_covers_whole_weeks_up_to_today(conn: Connection) -> ArreResult<()> {
+        let mut tf = TestFactory::new(&conn);
+        let items = tf.create_items(2)?;
+        let list_id = tf.create_lists(1)?[0].get_id()?;
+        list_items_add(&conn, list_id, [items[0].get_id()?].iter())?;
+        let today = Local::now().date_naive();
+        let start = local_day_start(today) + Duration::minutes(1);
+        session_persist(&conn, &mut Session::new(items[0].get_id()?, None, start, start + Duration::minutes(20)))?;
+        session_persist(&conn, &mut Session::new(items[1].get_id()?, None, start, start + Duration::minutes(30)))?;
+
+        let days = activity_calendar_get(&conn, &ItemFilter::default(), today)?;
+        assert_eq!(days[0].start.weekday(), Weekday::Mon);
+        assert_eq!(days.len() as i64, (CALENDAR_WEEKS - 1) * 7 + today.weekday().num_days_from_monday() as i64 + 1);
+        assert_eq!(days.last().unwrap().start, today);
+        assert_eq!(days.last().unwrap().time_spent, Duration::minutes(50));
+
+        let filter = ItemFilter { list_id: Some(list_id), tag_id: None };
+        let days = activity_calendar_get(&conn, &filter, today)?;
+        assert_eq!(days.last().unwrap().time_spent, Duration::minutes(20));
+        Ok(())
+    }
+
+    #[rstest]
+    fn day_sessions_are_those_started_on_the_day(conn: Connection) -> ArreResult<()> {
+        let item_id = item_create(&conn, "Name", "Description")?.get_id()?;
+        let day = date(2023, 6, 14);
+        let at = |date: NaiveDate, hour: i64| local_day_start(date) + Duration::hours(hour);
+        session_persist(&conn, &mut Session::new(item_id, None, at(day, 18), at(day, 19)))?;
+        session_persist(&conn, &mut Session::new(item_id, None, at(day, 9), at(day, 10)))?;
+        session_persist(&conn, &mut Session::new(item_id, None, at(date(2023, 6, 13), 23), at(day, 1)))?;
+        session_persist(&conn, &mut Session::new(item_id, None, at(date(2023, 6, 15), 0), at(date(2023, 6, 15), 1)))?;
+
+        let sessions = day_sessions_get(&conn, &ItemFilter::default(), day)?;
+        assert_eq!(sessions.len(), 2);
+        assert_eq!(*sessions[0].session.start, at(day, 9));
+        assert_eq!(sessions[0].item_name, "Name");
+        Ok(())
+    }
+}
@@ -3,7 +3,7 @@ use std::hash::{Hash, Hasher};
 use std::marker::PhantomData;
 use std::ops::Deref;
 use std::str::FromStr;
-use chrono::{DateTime, TimeZone, Utc};
+use chrono::{Datelike, DateTime, Duration, Local, NaiveDate, NaiveTime, TimeZone, Utc};
 use rusqlite::types::{FromSql, FromSqlResult, ToSql};
 
 #[derive(Debug, Eq, PartialEq)]
@@ -124,4 +124,59 @@ pub fn format_duration(duration: chrono::Duration) -> String {
     let minutes = (duration / 60) % 60;
     let hours = (duration / 60) / 60;
     format!("{:0>2}h {:0>2}m {:0>2}s", hours, minutes, seconds)
-}
\ No newline at end of file
+}
+
+/// Monday of the week containing `date`
+pub fn week_start(date: NaiveDate) -> NaiveDate {
+    date - Duration::days(date.weekday().num_days_from_monday() as i64)
+}
+
+/// First day of the month containing `date`
+pub fn month_start(date: NaiveDate) -> NaiveDate {
+    date - Duration::days(date.day0() as i64)
+}
+
+/// Moment the local day starts at, which every day, week and month period is bounded by.
+/// Midnight may be repeated on daylight saving time changes, the first one is used then. When it is skipped,
+/// the day starts once the clocks went forward.
+pub fn local_day_start(date: NaiveDate) -> DateTime<Utc> {
+    let midnight = date.and_time(NaiveTime::MIN);
+    Local.from_local_datetime(&midnight)
+        .earliest()
+        .or_else(|| Local.from_local_datetime(&(midnight + Duration::hours(1))).earliest())
+        .map(|date_time| date_time.with_timezone(&Utc))
+        .unwrap_or(Utc.from_utc_datetime(&midnight))
+}
+
+/// Moment the local week containing `date` starts at, weeks starting on Monday
+pub fn local_week_start(date: NaiveDate) -> DateTime<Utc> {
+    local_day_start(week_start(date))
+}
+
+/// Moment the local month containing `date` starts at
+pub fn local_month_start(date: NaiveDate) -> DateTime<Utc> {
+    local_day_start(month_start(date))
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+    use super::*;
+
+    #[rstest]
+    #[case::monday(NaiveDate::from_ymd_opt(2023, 6, 12).unwrap(), NaiveDate::from_ymd_opt(2023, 6, 12).unwrap(), NaiveDate::from_ymd_opt(2023, 6, 1).unwrap())]
+    #[case::sunday(NaiveDate::from_ymd_opt(2023, 6, 18).unwrap(), NaiveDate::from_ymd_opt(2023, 6, 12).unwrap(), NaiveDate::from_ymd_opt(2023, 6, 1).unwrap())]
+    #[case::across_months(NaiveDate::from_ymd_opt(2023, 3, 1).unwrap(), NaiveDate::from_ymd_opt(2023, 2, 27).unwrap(), NaiveDate::from_ymd_opt(2023, 3, 1).unwrap())]
+    fn periods_start_on_monday_and_first_of_month(
+        #[case] date: NaiveDate,
+        #[case] expected_week_start: NaiveDate,
+        #[case] expected_month_start: NaiveDate,
+    ) {
+        assert_eq!(week_start(date), expected_week_start);
+        assert_eq!(month_start(date), expected_month_start);
+        let local_start = local_week_start(date).with_timezone(&Local);
+        assert_eq!((local_start.date_naive(), local_start.time()), (expected_week_start, NaiveTime::MIN));
+        let local_start = local_month_start(date).with_timezone(&Local);
+        assert_eq!((local_start.date_naive(), local_start.time()), (expected_month_start, NaiveTime::MIN));
+    }
+}